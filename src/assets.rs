@@ -5,6 +5,8 @@ use bevy::reflect::{TypePath, TypeUuid};
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::AudioSource;
 
+use crate::misc::Balance;
+
 #[derive(AssetCollection, Resource)]
 pub struct GameAssets {
     // PLAYER
@@ -35,6 +37,10 @@ pub struct GameAssets {
     // MAP
     #[asset(path = "map/background.png")]
     pub background: Handle<Image>,
+    #[asset(path = "map/grass-background.png")]
+    pub grass_background: Handle<Image>,
+    #[asset(path = "map/old-background.png")]
+    pub old_background: Handle<Image>,
     #[asset(path = "map/walls/wall-1-1.png")]
     pub wall_1_1: Handle<Image>,
     #[asset(path = "map/walls/wall-2-2.png")]
@@ -97,6 +103,8 @@ pub struct GameAssets {
     // MISC
     #[asset(path = "turn-credentials.toml")]
     pub turn_credentials: Handle<TurnCredentials>,
+    #[asset(path = "default.balance.toml")]
+    pub balance: Handle<Balance>,
 }
 
 #[derive(Clone, Deserialize, TypeUuid, TypePath, Default)]
@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use bevy_ggrs::AddRollbackCommandExtension;
 
 use super::FadedLoopSound;
+use crate::world::map::theme::MapTheme;
 use crate::world::{Score, MAX_SCORE};
 use crate::GameAssets;
 
@@ -14,6 +15,7 @@ pub fn check_bgm_stage(
     mut commands: Commands,
     assets: Res<GameAssets>,
     score: Res<Score>,
+    theme: Res<MapTheme>,
     mut query: Query<(&mut FadedLoopSound, &BgmStage)>,
 ) {
     let match_point = score.p1 == MAX_SCORE - 1 || score.p2 == MAX_SCORE - 1;
@@ -35,6 +37,7 @@ pub fn check_bgm_stage(
                     clip,
                     volume: 0.075,
                     despawn_on_silence: true,
+                    playback_rate: theme.music_playback_rate(),
                     ..default()
                 },
             ))
@@ -48,6 +51,11 @@ pub fn check_bgm_stage(
     }
     let (mut sound, bgm_stage) = query.single_mut();
 
+    // The theme of the new map might play the music at a different speed
+    if sound.playback_rate != theme.music_playback_rate() {
+        sound.playback_rate = theme.music_playback_rate();
+    }
+
     // We are in a normal round and the correct BGM is already playing
     if !match_point && bgm_stage.stage == 0 {
         return;
@@ -65,6 +73,7 @@ pub fn check_bgm_stage(
                 clip,
                 volume: 0.1,
                 despawn_on_silence: true,
+                playback_rate: theme.music_playback_rate(),
                 ..default()
             },
         ))
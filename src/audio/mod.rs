@@ -11,6 +11,7 @@ use bevy_ggrs::GgrsSchedule;
 use bevy_kira_audio::prelude::{AudioPlugin, AudioSource, *};
 
 use crate::network::ggrs_config::GGRS_FPS;
+use crate::world::map;
 use crate::{GameState, RollbackState};
 
 const MAIN_VOLUME: f64 = 0.35;
@@ -60,6 +61,8 @@ pub struct FadedLoopSound {
     pub should_play: bool,
     pub despawn_on_silence: bool,
     pub volume: f64,
+    /// can be changed while the sound is playing
+    pub playback_rate: f64,
 }
 
 impl Default for FadedLoopSound {
@@ -72,6 +75,7 @@ impl Default for FadedLoopSound {
             should_play: true,
             despawn_on_silence: false,
             volume: 1.0,
+            playback_rate: 1.0,
         }
     }
 }
@@ -176,15 +180,26 @@ fn update_looped_sounds(
 ) {
     for mut sound in sounds.iter_mut() {
         if sound.should_play {
-            if sound.audio_instance.is_none() {
-                sound.audio_instance = Some(
-                    audio
-                        .play(sound.clip.clone())
-                        .looped()
-                        .linear_fade_in(Duration::from_secs_f32(sound.fade_in))
-                        .with_volume(sound.volume)
-                        .handle(),
-                );
+            match &sound.audio_instance {
+                None => {
+                    sound.audio_instance = Some(
+                        audio
+                            .play(sound.clip.clone())
+                            .looped()
+                            .linear_fade_in(Duration::from_secs_f32(sound.fade_in))
+                            .with_volume(sound.volume)
+                            .with_playback_rate(sound.playback_rate)
+                            .handle(),
+                    );
+                }
+                Some(instance_handle) => {
+                    if !sound.is_changed() {
+                        continue;
+                    }
+                    if let Some(instance) = audio_instances.get_mut(instance_handle) {
+                        instance.set_playback_rate(sound.playback_rate, AudioTween::default());
+                    }
+                }
             }
         } else if let Some(instance_handle) = sound.audio_instance.take() {
             if let Some(instance) = audio_instances.get_mut(&instance_handle) {
@@ -220,7 +235,10 @@ impl Plugin for GameAudioPlugin {
                 (remove_finished_sounds, remove_looped_sounds)
                     .run_if(in_state(GameState::MainMenu)),
             )
-            .add_systems(OnEnter(RollbackState::RoundStart), bgm::check_bgm_stage)
+            .add_systems(
+                OnEnter(RollbackState::RoundStart),
+                bgm::check_bgm_stage.after(map::spawn_random_map),
+            )
             .add_systems(
                 OnEnter(RollbackState::GameOver),
                 bgm::fade_out_game_over_bgm,
@@ -5,9 +5,18 @@ use bevy::window::{PrimaryWindow, WindowMode};
 use bevy::{prelude::*, render::camera::ScalingMode};
 use noisy_bevy::simplex_noise_2d_seeded;
 
+use crate::player::Player;
+use crate::world::map::border::Arena;
 use crate::GameState;
 
-const CAMERA_POSITION: Vec3 = Vec3::new(0.0, 50.0, 0.0);
+// Leaves some space above the arena for the scoreboard
+const CAMERA_OFFSET: Vec3 = Vec3::new(0.0, 50.0, 0.0);
+const MIN_VIEW_HEIGHT: f32 = 1100.0;
+const MAX_VIEW_HEIGHT: f32 = 2000.0;
+// Space around the planes that should always be visible
+const FRAMING_PADDING: Vec2 = Vec2::new(400.0, 300.0);
+// How fast the camera catches up with the planes, higher is faster
+const FRAMING_SPEED: f32 = 3.0;
 const NOISE_STRENGTH: f32 = 10.0;
 const TRANSLATION_SHAKE_STRENGTH: f32 = 25.0;
 const ROTATION_SHAKE_STRENGTH: f32 = 1.0;
@@ -21,7 +30,9 @@ impl Plugin for AceCameraPlugin {
             (
                 toggle_full_screen,
                 take_screenshot,
-                camera_shake.run_if(in_state(GameState::InRollbackGame)),
+                (frame_players, camera_shake)
+                    .chain()
+                    .run_if(in_state(GameState::InRollbackGame)),
             ),
         )
         .init_resource::<CameraShake>()
@@ -29,6 +40,7 @@ impl Plugin for AceCameraPlugin {
     }
 }
 
+/// Not part of the checksum, the seed comes from the wall clock.
 #[derive(Resource, Default, Reflect)]
 pub struct CameraShake {
     trauma: f32,
@@ -62,19 +74,111 @@ impl CameraShake {
     }
 }
 
+/// Where the camera would be without any camera shake.
+#[derive(Component)]
+pub struct CameraFraming {
+    position: Vec3,
+    view_height: f32,
+}
+
+impl Default for CameraFraming {
+    fn default() -> Self {
+        Self {
+            position: CAMERA_OFFSET,
+            view_height: MIN_VIEW_HEIGHT,
+        }
+    }
+}
+
+/// How much bigger the world looks compared to the default zoom.
+/// Use this to keep HUD elements that live in the world at a constant size on screen.
+pub fn view_scale(projection: &OrthographicProjection) -> f32 {
+    projection.area.height() / MIN_VIEW_HEIGHT
+}
+
+/// Position on the edge of the visible area (with the given margin) in the direction of the target.
+pub fn edge_of_view(camera_position: Vec2, area: Rect, target: Vec2, margin: f32) -> Vec2 {
+    let half_size = (area.half_size() - Vec2::splat(margin)).max(Vec2::ONE);
+    let dir = target - camera_position;
+    if dir == Vec2::ZERO {
+        return camera_position;
+    }
+
+    let scale_x = if dir.x != 0.0 {
+        half_size.x / dir.x.abs()
+    } else {
+        f32::MAX
+    };
+    let scale_y = if dir.y != 0.0 {
+        half_size.y / dir.y.abs()
+    } else {
+        f32::MAX
+    };
+    camera_position + dir * scale_x.min(scale_y)
+}
+
 fn spawn_camera(mut commands: Commands) {
     let mut camera = Camera2dBundle::default();
-    camera.projection.scaling_mode = ScalingMode::FixedVertical(1100.0);
-    camera.transform.translation = CAMERA_POSITION;
-    commands.spawn(camera);
+    camera.projection.scaling_mode = ScalingMode::FixedVertical(MIN_VIEW_HEIGHT);
+    camera.transform.translation = CAMERA_OFFSET;
+    commands.spawn((camera, CameraFraming::default()));
+}
+
+/// Center the view on the planes, it can't go past the arena borders
+/// unless the arena is smaller than the view.
+fn clamp_to_arena(center: f32, view_half_size: f32, arena_half_size: f32) -> f32 {
+    if arena_half_size <= view_half_size {
+        return 0.0;
+    }
+    center.clamp(
+        -arena_half_size + view_half_size,
+        arena_half_size - view_half_size,
+    )
+}
+
+fn frame_players(
+    arena: Res<Arena>,
+    time: Res<Time>,
+    players: Query<&Transform, (With<Player>, Without<Camera2d>)>,
+    mut camera: Query<(&mut CameraFraming, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    let (mut framing, mut projection) = camera.single_mut();
+
+    let mut positions = players
+        .iter()
+        .map(|transform| transform.translation.truncate());
+    let Some(first_position) = positions.next() else {
+        return;
+    };
+    let (min, max) = positions.fold((first_position, first_position), |(min, max), pos| {
+        (min.min(pos), max.max(pos))
+    });
+    let min = min - FRAMING_PADDING;
+    let max = max + FRAMING_PADDING;
+
+    let aspect_ratio = projection.area.width() / projection.area.height();
+    let view_height = ((max.y - min.y).max((max.x - min.x) / aspect_ratio))
+        .clamp(MIN_VIEW_HEIGHT, MAX_VIEW_HEIGHT);
+    let view_half_size = Vec2::new(view_height * aspect_ratio, view_height) / 2.0;
+    let center = (min + max) / 2.0;
+    let position = Vec3::new(
+        clamp_to_arena(center.x, view_half_size.x, arena.half_size.x),
+        clamp_to_arena(center.y, view_half_size.y, arena.half_size.y),
+        0.0,
+    ) + CAMERA_OFFSET;
+
+    let t = 1.0 - (-FRAMING_SPEED * time.delta_seconds()).exp();
+    framing.position = framing.position.lerp(position, t);
+    framing.view_height += (view_height - framing.view_height) * t;
+    projection.scaling_mode = ScalingMode::FixedVertical(framing.view_height);
 }
 
 fn camera_shake(
-    mut camera: Query<&mut Transform, With<Camera2d>>,
+    mut camera: Query<(&mut Transform, &CameraFraming), With<Camera2d>>,
     mut shake: ResMut<CameraShake>,
     time: Res<Time>,
 ) {
-    let mut transform = camera.single_mut();
+    let (mut transform, framing) = camera.single_mut();
 
     let translation_offset = Vec3::new(shake.noise_value(0), shake.noise_value(1), 0.0)
         * shake.trauma.powi(2)
@@ -83,7 +187,7 @@ fn camera_shake(
         (shake.noise_value(2) * shake.trauma.powi(2) * ROTATION_SHAKE_STRENGTH).to_radians(),
     );
 
-    transform.translation = CAMERA_POSITION + translation_offset;
+    transform.translation = framing.position + translation_offset;
     transform.rotation = Quat::IDENTITY + rotation_offset;
 
     shake.reduce_trauma(time.delta_seconds());
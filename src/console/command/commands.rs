@@ -1,12 +1,39 @@
-use chrono::Utc;
-use clap::Parser;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
 
 use bevy::prelude::*;
 use bevy_console::{AddConsoleCommand, ConsoleCommand};
-use bevy_ggrs::ggrs::PlayerType;
 
-use super::{AceCommands, CommandQueue};
-use crate::network::{ggrs_config::PLAYER_COUNT, socket::AceSocket};
+use super::{issue_command, player_stats, AceCommands, CommandQueue};
+use crate::misc::snapshot::{self, PendingSnapshot, SaveSnapshot};
+use crate::misc::Balance;
+use crate::network::direct::DirectSocket;
+use crate::network::local::PendingLocalMatch;
+use crate::network::{
+    ggrs_config::PLAYER_COUNT, netsim::NetworkConditions, socket::AceSocket, DEFAULT_PORT,
+};
+use crate::player::{PersistentPlayerStats, PlayerStats};
+use crate::world::{MatchRules, RoundStats};
+use crate::GameState;
+
+/// Send the commands to our peer, they take effect once our peer accepts them.
+fn issue_commands<T>(
+    cmd: &mut ConsoleCommand<T>,
+    socket: &mut AceSocket,
+    command_queue: &mut CommandQueue,
+    round_stats: &RoundStats,
+    match_rules: &MatchRules,
+    commands: impl IntoIterator<Item = AceCommands>,
+) {
+    let issued = commands.into_iter().try_for_each(|command| {
+        issue_command(socket, command_queue, round_stats, match_rules, command)
+    });
+    match issued {
+        Ok(()) => cmd.reply_ok("waiting for your opponent to accept"),
+        Err(err) => cmd.reply_failed(err),
+    }
+}
 
 pub struct AceCommandPlugin;
 
@@ -15,7 +42,15 @@ impl Plugin for AceCommandPlugin {
         app.add_console_command::<Mayhem, _>(mayhem_command)
             .add_console_command::<Buff, _>(buff_command)
             .add_console_command::<Reset, _>(reset_command)
-            .add_console_command::<Nerf, _>(nerf_command);
+            .add_console_command::<Nerf, _>(nerf_command)
+            .add_console_command::<Set, _>(set_command)
+            .add_console_command::<Stats, _>(stats_command)
+            .add_console_command::<Preset, _>(preset_command)
+            .add_console_command::<Snapshot, _>(snapshot_command)
+            .add_console_command::<Netsim, _>(netsim_command)
+            .add_console_command::<Local, _>(local_command)
+            .add_console_command::<Host, _>(host_command)
+            .add_console_command::<Join, _>(join_command);
     }
 }
 
@@ -27,20 +62,19 @@ pub struct Mayhem;
 fn mayhem_command(
     mut cmd: ConsoleCommand<Mayhem>,
     mut socket: ResMut<AceSocket>,
-    mut command_queque: ResMut<CommandQueue>,
+    mut command_queue: ResMut<CommandQueue>,
+    round_stats: Res<RoundStats>,
+    match_rules: Res<MatchRules>,
 ) {
     if let Some(Ok(Mayhem)) = cmd.take() {
-        for p in socket.players() {
-            if let PlayerType::Remote(peer_id) = p {
-                let timestamp = Utc::now().timestamp_millis();
-                for i in 0..2 {
-                    let push_cmd = AceCommands::Buff(i, 9, timestamp);
-                    socket.send_tcp_message(peer_id, &push_cmd.to_string());
-                    command_queque.queue.push(push_cmd.clone());
-                }
-            };
-        }
-        cmd.ok();
+        issue_commands(
+            &mut cmd,
+            &mut socket,
+            &mut command_queue,
+            &round_stats,
+            &match_rules,
+            (0..PLAYER_COUNT).map(|i| AceCommands::Buff(i, 9)),
+        );
     }
 }
 
@@ -57,22 +91,23 @@ pub struct Buff {
 fn buff_command(
     mut cmd: ConsoleCommand<Buff>,
     mut socket: ResMut<AceSocket>,
-    mut command_queque: ResMut<CommandQueue>,
+    mut command_queue: ResMut<CommandQueue>,
+    round_stats: Res<RoundStats>,
+    match_rules: Res<MatchRules>,
 ) {
     if let Some(Ok(Buff { handle, level })) = cmd.take() {
         if handle >= PLAYER_COUNT || level >= 10 {
             cmd.failed();
             return;
         }
-        for p in socket.players() {
-            if let PlayerType::Remote(peer_id) = p {
-                let timestamp = Utc::now().timestamp_millis();
-                let push_cmd = AceCommands::Buff(handle, level, timestamp);
-                socket.send_tcp_message(peer_id, &push_cmd.to_string());
-                command_queque.queue.push(push_cmd.clone());
-            };
-        }
-        cmd.ok();
+        issue_commands(
+            &mut cmd,
+            &mut socket,
+            &mut command_queue,
+            &round_stats,
+            &match_rules,
+            [AceCommands::Buff(handle, level)],
+        );
     }
 }
 
@@ -84,20 +119,19 @@ pub struct Reset;
 fn reset_command(
     mut cmd: ConsoleCommand<Reset>,
     mut socket: ResMut<AceSocket>,
-    mut command_queque: ResMut<CommandQueue>,
+    mut command_queue: ResMut<CommandQueue>,
+    round_stats: Res<RoundStats>,
+    match_rules: Res<MatchRules>,
 ) {
     if let Some(Ok(Reset)) = cmd.take() {
-        for p in socket.players() {
-            if let PlayerType::Remote(peer_id) = p {
-                let timestamp = Utc::now().timestamp_millis();
-                for i in 0..2 {
-                    let push_cmd = AceCommands::Nerf(i, 0, timestamp);
-                    socket.send_tcp_message(peer_id, &push_cmd.to_string());
-                    command_queque.queue.push(push_cmd.clone());
-                }
-            };
-        }
-        cmd.ok();
+        issue_commands(
+            &mut cmd,
+            &mut socket,
+            &mut command_queue,
+            &round_stats,
+            &match_rules,
+            (0..PLAYER_COUNT).map(|i| AceCommands::Nerf(i, 0)),
+        );
     }
 }
 
@@ -114,21 +148,322 @@ pub struct Nerf {
 fn nerf_command(
     mut cmd: ConsoleCommand<Nerf>,
     mut socket: ResMut<AceSocket>,
-    mut command_queque: ResMut<CommandQueue>,
+    mut command_queue: ResMut<CommandQueue>,
+    round_stats: Res<RoundStats>,
+    match_rules: Res<MatchRules>,
 ) {
     if let Some(Ok(Nerf { handle, level })) = cmd.take() {
         if handle >= PLAYER_COUNT || level >= 10 {
             cmd.failed();
             return;
         }
-        for p in socket.players() {
-            if let PlayerType::Remote(peer_id) = p {
-                let timestamp = Utc::now().timestamp_millis();
-                let push_cmd = AceCommands::Nerf(handle, level, timestamp);
-                socket.send_tcp_message(peer_id, &push_cmd.to_string());
-                command_queque.queue.push(push_cmd.clone());
-            };
+        issue_commands(
+            &mut cmd,
+            &mut socket,
+            &mut command_queue,
+            &round_stats,
+            &match_rules,
+            [AceCommands::Nerf(handle, level)],
+        );
+    }
+}
+
+/// Set a single stat of one player
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "set")]
+pub struct Set {
+    /// The handle of the player from 0 to PLAYER_COUNT - 1
+    pub handle: usize,
+    /// The name of the stat, see `stats`
+    pub stat: String,
+    pub value: f32,
+}
+
+fn set_command(
+    mut cmd: ConsoleCommand<Set>,
+    mut socket: ResMut<AceSocket>,
+    mut command_queue: ResMut<CommandQueue>,
+    round_stats: Res<RoundStats>,
+    match_rules: Res<MatchRules>,
+    balance: Res<Balance>,
+) {
+    if let Some(Ok(Set {
+        handle,
+        stat,
+        value,
+    })) = cmd.take()
+    {
+        if handle >= PLAYER_COUNT {
+            cmd.failed();
+            return;
+        }
+        if let Err(err) =
+            player_stats::set_stat(&mut PlayerStats::default(), &stat, value, &balance)
+        {
+            cmd.reply_failed(err);
+            return;
+        }
+        issue_commands(
+            &mut cmd,
+            &mut socket,
+            &mut command_queue,
+            &round_stats,
+            &match_rules,
+            [AceCommands::Set(handle, stat, value)],
+        );
+    }
+}
+
+/// Show the stats of one player (without their perks)
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "stats")]
+pub struct Stats {
+    /// The handle of the player from 0 to PLAYER_COUNT - 1
+    pub handle: usize,
+}
+
+fn stats_command(mut cmd: ConsoleCommand<Stats>, stats: Res<PersistentPlayerStats>) {
+    if let Some(Ok(Stats { handle })) = cmd.take() {
+        if handle >= PLAYER_COUNT {
+            cmd.failed();
+            return;
+        }
+        let values = player_stats::stats_to_values(&stats.stats[handle]);
+        for (stat, value) in player_stats::STAT_NAMES.iter().zip(values) {
+            cmd.reply(format!("{}: {}", stat, value));
         }
         cmd.ok();
     }
 }
+
+#[derive(Subcommand)]
+pub enum PresetAction {
+    /// Store the current stats of all players
+    Save { name: String },
+    /// Apply the stored stats to all players
+    Load { name: String },
+}
+
+/// Save and load named stat sets
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "preset")]
+pub struct Preset {
+    #[command(subcommand)]
+    pub action: PresetAction,
+}
+
+fn preset_command(
+    mut cmd: ConsoleCommand<Preset>,
+    mut socket: ResMut<AceSocket>,
+    mut command_queue: ResMut<CommandQueue>,
+    stats: Res<PersistentPlayerStats>,
+    round_stats: Res<RoundStats>,
+    match_rules: Res<MatchRules>,
+    balance: Res<Balance>,
+) {
+    if let Some(Ok(Preset { action })) = cmd.take() {
+        match action {
+            PresetAction::Save { name } => match player_stats::save_preset(&name, &stats.stats) {
+                Ok(()) => cmd.reply_ok(format!("saved preset '{}'", name)),
+                Err(err) => cmd.reply_failed(err),
+            },
+            PresetAction::Load { name } => {
+                let preset_stats = match player_stats::load_preset(&name, &balance) {
+                    Ok(preset_stats) => preset_stats,
+                    Err(err) => {
+                        cmd.reply_failed(err);
+                        return;
+                    }
+                };
+                issue_commands(
+                    &mut cmd,
+                    &mut socket,
+                    &mut command_queue,
+                    &round_stats,
+                    &match_rules,
+                    [AceCommands::Preset(name, preset_stats)],
+                );
+            }
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotAction {
+    /// Write the rollback state of the current frame to a file
+    Save { name: String },
+    /// Resume from the stored state in a local session, only from the main menu
+    Load { name: String },
+}
+
+/// Save and load the rollback state to reproduce edge cases
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "snapshot")]
+pub struct Snapshot {
+    #[command(subcommand)]
+    pub action: SnapshotAction,
+}
+
+fn snapshot_command(
+    mut cmd: ConsoleCommand<Snapshot>,
+    mut commands: Commands,
+    mut ev_save_snapshot: EventWriter<SaveSnapshot>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    game_state: Res<State<GameState>>,
+    registry: Res<AppTypeRegistry>,
+) {
+    if let Some(Ok(Snapshot { action })) = cmd.take() {
+        match action {
+            SnapshotAction::Save { name } => {
+                if *game_state.get() != GameState::InRollbackGame {
+                    cmd.reply_failed("snapshots can only be saved during a match");
+                    return;
+                }
+                ev_save_snapshot.send(SaveSnapshot { name });
+                cmd.ok();
+            }
+            SnapshotAction::Load { name } => {
+                if *game_state.get() != GameState::MainMenu {
+                    cmd.reply_failed("snapshots can only be loaded from the main menu");
+                    return;
+                }
+                match snapshot::Snapshot::load(&name, &registry.read()) {
+                    Ok(snapshot) => {
+                        commands.insert_resource(PendingSnapshot(snapshot));
+                        next_game_state.set(GameState::Matchmaking);
+                        cmd.reply_ok(format!("loading snapshot '{}'", name));
+                    }
+                    Err(err) => cmd.reply_failed(err),
+                }
+            }
+        }
+    }
+}
+
+/// Simulate a bad network on the messages we receive, from the next match on
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "netsim")]
+pub struct Netsim {
+    /// One way latency in milliseconds
+    #[arg(long, default_value_t = 0)]
+    pub latency: u64,
+    /// Random extra latency in milliseconds, up to this much
+    #[arg(long, default_value_t = 0)]
+    pub jitter: u64,
+    /// Percentage of packets that get dropped
+    #[arg(long, default_value_t = 0.0)]
+    pub loss: f32,
+    /// Percentage of packets that arrive out of order
+    #[arg(long, default_value_t = 0.0)]
+    pub reorder: f32,
+}
+
+fn netsim_command(
+    mut cmd: ConsoleCommand<Netsim>,
+    mut network_conditions: ResMut<NetworkConditions>,
+) {
+    if let Some(Ok(Netsim {
+        latency,
+        jitter,
+        loss,
+        reorder,
+    })) = cmd.take()
+    {
+        if !(0.0..=100.0).contains(&loss) || !(0.0..=100.0).contains(&reorder) {
+            cmd.reply_failed("loss and reorder are percentages from 0 to 100");
+            return;
+        }
+        *network_conditions = NetworkConditions {
+            latency: Duration::from_millis(latency),
+            jitter: Duration::from_millis(jitter),
+            packet_loss: loss / 100.0,
+            reorder: reorder / 100.0,
+        };
+        cmd.reply_ok(format!(
+            "simulating {} from the next match on",
+            *network_conditions
+        ));
+    }
+}
+
+/// Play against an idle peer in the same process, over the network of `netsim`
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "local")]
+pub struct Local;
+
+fn local_command(
+    mut cmd: ConsoleCommand<Local>,
+    mut commands: Commands,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    game_state: Res<State<GameState>>,
+) {
+    if let Some(Ok(Local)) = cmd.take() {
+        if *game_state.get() != GameState::MainMenu {
+            cmd.reply_failed("local matches can only be started from the main menu");
+            return;
+        }
+        commands.insert_resource(PendingLocalMatch);
+        next_game_state.set(GameState::Matchmaking);
+        cmd.reply_ok("starting a local match");
+    }
+}
+
+/// Host a match on a UDP port, without the matchmaking server
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "host")]
+pub struct Host {
+    #[arg(long, default_value_t = DEFAULT_PORT)]
+    pub port: u16,
+}
+
+fn host_command(
+    mut cmd: ConsoleCommand<Host>,
+    mut commands: Commands,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    game_state: Res<State<GameState>>,
+) {
+    if let Some(Ok(Host { port })) = cmd.take() {
+        if *game_state.get() != GameState::MainMenu {
+            cmd.reply_failed("matches can only be hosted from the main menu");
+            return;
+        }
+        match DirectSocket::host(port) {
+            Ok(socket) => {
+                commands.insert_resource(AceSocket::from(socket));
+                next_game_state.set(GameState::Matchmaking);
+                cmd.reply_ok(format!("waiting for a player to join on port {}", port));
+            }
+            Err(err) => cmd.reply_failed(err),
+        }
+    }
+}
+
+/// Join a match that is hosted with `host`
+#[derive(Parser, ConsoleCommand)]
+#[command(name = "join")]
+pub struct Join {
+    /// The address of the host, ip:port
+    pub address: String,
+}
+
+fn join_command(
+    mut cmd: ConsoleCommand<Join>,
+    mut commands: Commands,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    game_state: Res<State<GameState>>,
+) {
+    if let Some(Ok(Join { address })) = cmd.take() {
+        if *game_state.get() != GameState::MainMenu {
+            cmd.reply_failed("matches can only be joined from the main menu");
+            return;
+        }
+        match DirectSocket::join(&address) {
+            Ok(socket) => {
+                commands.insert_resource(AceSocket::from(socket));
+                next_game_state.set(GameState::Matchmaking);
+                cmd.reply_ok(format!("joining {}", address));
+            }
+            Err(err) => cmd.reply_failed(err),
+        }
+    }
+}
@@ -1,4 +1,3 @@
-use chrono::Utc;
 pub use commands::AceCommandPlugin;
 
 mod commands;
@@ -7,121 +6,389 @@ mod player_stats;
 use std::fmt::Display;
 
 use bevy::prelude::*;
+use bevy_console::PrintConsoleLine;
 
-use super::CommandQueue;
-use crate::{network::socket::AceSocket, player::PersistentPlayerStats};
-
-// Milliseconds that must have past since the command was pushed to the queue
-// in order to get applied. This is to prevent desyncs.
-const MIN_TIME_THRESHOLD: i64 = 1500;
+use super::{CommandQueue, ConsentRequests};
+use crate::misc::Balance;
+use crate::network::desync::{send_to_remotes, ReliableMessage};
+use crate::network::{ggrs_config::PLAYER_COUNT, socket::AceSocket};
+use crate::player::{PersistentPlayerStats, PlayerStats};
+use crate::world::{MatchRules, RoundStats};
 
 #[derive(Clone)]
 pub enum AceCommands {
-    Buff(usize, usize, i64),
-    Nerf(usize, usize, i64),
+    Buff(usize, usize),
+    Nerf(usize, usize),
+    /// Set a single stat of one player
+    Set(usize, String, f32),
+    /// Replace the stats of all players with a named preset
+    Preset(String, [PlayerStats; PLAYER_COUNT]),
 }
 
 impl Display for AceCommands {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Buff(handle, level, timestamp) => {
-                write!(f, "buff {} {} {}", handle, level, timestamp)
-            }
-            Self::Nerf(handle, level, timestamp) => {
-                write!(f, "nerf {} {} {}", handle, level, timestamp)
+            Self::Buff(handle, level) => write!(f, "buff {} {}", handle, level),
+            Self::Nerf(handle, level) => write!(f, "nerf {} {}", handle, level),
+            Self::Set(handle, stat, value) => write!(f, "set {} {} {}", handle, stat, value),
+            Self::Preset(name, stats) => {
+                write!(f, "preset {}", name)?;
+                for value in stats.iter().flat_map(player_stats::stats_to_values) {
+                    write!(f, " {}", value)?;
+                }
+                Ok(())
             }
         }
     }
 }
 
 impl AceCommands {
-    pub fn from_str(s: &str) -> Option<AceCommands> {
-        let parts: Vec<&str> = s.split_whitespace().collect();
-
-        if parts.len() < 2 {
-            return None;
-        }
+    fn from_parts(parts: &[&str], balance: &Balance) -> Option<AceCommands> {
+        let parse_handle = |s: &str| s.parse().ok().filter(|&handle| handle < PLAYER_COUNT);
 
-        match parts[0] {
-            "buff" => {
-                if let Ok(handle) = parts[1].parse() {
-                    if let Ok(level) = parts[2].parse() {
-                        if let Ok(timestamp) = parts[3].parse() {
-                            return Some(AceCommands::Buff(handle, level, timestamp));
-                        }
-                    }
-                }
+        match parts {
+            ["buff", handle, level] => Some(AceCommands::Buff(
+                parse_handle(handle)?,
+                level.parse().ok()?,
+            )),
+            ["nerf", handle, level] => Some(AceCommands::Nerf(
+                parse_handle(handle)?,
+                level.parse().ok()?,
+            )),
+            ["set", handle, stat, value] => {
+                let value = value.parse().ok()?;
+                // Make sure that the stat exists and the value is valid
+                player_stats::set_stat(&mut PlayerStats::default(), stat, value, balance).ok()?;
+                Some(AceCommands::Set(
+                    parse_handle(handle)?,
+                    stat.to_string(),
+                    value,
+                ))
             }
-            "nerf" => {
-                if let Ok(handle) = parts[1].parse() {
-                    if let Ok(level) = parts[2].parse() {
-                        if let Ok(timestamp) = parts[3].parse() {
-                            return Some(AceCommands::Nerf(handle, level, timestamp));
-                        }
-                    }
+            ["preset", name, values @ ..] => {
+                let values = values
+                    .iter()
+                    .map(|value| value.parse().ok())
+                    .collect::<Option<Vec<f32>>>()?;
+                if values.len() != PLAYER_COUNT * player_stats::STAT_NAMES.len() {
+                    return None;
+                }
+
+                let mut stats: [PlayerStats; PLAYER_COUNT] = Default::default();
+                for (stats, values) in stats
+                    .iter_mut()
+                    .zip(values.chunks(player_stats::STAT_NAMES.len()))
+                {
+                    *stats = player_stats::stats_from_values(values, balance).ok()?;
                 }
+                Some(AceCommands::Preset(name.to_string(), stats))
             }
-            "dummy" => {}
-            _ => {}
+            _ => None,
+        }
+    }
+
+    /// Short human readable version of the command.
+    pub fn description(&self) -> String {
+        match self {
+            Self::Preset(name, _) => format!("load preset {}", name),
+            command => command.to_string(),
+        }
+    }
+}
+
+/// A command that takes effect at the start of the given round.
+/// Rounds are counted the same on both peers (see `RoundStats`),
+/// so both apply the command at the same point of the game.
+///
+/// The peers agree on the round with a three way handshake:
+/// the issuer proposes a round (`cmd`), the acceptor answers with the earliest round
+/// it didn't start yet (`ack`) and the issuer confirms it (`confirm`),
+/// or proposes a later round if it already started that one (`reschedule`).
+/// Both only schedule the command once the round is confirmed.
+#[derive(Clone)]
+pub struct ScheduledCommand {
+    /// Only unique among the commands of one peer
+    pub id: u64,
+    pub round: u64,
+    pub command: AceCommands,
+}
+
+impl Display for ScheduledCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cmd {} {} {}", self.id, self.round, self.command)
+    }
+}
+
+impl ScheduledCommand {
+    pub fn from_str(s: &str, balance: &Balance) -> Option<ScheduledCommand> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        if parts.len() < 3 || parts[0] != "cmd" {
+            return None;
+        }
+
+        Some(ScheduledCommand {
+            id: parts[1].parse().ok()?,
+            round: parts[2].parse().ok()?,
+            command: AceCommands::from_parts(&parts[3..], balance)?,
+        })
+    }
+}
+
+/// Messages that are exchanged over the reliable channel to agree on commands.
+enum CommandMessage {
+    /// Our peer asks us to accept their command
+    Command(ScheduledCommand),
+    /// The remote accepted our command for the given round
+    Ack(u64, u64),
+    /// The remote declined our command
+    Decline(u64),
+    /// The remote scheduled the command we accepted for the given round
+    Confirm(u64, u64),
+    /// The remote already started the round we acknowledged, it proposes this one instead
+    Reschedule(u64, u64),
+}
+
+impl CommandMessage {
+    fn from_str(s: &str, balance: &Balance) -> Option<CommandMessage> {
+        if let Some(command) = ScheduledCommand::from_str(s, balance) {
+            return Some(CommandMessage::Command(command));
+        }
+
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        match parts[..] {
+            ["ack", id, round] => Some(CommandMessage::Ack(id.parse().ok()?, round.parse().ok()?)),
+            ["decline", id] => Some(CommandMessage::Decline(id.parse().ok()?)),
+            ["confirm", id, round] => Some(CommandMessage::Confirm(
+                id.parse().ok()?,
+                round.parse().ok()?,
+            )),
+            ["reschedule", id, round] => Some(CommandMessage::Reschedule(
+                id.parse().ok()?,
+                round.parse().ok()?,
+            )),
+            _ => None,
         }
-        None
     }
 }
 
+/// Send the command to our peer, it takes effect once they accept it.
+/// Fails if cheats are disabled in this match.
+pub fn issue_command(
+    socket: &mut AceSocket,
+    command_queue: &mut CommandQueue,
+    round_stats: &RoundStats,
+    match_rules: &MatchRules,
+    command: AceCommands,
+) -> Result<(), String> {
+    if !match_rules.cheats {
+        return Err("cheats are disabled in this match".to_string());
+    }
+
+    let scheduled_command = ScheduledCommand {
+        id: command_queue.next_id,
+        // The next round, the current one already started
+        round: round_stats.rounds_played + 1,
+        command,
+    };
+    command_queue.next_id += 1;
+
+    send_to_remotes(socket, &scheduled_command.to_string());
+    command_queue.pending.push(scheduled_command);
+    Ok(())
+}
+
 pub fn apply_commands(
-    mut command_queque: ResMut<CommandQueue>,
+    mut command_queue: ResMut<CommandQueue>,
     mut stats: ResMut<PersistentPlayerStats>,
+    round_stats: Res<RoundStats>,
+    balance: Res<Balance>,
 ) {
-    let mut skipped_queue = Vec::<AceCommands>::default();
-    for command in command_queque.queue.drain(..) {
-        match command {
-            AceCommands::Buff(handle, level, timestamp) => {
-                if Utc::now().timestamp_millis() - timestamp < MIN_TIME_THRESHOLD {
-                    warn!("skipping this command as it was added too late");
-                    skipped_queue.push(AceCommands::Buff(handle, level, timestamp));
-                    continue;
-                }
-                let buffed_stats = player_stats::buff_stats(level);
-                stats.stats[handle] = buffed_stats;
+    let round = round_stats.rounds_played;
+    let mut later_commands = Vec::<ScheduledCommand>::default();
+    for scheduled_command in command_queue.queue.drain(..) {
+        if scheduled_command.round > round {
+            later_commands.push(scheduled_command);
+            continue;
+        }
+        if scheduled_command.round < round {
+            error!(
+                "command '{}' was scheduled for round {} but we are already in round {}, skipping it",
+                scheduled_command.command, scheduled_command.round, round
+            );
+            continue;
+        }
+
+        info!("applying command '{}'", scheduled_command.command);
+        match scheduled_command.command {
+            AceCommands::Buff(handle, level) => {
+                stats.stats[handle] = player_stats::buff_stats(level, &balance);
             }
-            AceCommands::Nerf(handle, level, timestamp) => {
-                if Utc::now().timestamp_millis() - timestamp < MIN_TIME_THRESHOLD {
-                    warn!("skipping this command as it was added too late");
-                    skipped_queue.push(AceCommands::Nerf(handle, level, timestamp));
-                    continue;
+            AceCommands::Nerf(handle, level) => {
+                stats.stats[handle] = player_stats::nerf_stats(level, &balance);
+            }
+            AceCommands::Set(handle, stat, value) => {
+                if let Err(err) =
+                    player_stats::set_stat(&mut stats.stats[handle], &stat, value, &balance)
+                {
+                    error!("failed to set stat, {}", err);
                 }
-                info!("applying nerf command");
-                let nerfed_stats = player_stats::nerf_stats(level);
-                stats.stats[handle] = nerfed_stats;
+            }
+            AceCommands::Preset(_, preset_stats) => {
+                stats.stats = preset_stats;
             }
         }
     }
 
-    command_queque.queue.append(&mut skipped_queue);
+    command_queue.queue.append(&mut later_commands);
 }
 
-pub fn receive_commands(mut socket: ResMut<AceSocket>, mut command_queque: ResMut<CommandQueue>) {
-    let received_messages = socket.receive_tcp_message();
+/// Answer with the earliest round we didn't start yet,
+/// the command only gets scheduled once our peer confirms that round.
+fn acknowledge_command(
+    socket: &mut AceSocket,
+    command_queue: &mut CommandQueue,
+    round_stats: &RoundStats,
+    mut scheduled_command: ScheduledCommand,
+) {
+    // The player might have taken their time to accept it
+    scheduled_command.round = scheduled_command.round.max(round_stats.rounds_played + 1);
+    send_to_remotes(
+        socket,
+        &format!("ack {} {}", scheduled_command.id, scheduled_command.round),
+    );
+    command_queue.accepted.push(scheduled_command);
+}
 
-    if received_messages.is_empty() {
-        return;
-    }
+/// Accept the command of our peer, it gets scheduled once our peer confirms the round.
+pub fn accept_command(
+    socket: &mut AceSocket,
+    command_queue: &mut CommandQueue,
+    round_stats: &RoundStats,
+    scheduled_command: ScheduledCommand,
+) {
+    info!("accepting command of our peer, '{}'", scheduled_command);
+    acknowledge_command(socket, command_queue, round_stats, scheduled_command);
+}
 
-    for message in received_messages {
-        match AceCommands::from_str(&message.1) {
-            Some(ace_command) => {
-                info!("received command messsage, adding to queue");
-                command_queque.queue.push(ace_command);
+pub fn decline_command(socket: &mut AceSocket, scheduled_command: &ScheduledCommand) {
+    info!("declining command of our peer, '{}'", scheduled_command);
+    send_to_remotes(socket, &format!("decline {}", scheduled_command.id));
+}
+
+pub fn receive_commands(
+    mut socket: ResMut<AceSocket>,
+    mut ev_reliable_message: EventReader<ReliableMessage>,
+    mut command_queue: ResMut<CommandQueue>,
+    mut consent_requests: ResMut<ConsentRequests>,
+    mut console_lines: EventWriter<PrintConsoleLine>,
+    match_rules: Res<MatchRules>,
+    round_stats: Res<RoundStats>,
+    balance: Res<Balance>,
+) {
+    for ev in ev_reliable_message.iter() {
+        let command_message = match CommandMessage::from_str(&ev.message, &balance) {
+            Some(command_message) => command_message,
+            None => {
+                warn!("command message failed to parse, '{}'", ev.message);
+                continue;
+            }
+        };
+
+        match command_message {
+            CommandMessage::Command(scheduled_command) => {
+                if !match_rules.cheats {
+                    warn!("received command while cheats are disabled");
+                    decline_command(&mut socket, &scheduled_command);
+                    continue;
+                }
+                consent_requests.0.push(scheduled_command);
+            }
+            CommandMessage::Ack(id, round) => {
+                let Some(index) = command_queue.pending.iter().position(|c| c.id == id) else {
+                    warn!("received ack for unknown command {}", id);
+                    continue;
+                };
+                if round <= round_stats.rounds_played {
+                    // We already started that round, our peer has to agree on a later one
+                    let round = round_stats.rounds_played + 2;
+                    info!("rescheduling command {} to round {}", id, round);
+                    command_queue.pending[index].round = round;
+                    send_to_remotes(&mut socket, &format!("reschedule {} {}", id, round));
+                    continue;
+                }
+
+                let mut scheduled_command = command_queue.pending.remove(index);
+                scheduled_command.round = round;
+                send_to_remotes(&mut socket, &format!("confirm {} {}", id, round));
+                info!("peer accepted command '{}'", scheduled_command);
+                console_lines.send(PrintConsoleLine::new(
+                    format!(
+                        "opponent accepted '{}', applying it in round {}",
+                        scheduled_command.command.description(),
+                        round + 1
+                    )
+                    .into(),
+                ));
+                command_queue.queue.push(scheduled_command);
+            }
+            CommandMessage::Decline(id) => {
+                let Some(index) = command_queue.pending.iter().position(|c| c.id == id) else {
+                    warn!("received decline for unknown command {}", id);
+                    continue;
+                };
+                let scheduled_command = command_queue.pending.remove(index);
+                info!("peer declined command '{}'", scheduled_command);
+                console_lines.send(PrintConsoleLine::new(
+                    format!(
+                        "opponent declined '{}'",
+                        scheduled_command.command.description()
+                    )
+                    .into(),
+                ));
+            }
+            CommandMessage::Confirm(id, round) => {
+                let Some(index) = command_queue.accepted.iter().position(|c| c.id == id) else {
+                    warn!("received confirm for unknown command {}", id);
+                    continue;
+                };
+                let mut scheduled_command = command_queue.accepted.remove(index);
+                scheduled_command.round = round;
+                if round <= round_stats.rounds_played {
+                    // Our peer only confirms rounds it didn't start yet and the peers
+                    // are at most a few frames apart, this needs a confirm that took ages
+                    error!(
+                        "command '{}' was confirmed for round {} but we are already in round {}",
+                        scheduled_command.command, round, round_stats.rounds_played
+                    );
+                }
+                info!("scheduling command of our peer, '{}'", scheduled_command);
+                command_queue.queue.push(scheduled_command);
+            }
+            CommandMessage::Reschedule(id, round) => {
+                let Some(index) = command_queue.accepted.iter().position(|c| c.id == id) else {
+                    warn!("received reschedule for unknown command {}", id);
+                    continue;
+                };
+                let mut scheduled_command = command_queue.accepted.remove(index);
+                scheduled_command.round = round;
+                acknowledge_command(
+                    &mut socket,
+                    &mut command_queue,
+                    &round_stats,
+                    scheduled_command,
+                );
             }
-            None => warn!("command message failed to pass to string"),
         }
     }
 }
 
 pub fn reset_commands(
-    mut command_queque: ResMut<CommandQueue>,
+    mut command_queue: ResMut<CommandQueue>,
+    mut consent_requests: ResMut<ConsentRequests>,
     mut stats: ResMut<PersistentPlayerStats>,
+    balance: Res<Balance>,
 ) {
-    *command_queque = CommandQueue::default();
-    *stats = PersistentPlayerStats::default();
+    *command_queue = CommandQueue::default();
+    *consent_requests = ConsentRequests::default();
+    *stats = PersistentPlayerStats::new(&balance);
 }
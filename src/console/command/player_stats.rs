@@ -1,6 +1,25 @@
-use bevy::utils::default;
+use std::fs;
+use std::path::PathBuf;
 
-use crate::player::{PlayerStats, MIN_SPEED};
+use crate::misc::Balance;
+use crate::network::ggrs_config::PLAYER_COUNT;
+use crate::player::{PersistentPlayerStats, PlayerStats};
+
+// Presets are stored next to the executable, like the logs
+const PRESET_DIR: &str = "presets";
+
+pub const STAT_NAMES: [&str; 10] = [
+    "max_speed",
+    "max_health",
+    "bullet_damage",
+    "bullet_reload_time",
+    "bullet_heat",
+    "rocket_reload_time",
+    "dodge_time",
+    "dodge_cooldown",
+    "flare_charges",
+    "bullet_size",
+];
 
 const MAX_BUFF_STATS: PlayerStats = PlayerStats {
     max_speed: 10000.0,
@@ -11,10 +30,16 @@ const MAX_BUFF_STATS: PlayerStats = PlayerStats {
     rocket_reload_time: 0.0,
     dodge_time: 0.5,
     dodge_cooldown: 0.1,
+    flare_charges: 99,
+    bullet_size: 3.0,
 };
 
+// The fully nerfed max speed is this much above the minimum speed of the balance
+const MAX_NERF_SPEED_RATIO: f32 = 1.25;
+
 const MAX_NERF_STATS: PlayerStats = PlayerStats {
-    max_speed: MIN_SPEED,
+    // Depends on the balance, see `nerf_stats`
+    max_speed: 0.0,
     max_health: 1,
     bullet_damage: 1,
     bullet_reload_time: 1.0,
@@ -22,29 +47,193 @@ const MAX_NERF_STATS: PlayerStats = PlayerStats {
     rocket_reload_time: 10.0,
     dodge_time: 0.1,
     dodge_cooldown: 2.5,
+    flare_charges: 0,
+    bullet_size: 0.5,
 };
 
-pub fn buff_stats(_level: usize) -> PlayerStats {
-    MAX_BUFF_STATS
+fn lerp(from: f32, to: f32, percent: f32) -> f32 {
+    from + (to - from) * percent
 }
 
-pub fn nerf_stats(level: usize) -> PlayerStats {
-    let min_nerf = PlayerStats::default();
-    let max_nerf = MAX_NERF_STATS;
-    let percent = (level as f32 / 9.0).clamp(0.0, 1.0);
+fn lerp_u32(from: u32, to: u32, percent: f32) -> u32 {
+    lerp(from as f32, to as f32, percent).round() as u32
+}
 
+fn interpolate_stats(from: &PlayerStats, to: &PlayerStats, level: usize) -> PlayerStats {
+    let percent = (level as f32 / 9.0).clamp(0.0, 1.0);
     PlayerStats {
-        max_speed: min_nerf.max_speed + (max_nerf.max_speed - min_nerf.max_speed) * percent,
-        // We know that min_nerf has a higher value and because max_nerf > 0 this will not overflow
-        max_health: min_nerf.max_health
-            - ((min_nerf.max_health - max_nerf.max_health) as f32 * percent) as u32,
-        bullet_damage: min_nerf.bullet_damage
-            - ((min_nerf.bullet_damage - max_nerf.bullet_damage) as f32 * percent) as u32,
-        bullet_reload_time: min_nerf.bullet_reload_time
-            + (max_nerf.bullet_reload_time - min_nerf.bullet_reload_time) * percent,
-        rocket_reload_time: min_nerf.rocket_reload_time
-            + (max_nerf.rocket_reload_time - min_nerf.rocket_reload_time) * percent,
-        dodge_time: min_nerf.dodge_time + (max_nerf.dodge_time - min_nerf.dodge_time) * percent,
-        ..default()
+        max_speed: lerp(from.max_speed, to.max_speed, percent),
+        max_health: lerp_u32(from.max_health, to.max_health, percent),
+        bullet_damage: lerp_u32(from.bullet_damage, to.bullet_damage, percent),
+        bullet_reload_time: lerp(from.bullet_reload_time, to.bullet_reload_time, percent),
+        bullet_heat: lerp_u32(from.bullet_heat, to.bullet_heat, percent),
+        rocket_reload_time: lerp(from.rocket_reload_time, to.rocket_reload_time, percent),
+        dodge_time: lerp(from.dodge_time, to.dodge_time, percent),
+        dodge_cooldown: lerp(from.dodge_cooldown, to.dodge_cooldown, percent),
+        flare_charges: lerp_u32(from.flare_charges, to.flare_charges, percent),
+        bullet_size: lerp(from.bullet_size, to.bullet_size, percent),
+    }
+}
+
+/// Move every stat from the balance towards `MAX_BUFF_STATS`, level 9 is the full buff.
+pub fn buff_stats(level: usize, balance: &Balance) -> PlayerStats {
+    interpolate_stats(&balance.player_stats(), &MAX_BUFF_STATS, level)
+}
+
+/// Move every stat from the balance towards `MAX_NERF_STATS`, level 9 is the full nerf.
+/// This includes `bullet_heat` and `dodge_cooldown`, which used to stay at their defaults.
+pub fn nerf_stats(level: usize, balance: &Balance) -> PlayerStats {
+    let stats = balance.player_stats();
+    let max_nerf = PlayerStats {
+        // Never above the default, the balance makes sure that one is above the minimum speed
+        max_speed: (balance.min_speed() * MAX_NERF_SPEED_RATIO).min(stats.max_speed),
+        ..MAX_NERF_STATS
+    };
+    interpolate_stats(&stats, &max_nerf, level)
+}
+
+pub fn get_stat(stats: &PlayerStats, name: &str) -> Option<f32> {
+    let value = match name {
+        "max_speed" => stats.max_speed,
+        "max_health" => stats.max_health as f32,
+        "bullet_damage" => stats.bullet_damage as f32,
+        "bullet_reload_time" => stats.bullet_reload_time,
+        "bullet_heat" => stats.bullet_heat as f32,
+        "rocket_reload_time" => stats.rocket_reload_time,
+        "dodge_time" => stats.dodge_time,
+        "dodge_cooldown" => stats.dodge_cooldown,
+        "flare_charges" => stats.flare_charges as f32,
+        "bullet_size" => stats.bullet_size,
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn to_u32(name: &str, value: f32) -> Result<u32, String> {
+    if value.fract() != 0.0 || value > u32::MAX as f32 {
+        return Err(format!("{} must be a whole number", name));
+    }
+    Ok(value as u32)
+}
+
+/// Set a single stat, fails if the stat doesn't exist or the value makes no sense.
+pub fn set_stat(
+    stats: &mut PlayerStats,
+    name: &str,
+    value: f32,
+    balance: &Balance,
+) -> Result<(), String> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(format!("{} must be a positive number", name));
+    }
+
+    match name {
+        "max_speed" => {
+            if value <= balance.min_speed() {
+                return Err(format!(
+                    "max_speed must be greater than {}",
+                    balance.min_speed()
+                ));
+            }
+            stats.max_speed = value;
+        }
+        "max_health" => stats.max_health = to_u32(name, value)?.max(1),
+        "bullet_damage" => stats.bullet_damage = to_u32(name, value)?,
+        "bullet_reload_time" => stats.bullet_reload_time = value,
+        "bullet_heat" => stats.bullet_heat = to_u32(name, value)?,
+        "rocket_reload_time" => stats.rocket_reload_time = value,
+        "dodge_time" => stats.dodge_time = value,
+        "dodge_cooldown" => stats.dodge_cooldown = value,
+        "flare_charges" => stats.flare_charges = to_u32(name, value)?,
+        "bullet_size" => stats.bullet_size = value,
+        _ => {
+            return Err(format!(
+                "unknown stat '{}', expected one of {}",
+                name,
+                STAT_NAMES.join(", ")
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// All stats in the order of `STAT_NAMES`.
+pub fn stats_to_values(stats: &PlayerStats) -> Vec<f32> {
+    STAT_NAMES
+        .iter()
+        .map(|name| get_stat(stats, name).expect("stat names must be valid"))
+        .collect()
+}
+
+pub fn stats_from_values(values: &[f32], balance: &Balance) -> Result<PlayerStats, String> {
+    if values.len() != STAT_NAMES.len() {
+        return Err(format!(
+            "expected {} stats, got {}",
+            STAT_NAMES.len(),
+            values.len()
+        ));
+    }
+
+    let mut stats = PlayerStats::default();
+    for (name, value) in STAT_NAMES.iter().zip(values) {
+        set_stat(&mut stats, name, *value, balance)?;
+    }
+    Ok(stats)
+}
+
+fn preset_path(name: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(format!(
+            "invalid preset name '{}', only use letters, digits, '_' and '-'",
+            name
+        ));
+    }
+    Ok(PathBuf::from(PRESET_DIR).join(format!("{}.txt", name)))
+}
+
+/// Store the stats of all players, one line per stat in the form `<handle> <stat> <value>`.
+pub fn save_preset(name: &str, stats: &[PlayerStats; PLAYER_COUNT]) -> Result<(), String> {
+    let path = preset_path(name)?;
+    let mut lines = Vec::new();
+    for (handle, stats) in stats.iter().enumerate() {
+        for (stat, value) in STAT_NAMES.iter().zip(stats_to_values(stats)) {
+            lines.push(format!("{} {} {}", handle, stat, value));
+        }
+    }
+
+    fs::create_dir_all(PRESET_DIR).map_err(|e| e.to_string())?;
+    fs::write(&path, lines.join("\n") + "\n").map_err(|e| e.to_string())
+}
+
+/// Stats that are missing from the preset keep their value from the balance.
+pub fn load_preset(name: &str, balance: &Balance) -> Result<[PlayerStats; PLAYER_COUNT], String> {
+    let path = preset_path(name)?;
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("failed to read '{}', {}", path.display(), e))?;
+
+    let mut stats = PersistentPlayerStats::new(balance).stats;
+    for (i, line) in content.lines().enumerate() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let [handle, stat, value] = parts[..] else {
+            if parts.is_empty() {
+                continue;
+            }
+            return Err(format!("line {} is not '<handle> <stat> <value>'", i + 1));
+        };
+
+        let handle = handle
+            .parse::<usize>()
+            .ok()
+            .filter(|&handle| handle < PLAYER_COUNT)
+            .ok_or(format!("line {} has an invalid handle", i + 1))?;
+        let value = value
+            .parse::<f32>()
+            .map_err(|_| format!("line {} has an invalid value", i + 1))?;
+        set_stat(&mut stats[handle], stat, value, balance)?;
     }
+    Ok(stats)
 }
@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+use bevy_console::ConsoleOpen;
+use bevy_ggrs::AddRollbackCommandExtension;
+
+use super::command::{accept_command, decline_command, ScheduledCommand};
+use super::CommandQueue;
+use crate::network::socket::AceSocket;
+use crate::ui::just_pressed;
+use crate::world::RoundStats;
+use crate::{GameAssets, GameState, RollbackState};
+
+/// Commands of our peer that wait for the local player to accept or decline them.
+#[derive(Resource, Default)]
+pub struct ConsentRequests(pub Vec<ScheduledCommand>);
+
+#[derive(Component)]
+struct ConsentText;
+
+fn spawn_consent_screen(mut commands: Commands, assets: Res<GameAssets>) {
+    let root_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Vw(100.0),
+                top: Val::Vh(15.0),
+                justify_content: JustifyContent::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            z_index: ZIndex::Global(101),
+            ..default()
+        })
+        .add_rollback()
+        .id();
+    let text = commands
+        .spawn((
+            ConsentText,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 25.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_text_alignment(TextAlignment::Center),
+        ))
+        .add_rollback()
+        .id();
+    commands.entity(root_node).push_children(&[text]);
+}
+
+fn consent_text(consent_requests: &ConsentRequests) -> String {
+    match consent_requests.0.first() {
+        Some(scheduled_command) => format!(
+            "OPPONENT WANTS TO APPLY: {}\n[Y] ACCEPT [N] DECLINE",
+            scheduled_command.command.description().to_uppercase()
+        ),
+        None => String::new(),
+    }
+}
+
+fn update_consent_screen(
+    consent_requests: Res<ConsentRequests>,
+    mut texts: Query<&mut Text, With<ConsentText>>,
+) {
+    let value = consent_text(&consent_requests);
+    for mut text in &mut texts {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+/// Accept or decline the oldest request of our peer.
+fn answer_consent_requests(
+    keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    button_inputs: Res<Input<GamepadButton>>,
+    console_open: Res<ConsoleOpen>,
+    mut socket: ResMut<AceSocket>,
+    mut command_queue: ResMut<CommandQueue>,
+    mut consent_requests: ResMut<ConsentRequests>,
+    round_stats: Res<RoundStats>,
+) {
+    // Don't answer while the player is typing
+    if consent_requests.0.is_empty() || console_open.open {
+        return;
+    }
+
+    if just_pressed(
+        &keys,
+        &gamepads,
+        &button_inputs,
+        KeyCode::Y,
+        GamepadButtonType::Start,
+    ) {
+        let scheduled_command = consent_requests.0.remove(0);
+        accept_command(
+            &mut socket,
+            &mut command_queue,
+            &round_stats,
+            scheduled_command,
+        );
+    } else if just_pressed(
+        &keys,
+        &gamepads,
+        &button_inputs,
+        KeyCode::N,
+        GamepadButtonType::Select,
+    ) {
+        let scheduled_command = consent_requests.0.remove(0);
+        decline_command(&mut socket, &scheduled_command);
+    }
+}
+
+pub struct ConsentPlugin;
+
+impl Plugin for ConsentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsentRequests>()
+            .add_systems(OnExit(RollbackState::Setup), spawn_consent_screen)
+            .add_systems(
+                Update,
+                (
+                    answer_consent_requests.run_if(resource_exists::<AceSocket>()),
+                    update_consent_screen,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InRollbackGame)),
+            );
+    }
+}
@@ -1,14 +1,25 @@
 mod command;
+mod consent;
 
 use bevy::prelude::*;
 use bevy_console::{ConsoleConfiguration, ConsolePlugin, ToggleConsoleKey};
 
+use crate::network::socket::AceSocket;
 use crate::{player::spawning::spawn_players, GameState, RollbackState};
-use command::{apply_commands, receive_commands, reset_commands, AceCommandPlugin, AceCommands};
+use command::{
+    apply_commands, receive_commands, reset_commands, AceCommandPlugin, ScheduledCommand,
+};
+use consent::{ConsentPlugin, ConsentRequests};
 
 #[derive(Resource, Default)]
 pub struct CommandQueue {
-    pub queue: Vec<AceCommands>,
+    /// Commands both peers agreed on, waiting for their round
+    pub queue: Vec<ScheduledCommand>,
+    /// Our commands that the remote didn't accept yet
+    pub pending: Vec<ScheduledCommand>,
+    /// Commands of the remote that we accepted, waiting for the remote to confirm their round
+    pub accepted: Vec<ScheduledCommand>,
+    pub next_id: u64,
 }
 
 pub struct AceConsolePlugin;
@@ -19,9 +30,10 @@ impl Plugin for AceConsolePlugin {
             Update,
             receive_commands
                 .chain()
-                .run_if(in_state(GameState::InRollbackGame)),
+                .run_if(in_state(GameState::InRollbackGame))
+                .run_if(resource_exists::<AceSocket>()),
         )
-        .add_plugins((ConsolePlugin, AceCommandPlugin))
+        .add_plugins((ConsolePlugin, AceCommandPlugin, ConsentPlugin))
         .init_resource::<CommandQueue>()
         .insert_resource(ConsoleConfiguration {
             keys: vec![ToggleConsoleKey::KeyCode(KeyCode::F1)],
@@ -28,8 +28,8 @@ pub fn get_gamepad_input(
     axes: &Res<Axis<GamepadAxis>>,
     players: &Query<(&Transform, &Player)>,
     local_handle: ggrs::PlayerHandle,
-) -> u8 {
-    let mut input = 0u8;
+) -> u16 {
+    let mut input = 0u16;
 
     for gamepad in gamepads.iter() {
         if gamepad.id != 0 {
@@ -47,6 +47,19 @@ pub fn get_gamepad_input(
         {
             input |= INPUT_REMATCH;
         }
+        if button_inputs.pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadDown)) {
+            input |= INPUT_FLARE;
+        }
+        if button_inputs.pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadLeft)) {
+            input |= INPUT_PERK_1;
+        }
+        // DPadUp toggles fullscreen
+        if button_inputs.pressed(GamepadButton::new(gamepad, GamepadButtonType::RightThumb)) {
+            input |= INPUT_PERK_2;
+        }
+        if button_inputs.pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadRight)) {
+            input |= INPUT_PERK_3;
+        }
 
         let l1 = button_axes
             .get(GamepadButton::new(gamepad, GamepadButtonType::LeftTrigger))
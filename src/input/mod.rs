@@ -5,16 +5,23 @@ pub use gamepad::GamepadRumble;
 use bevy::{app::AppExit, input::gamepad::*, prelude::*};
 use bevy_ggrs::*;
 
-use crate::{player::Player, GameState, RollbackState};
-
-pub const INPUT_FORWARD: u8 = 1 << 0;
-pub const INPUT_BACKWARD: u8 = 1 << 1;
-pub const INPUT_LEFT: u8 = 1 << 2;
-pub const INPUT_RIGHT: u8 = 1 << 3;
-pub const INPUT_FIRE: u8 = 1 << 4;
-pub const INPUT_DODGE: u8 = 1 << 5;
-pub const INPUT_ROCKET: u8 = 1 << 6;
-pub const INPUT_REMATCH: u8 = 1 << 7;
+use crate::{
+    player::{LocalPlayerHandle, Player},
+    GameState, RollbackState,
+};
+
+pub const INPUT_FORWARD: u16 = 1 << 0;
+pub const INPUT_BACKWARD: u16 = 1 << 1;
+pub const INPUT_LEFT: u16 = 1 << 2;
+pub const INPUT_RIGHT: u16 = 1 << 3;
+pub const INPUT_FIRE: u16 = 1 << 4;
+pub const INPUT_DODGE: u16 = 1 << 5;
+pub const INPUT_ROCKET: u16 = 1 << 6;
+pub const INPUT_REMATCH: u16 = 1 << 7;
+pub const INPUT_FLARE: u16 = 1 << 8;
+pub const INPUT_PERK_1: u16 = 1 << 9;
+pub const INPUT_PERK_2: u16 = 1 << 10;
+pub const INPUT_PERK_3: u16 = 1 << 11;
 
 pub fn input(
     In(local_handle): In<ggrs::PlayerHandle>,
@@ -25,8 +32,14 @@ pub fn input(
     button_axes: Res<Axis<GamepadButton>>,
     axes: Res<Axis<GamepadAxis>>,
     players: Query<(&Transform, &Player)>,
-) -> u8 {
-    let mut input = 0u8;
+    local_player: Option<Res<LocalPlayerHandle>>,
+) -> u16 {
+    // In the local session of a snapshot both players are local, only the first one is controlled
+    if local_player.is_some_and(|local_player| local_player.0 != local_handle) {
+        return 0;
+    }
+
+    let mut input = 0u16;
 
     if keys.any_pressed([KeyCode::Up, KeyCode::W, KeyCode::K]) {
         input |= INPUT_FORWARD;
@@ -54,6 +67,18 @@ pub fn input(
     if keys.pressed(KeyCode::R) {
         input |= INPUT_REMATCH;
     }
+    if keys.any_pressed([KeyCode::C, KeyCode::I]) {
+        input |= INPUT_FLARE;
+    }
+    if keys.pressed(KeyCode::Key1) {
+        input |= INPUT_PERK_1;
+    }
+    if keys.pressed(KeyCode::Key2) {
+        input |= INPUT_PERK_2;
+    }
+    if keys.pressed(KeyCode::Key3) {
+        input |= INPUT_PERK_3;
+    }
 
     let controller_input = gamepad::get_gamepad_input(
         &gamepads,
@@ -67,7 +92,7 @@ pub fn input(
     input
 }
 
-pub fn steer_direction(input: u8) -> f32 {
+pub fn steer_direction(input: u16) -> f32 {
     let mut steer_direction: f32 = 0.0;
     if input & INPUT_LEFT != 0 {
         steer_direction += 1.0;
@@ -78,7 +103,7 @@ pub fn steer_direction(input: u8) -> f32 {
     steer_direction
 }
 
-pub fn accelerate_direction(input: u8) -> f32 {
+pub fn accelerate_direction(input: u16) -> f32 {
     let mut accelerate_direction: f32 = 0.0;
     if input & INPUT_FORWARD != 0 {
         accelerate_direction += 1.0;
@@ -89,22 +114,33 @@ pub fn accelerate_direction(input: u8) -> f32 {
     accelerate_direction
 }
 
-pub fn fire(input: u8) -> bool {
+pub fn fire(input: u16) -> bool {
     input & INPUT_FIRE != 0
 }
 
-pub fn dodge(input: u8) -> bool {
+pub fn dodge(input: u16) -> bool {
     input & INPUT_DODGE != 0
 }
 
-pub fn rocket(input: u8) -> bool {
+pub fn rocket(input: u16) -> bool {
     input & INPUT_ROCKET != 0
 }
 
-pub fn rematch(input: u8) -> bool {
+pub fn rematch(input: u16) -> bool {
     input & INPUT_REMATCH != 0
 }
 
+pub fn flare(input: u16) -> bool {
+    input & INPUT_FLARE != 0
+}
+
+/// The index of the perk that was picked, if any.
+pub fn perk_choice(input: u16) -> Option<usize> {
+    [INPUT_PERK_1, INPUT_PERK_2, INPUT_PERK_3]
+        .into_iter()
+        .position(|perk| input & perk != 0)
+}
+
 pub fn quit(
     mut exit: EventWriter<AppExit>,
     keys: Res<Input<KeyCode>>,
@@ -133,6 +169,7 @@ impl Plugin for AceInputPlugin {
                 quit.run_if(
                     in_state(GameState::MainMenu)
                         .or_else(in_state(GameState::Matchmaking))
+                        .or_else(in_state(GameState::Lobby))
                         .or_else(
                             in_state(GameState::InRollbackGame)
                                 .and_then(in_state(RollbackState::GameOver)),
@@ -1,13 +1,21 @@
 //use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+#[cfg(debug_assertions)]
+use std::time::Duration;
+
+use bevy::app::PluginGroupBuilder;
+#[cfg(debug_assertions)]
+use bevy::asset::ChangeWatcher;
 use bevy::prelude::*;
 use bevy::window::{PresentMode, Window};
 
 use bevy_asset_loader::prelude::*;
 use bevy_common_assets::toml::TomlAssetPlugin;
+#[cfg(not(debug_assertions))]
 use bevy_embedded_assets::EmbeddedAssetPlugin;
 use bevy_ggrs::*;
 use bevy_hanabi::HanabiPlugin;
 use bevy_roll_safe::prelude::*;
+use clap::{Parser, Subcommand};
 
 mod assets;
 mod audio;
@@ -21,7 +29,8 @@ mod ui;
 mod world;
 
 use misc::debug;
-use network::GgrsConfig;
+use network::session::{MatchboxServer, DEFAULT_MATCHBOX_SERVER};
+use network::{rollback::RegisterRollbackTypes, signalling, GgrsConfig};
 use ui::round_start_screen::{HideScreenTimer, RoundStartTimer};
 
 pub use assets::GameAssets;
@@ -32,6 +41,8 @@ pub enum GameState {
     AssetLoading,
     MainMenu,
     Matchmaking,
+    /// Both players ban and pick maps before the match starts
+    Lobby,
     InRollbackGame,
 }
 
@@ -45,14 +56,56 @@ pub enum RollbackState {
     GameOver,
 }
 
+/// Release builds ship with embedded assets,
+/// debug builds load them from disk so that the balance can be hot reloaded.
+#[cfg(not(debug_assertions))]
+fn asset_plugins(plugins: PluginGroupBuilder) -> PluginGroupBuilder {
+    plugins
+        .build()
+        .add_before::<AssetPlugin, _>(EmbeddedAssetPlugin)
+}
+
+#[cfg(debug_assertions)]
+fn asset_plugins(plugins: PluginGroupBuilder) -> PluginGroupBuilder {
+    plugins.set(AssetPlugin {
+        watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(200)),
+        ..default()
+    })
+}
+
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// The signalling server to find opponents on, e.g. ws://<ip>:<port>
+    #[arg(long, default_value = DEFAULT_MATCHBOX_SERVER)]
+    matchbox: String,
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Host a signalling server instead of starting the game
+    Serve {
+        #[arg(long, default_value_t = network::DEFAULT_PORT)]
+        port: u16,
+    },
+}
+
 fn main() {
-    App::new()
-        .add_state::<GameState>()
+    let cli = Cli::parse();
+    if let Some(CliCommand::Serve { port }) = cli.command {
+        signalling::serve(port);
+        return;
+    }
+
+    let mut app = App::new();
+    app.add_state::<GameState>()
         .add_loading_state(
             LoadingState::new(GameState::AssetLoading).continue_to_state(GameState::MainMenu),
         )
         .add_collection_to_loading_state::<_, GameAssets>(GameState::AssetLoading)
-        .add_plugins(
+        .add_plugins(asset_plugins(
             DefaultPlugins
                 .set(WindowPlugin {
                     primary_window: Some(Window {
@@ -61,40 +114,60 @@ fn main() {
                     }),
                     ..default()
                 })
-                .set(ImagePlugin::default_nearest())
-                .build()
-                .add_before::<bevy::asset::AssetPlugin, _>(EmbeddedAssetPlugin),
-        )
-        .add_ggrs_plugin(
-            GgrsPlugin::<GgrsConfig>::new()
-                .with_input_system(input::input)
-                .register_roll_state::<RollbackState>()
-                .register_rollback_resource::<world::RoundEndTimer>()
-                .register_rollback_resource::<world::Score>()
-                .register_rollback_resource::<world::Rematch>()
-                .register_rollback_resource::<world::RoundStats>()
-                .register_rollback_resource::<camera::CameraShake>()
-                .register_rollback_resource::<RoundStartTimer>()
-                .register_rollback_resource::<HideScreenTimer>()
-                .register_rollback_component::<Transform>()
-                .register_rollback_component::<Style>()
-                .register_rollback_component::<debug::DebugTransform>()
-                .register_rollback_component::<world::map::obstacle::CollisionEntity>()
-                .register_rollback_component::<player::Player>()
-                .register_rollback_component::<player::dodge::DodgeTimer>()
-                .register_rollback_component::<player::shooting::bullet::Bullet>()
-                .register_rollback_component::<player::shooting::bullet::BulletTimer>()
-                .register_rollback_component::<player::shooting::rocket::Rocket>()
-                .register_rollback_component::<player::shooting::rocket::RocketTimer>()
-                .register_rollback_component::<player::shooting::rocket_explosion::RocketExplosion>()
-                .register_rollback_component::<player::shooting::rocket_explosion::ExplosionAnimationTimer>()
-                .register_rollback_component::<audio::RollbackSound>(),
-        )
+                .set(ImagePlugin::default_nearest()),
+        ))
+        // `register_roll_state` below only adds it to the type registry of `bevy_ggrs`
+        .register_rollback_resource_type::<State<RollbackState>>();
+
+    let ggrs_plugin = register_rollback_types!(
+        app,
+        GgrsPlugin::<GgrsConfig>::new()
+            .with_input_system(input::input)
+            .register_roll_state::<RollbackState>(),
+        resources: [
+            world::RoundEndTimer,
+            world::Score,
+            world::RoundFrame,
+            world::map::border::Arena,
+            world::map::theme::MapTheme,
+            world::Rematch,
+            world::RoundStats,
+            world::ObjectiveState,
+            player::perk::Perks,
+            camera::CameraShake,
+            RoundStartTimer,
+            HideScreenTimer,
+            network::desync::SimulationFrame,
+        ],
+        components: [
+            Transform,
+            Style,
+            debug::DebugTransform,
+            world::map::obstacle::CollisionEntity,
+            world::map::destructible::DestructibleWall,
+            player::Player,
+            player::dodge::DodgeTimer,
+            player::shooting::bullet::Bullet,
+            player::shooting::bullet::BulletTimer,
+            player::shooting::rocket::Rocket,
+            player::shooting::rocket::RocketTimer,
+            player::shooting::flare::Flare,
+            player::shooting::flare::FlareTimer,
+            player::shooting::rocket_explosion::RocketExplosion,
+            player::shooting::rocket_explosion::ExplosionAnimationTimer,
+            audio::RollbackSound,
+        ],
+    );
+
+    app.add_ggrs_plugin(ggrs_plugin)
         .add_roll_state::<RollbackState>(GgrsSchedule)
         .add_plugins((
             //LogDiagnosticsPlugin::default(),
             //FrameTimeDiagnosticsPlugin::default(),
             TomlAssetPlugin::<assets::TurnCredentials>::new(&["toml"]),
+            TomlAssetPlugin::<misc::Balance>::new(&["balance.toml"]),
+            misc::balance::BalancePlugin,
+            misc::snapshot::SnapshotPlugin,
             HanabiPlugin,
             audio::GameAudioPlugin,
             world::GameLogicPlugin,
@@ -107,6 +180,8 @@ fn main() {
             debug::AceDebugPlugin,
         ))
         .insert_resource(ClearColor(Color::BLACK))
+        .insert_resource(MatchboxServer(cli.matchbox))
+        .init_resource::<misc::Settings>()
         .init_resource::<RoundStartTimer>()
         .init_resource::<HideScreenTimer>()
         .run();
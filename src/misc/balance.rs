@@ -0,0 +1,158 @@
+use serde::Deserialize;
+
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+
+use crate::network::ggrs_config::GGRS_FPS;
+use crate::player::PlayerStats;
+use crate::{GameAssets, GameState};
+
+/// Gameplay values that are shared by all players, loaded from `balance.toml`.
+/// Both peers must use the same values, see `hash`.
+///
+/// Speeds and rotations are stored per second in the file,
+/// the accessors return them per frame.
+#[derive(Resource, Clone, Debug, Deserialize, TypeUuid, TypePath)]
+#[uuid = "6f1b2c4e-5d0a-4f63-9a87-3c2e1d5b7a90"]
+pub struct Balance {
+    /// Pixels per second
+    min_speed: f32,
+    /// Radians per second
+    steering: f32,
+    player_radius: f32,
+    /// Pixels per second, on top of the speed of the plane
+    bullet_speed: f32,
+    overheat: u32,
+    /// Pixels per second
+    rocket_speed: f32,
+    /// Radians per second
+    rocket_steering: f32,
+    rocket_push_strength: f32,
+    /// Degrees, rockets can only see targets in front of them
+    rocket_visibility_angle: f32,
+    /// Seconds
+    dodge_refresh_time: f32,
+    /// The stats every player starts with, `max_speed` in pixels per second
+    player_stats: PlayerStats,
+}
+
+impl Balance {
+    pub fn min_speed(&self) -> f32 {
+        self.min_speed / GGRS_FPS as f32
+    }
+
+    pub fn delta_steering(&self) -> f32 {
+        self.steering / GGRS_FPS as f32
+    }
+
+    pub fn player_radius(&self) -> f32 {
+        self.player_radius
+    }
+
+    pub fn bullet_speed(&self) -> f32 {
+        self.bullet_speed / GGRS_FPS as f32
+    }
+
+    pub fn overheat(&self) -> u32 {
+        // We divide by this
+        self.overheat.max(1)
+    }
+
+    pub fn rocket_speed(&self) -> f32 {
+        self.rocket_speed / GGRS_FPS as f32
+    }
+
+    pub fn rocket_delta_steering(&self) -> f32 {
+        self.rocket_steering / GGRS_FPS as f32
+    }
+
+    pub fn rocket_push_strength(&self) -> f32 {
+        self.rocket_push_strength
+    }
+
+    pub fn rocket_visibility_angle(&self) -> f32 {
+        self.rocket_visibility_angle.to_radians()
+    }
+
+    pub fn dodge_refresh_time(&self) -> f32 {
+        self.dodge_refresh_time
+    }
+
+    pub fn player_stats(&self) -> PlayerStats {
+        PlayerStats {
+            max_speed: self.player_stats.max_speed / GGRS_FPS as f32,
+            ..self.player_stats.clone()
+        }
+    }
+
+    /// Values that would break the game, like planes that can't get faster than the minimum speed.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.player_stats.max_speed <= self.min_speed {
+            return Err(format!(
+                "player_stats.max_speed ({}) must be greater than min_speed ({})",
+                self.player_stats.max_speed, self.min_speed
+            ));
+        }
+        Ok(())
+    }
+
+    /// A hash that is the same on all platforms and builds (unlike `DefaultHasher`),
+    /// peers compare it before a match to make sure they use the same balance.
+    pub fn hash(&self) -> u64 {
+        // FNV-1a, floats are formatted as their shortest exact representation
+        format!("{:?}", self)
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+}
+
+/// Copy the asset into the resource.
+/// This only happens in the main menu so that the balance can't change during a match.
+/// An invalid balance is rejected, reloads keep the previous one.
+fn load_balance(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    balances: Res<Assets<Balance>>,
+    current: Option<Res<Balance>>,
+) {
+    let Some(balance) = balances.get(&assets.balance) else {
+        error!("balance asset is not loaded");
+        return;
+    };
+    if let Err(err) = balance.validate() {
+        if current.is_none() {
+            panic!("invalid balance, {}", err);
+        }
+        error!("invalid balance, keeping the previous one, {}", err);
+        return;
+    }
+
+    info!("loaded balance, hash {:x}", balance.hash());
+    commands.insert_resource(balance.clone());
+}
+
+fn reload_balance(
+    commands: Commands,
+    assets: Res<GameAssets>,
+    balances: Res<Assets<Balance>>,
+    current: Option<Res<Balance>>,
+    mut ev_asset: EventReader<AssetEvent<Balance>>,
+) {
+    let modified = ev_asset
+        .iter()
+        .any(|ev| matches!(ev, AssetEvent::Modified { handle } if *handle == assets.balance));
+    if modified {
+        load_balance(commands, assets, balances, current);
+    }
+}
+
+pub struct BalancePlugin;
+
+impl Plugin for BalancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::MainMenu), load_balance)
+            .add_systems(Update, reload_balance.run_if(in_state(GameState::MainMenu)));
+    }
+}
@@ -2,7 +2,9 @@ use std::hash::{Hash, Hasher};
 
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
+use bevy_ggrs::{GgrsSchedule, Rollback};
 
+use crate::player::InGameSet;
 use crate::{GameState, RollbackState};
 
 #[derive(Reflect, Component, Default)]
@@ -28,7 +30,8 @@ impl Plugin for AceDebugPlugin {
                 print_mouse_transform.run_if(in_state(GameState::InRollbackGame)),
             ),
         )
-        .add_systems(OnExit(GameState::Matchmaking), setup_mouse_tracking);
+        .add_systems(OnExit(GameState::Matchmaking), setup_mouse_tracking)
+        .add_systems(GgrsSchedule, check_debug_transforms.after(InGameSet::Last));
     }
 }
 
@@ -73,6 +76,26 @@ impl DebugTransform {
     }
 }
 
+/// Every rollback entity with a position (except UI nodes, they depend on the window)
+/// mirrors it in a `DebugTransform`, so that the position is part of the checksum.
+fn check_debug_transforms(
+    missing: Query<
+        Entity,
+        (
+            With<Rollback>,
+            With<Transform>,
+            Without<Node>,
+            Without<DebugTransform>,
+        ),
+    >,
+) {
+    debug_assert!(
+        missing.is_empty(),
+        "rollback entities without a DebugTransform, {:?}",
+        missing.iter().collect::<Vec<_>>()
+    );
+}
+
 fn debug_state_main_menu() {
     error!("the rollbackstate is not in setup. This is most likely caused by rollingback nextstate calls");
 }
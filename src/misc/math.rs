@@ -0,0 +1,249 @@
+//! Math for the rollback simulation that gives the exact same bits on every platform.
+//!
+//! The trigonometric functions of `std` call into the platform's libm and `glam` uses SIMD
+//! on some targets only, so their results can differ between peers in the last bits.
+//! Everything in here only uses the basic IEEE operations (`+`, `-`, `*`, `/`),
+//! which are correctly rounded everywhere.
+//!
+//! Planes and projectiles only rotate around the z axis (and dodges roll around the x axis).
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_6, PI};
+
+use bevy::prelude::*;
+
+// pi / 2 split into a part with trailing zeros and the rest (from fdlibm),
+// multiples of the first part are exact
+const FRAC_PI_2_HI: f64 = 1.570_796_326_734_125_6;
+const FRAC_PI_2_LO: f64 = 6.077_100_506_506_192e-11;
+const SQRT_3: f64 = 1.732_050_807_568_877_2;
+// tan(pi / 12)
+const TAN_FRAC_PI_12: f64 = 0.267_949_192_431_122_7;
+
+/// Taylor series around 0, accurate for |x| <= pi / 4.
+fn sin_cos_reduced(x: f64) -> (f64, f64) {
+    let x2 = x * x;
+
+    let mut term = x;
+    let mut sin = x;
+    for k in 1..=8 {
+        term *= -x2 / ((2 * k) * (2 * k + 1)) as f64;
+        sin += term;
+    }
+
+    let mut term = 1.0;
+    let mut cos = 1.0;
+    for k in 1..=9 {
+        term *= -x2 / ((2 * k - 1) * (2 * k)) as f64;
+        cos += term;
+    }
+    (sin, cos)
+}
+
+pub fn sin_cos(angle: f32) -> (f32, f32) {
+    if !angle.is_finite() {
+        return (f32::NAN, f32::NAN);
+    }
+
+    let angle = angle as f64;
+    let quadrant = (angle / FRAC_PI_2).round();
+    let reduced = angle - quadrant * FRAC_PI_2_HI - quadrant * FRAC_PI_2_LO;
+    let (sin, cos) = sin_cos_reduced(reduced);
+    let (sin, cos) = match (quadrant as i64).rem_euclid(4) {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    };
+    (sin as f32, cos as f32)
+}
+
+fn atan(t: f64) -> f64 {
+    let (t, sign) = if t < 0.0 { (-t, -1.0) } else { (t, 1.0) };
+    // atan(t) = pi / 2 - atan(1 / t)
+    let (t, inverted) = if t > 1.0 { (1.0 / t, true) } else { (t, false) };
+    // atan(t) = pi / 6 + atan((t * sqrt(3) - 1) / (t + sqrt(3)))
+    let (t, offset) = if t > TAN_FRAC_PI_12 {
+        ((t * SQRT_3 - 1.0) / (t + SQRT_3), FRAC_PI_6)
+    } else {
+        (t, 0.0)
+    };
+
+    // Taylor series, |t| <= tan(pi / 12)
+    let t2 = t * t;
+    let mut term = t;
+    let mut series = t;
+    for k in 1..=14 {
+        term *= -t2;
+        series += term / (2 * k + 1) as f64;
+    }
+
+    let result = offset + series;
+    let result = if inverted { FRAC_PI_2 - result } else { result };
+    sign * result
+}
+
+pub fn atan2(y: f32, x: f32) -> f32 {
+    if x.is_nan() || y.is_nan() {
+        return f32::NAN;
+    }
+
+    let (y, x) = (y as f64, x as f64);
+    let angle = if x > 0.0 {
+        atan(y / x)
+    } else if x < 0.0 {
+        if y >= 0.0 {
+            atan(y / x) + PI
+        } else {
+            atan(y / x) - PI
+        }
+    } else if y > 0.0 {
+        FRAC_PI_2
+    } else if y < 0.0 {
+        -FRAC_PI_2
+    } else {
+        0.0
+    };
+    angle as f32
+}
+
+/// Same as `f32::powi`, which isn't guaranteed to give the same result everywhere.
+pub fn powi(x: f32, n: u32) -> f32 {
+    let mut result = 1.0;
+    for _ in 0..n {
+        result *= x;
+    }
+    result
+}
+
+/// Same as `Vec2::from_angle`.
+pub fn from_angle(angle: f32) -> Vec2 {
+    let (sin, cos) = sin_cos(angle);
+    Vec2::new(cos, sin)
+}
+
+/// The signed angle from `a` to `b`, in `[-pi, pi]`.
+pub fn angle_between(a: Vec2, b: Vec2) -> f32 {
+    atan2(a.x * b.y - a.y * b.x, a.x * b.x + a.y * b.y)
+}
+
+pub fn rotation_z(angle: f32) -> Quat {
+    let (sin, cos) = sin_cos(angle * 0.5);
+    Quat::from_xyzw(0.0, 0.0, sin, cos)
+}
+
+pub fn rotation_x(angle: f32) -> Quat {
+    let (sin, cos) = sin_cos(angle * 0.5);
+    Quat::from_xyzw(sin, 0.0, 0.0, cos)
+}
+
+/// Same as `a * b`.
+pub fn mul_quat(a: Quat, b: Quat) -> Quat {
+    Quat::from_xyzw(
+        a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+        a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+        a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+    )
+}
+
+/// Same as `rotation * v`.
+pub fn rotate(rotation: Quat, v: Vec3) -> Vec3 {
+    let (x, y, z, w) = (rotation.x, rotation.y, rotation.z, rotation.w);
+    // t = 2 * cross(xyz, v), the result is v + w * t + cross(xyz, t)
+    let tx = 2.0 * (y * v.z - z * v.y);
+    let ty = 2.0 * (z * v.x - x * v.z);
+    let tz = 2.0 * (x * v.y - y * v.x);
+    Vec3::new(
+        v.x + w * tx + (y * tz - z * ty),
+        v.y + w * ty + (z * tx - x * tz),
+        v.z + w * tz + (x * ty - y * tx),
+    )
+}
+
+/// Same as `transform.local_x()`, the direction the plane (or projectile) is facing.
+pub fn forward(transform: &Transform) -> Vec3 {
+    rotate(transform.rotation, Vec3::X)
+}
+
+/// Same as `transform.rotate_z(angle)`.
+pub fn rotate_z(transform: &mut Transform, angle: f32) {
+    transform.rotation = mul_quat(rotation_z(angle), transform.rotation);
+}
+
+/// Same as `transform.rotate_local_x(angle)`.
+pub fn rotate_local_x(transform: &mut Transform, angle: f32) {
+    transform.rotation = mul_quat(transform.rotation, rotation_x(angle));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The expected bits are the correctly rounded results,
+    // if any of these change the simulation isn't compatible with older builds anymore.
+
+    #[test]
+    fn sin_cos_bits() {
+        let cases = [
+            (0.5, 0x3ef5_7744, 0x3f60_a940),
+            (1.0, 0x3f57_6aa4, 0x3f0a_5140),
+            (-2.5, 0xbf19_3578, 0xbf4d_17bf),
+            (3.0, 0x3e10_81c3, 0xbf7d_7026),
+            (100.0, 0xbf01_a12e, 0x3f5c_c0ee),
+        ];
+        for (angle, sin, cos) in cases {
+            let (s, c) = sin_cos(angle);
+            assert_eq!((s.to_bits(), c.to_bits()), (sin, cos), "sin_cos({})", angle);
+        }
+    }
+
+    #[test]
+    fn atan2_bits() {
+        let cases = [
+            (1.0, 1.0, 0x3f49_0fdb),
+            (1.0, -2.0, 0x402b_6374),
+            (-3.0, 0.5, 0xbfb3_ec43),
+            (0.0, -1.0, 0x4049_0fdb),
+            (-0.25, -4.0, 0xc045_112f),
+        ];
+        for (y, x, expected) in cases {
+            assert_eq!(atan2(y, x).to_bits(), expected, "atan2({}, {})", y, x);
+        }
+        assert_eq!(atan2(0.0, 0.0), 0.0);
+        assert!(atan2(f32::NAN, 1.0).is_nan());
+    }
+
+    #[test]
+    fn powi_bits() {
+        assert_eq!(powi(1.1, 3).to_bits(), 0x3faa_5e36);
+        assert_eq!(powi(-3.0, 0), 1.0);
+    }
+
+    #[test]
+    fn rotation_bits() {
+        let rotation = rotation_z(1.0);
+        assert_eq!(rotation.z.to_bits(), 0x3ef5_7744);
+        assert_eq!(rotation.w.to_bits(), 0x3f60_a940);
+
+        let direction = rotate(rotation, Vec3::X);
+        assert_eq!(
+            (
+                direction.x.to_bits(),
+                direction.y.to_bits(),
+                direction.z.to_bits()
+            ),
+            (0x3f0a_5140, 0x3f57_6aa5, 0)
+        );
+
+        let angle = angle_between(Vec2::X, Vec2::new(0.6, 0.8));
+        assert_eq!(angle.to_bits(), 0x3f6d_6338);
+    }
+
+    #[test]
+    fn rotate_matches_glam() {
+        let rotation = mul_quat(rotation_z(0.7), rotation_x(1.3));
+        let v = Vec3::new(3.0, -2.0, 0.5);
+        assert!((rotate(rotation, v) - rotation * v).length() < 1e-5);
+        assert!(mul_quat(rotation, rotation).abs_diff_eq(rotation * rotation, 1e-6));
+    }
+}
@@ -1,6 +1,13 @@
+pub mod balance;
 pub mod debug;
 pub mod log;
+pub mod math;
+pub mod settings;
+pub mod snapshot;
 pub mod utils;
 
+pub use balance::Balance;
+
 pub type GameRng = rand_xoshiro::Xoshiro256PlusPlus;
+pub use settings::Settings;
 pub use utils::DeadSprite;
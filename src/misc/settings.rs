@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+
+use crate::world::RulesProposal;
+
+/// Local preferences of the player.
+/// Only the rules affect the game logic, they are agreed on with the peer
+/// when the match starts (see `MatchRules`).
+#[derive(Resource)]
+pub struct Settings {
+    pub missile_warning: bool,
+    pub rules: RulesProposal,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            missile_warning: true,
+            rules: RulesProposal::default(),
+        }
+    }
+}
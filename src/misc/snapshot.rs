@@ -0,0 +1,444 @@
+//! Snapshots of the rollback state, to reproduce edge cases without replaying a whole match.
+//!
+//! A snapshot contains every rollback component of the entities that aren't UI or trails
+//! (those are spawned together with their plane or projectile) and every rollback resource,
+//! loading it starts a local session that resumes from that state.
+//! Both players are local in that session, only the first one is controlled.
+
+use std::any::{type_name, TypeId};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::ecs::system::CommandQueue;
+use bevy::prelude::*;
+use bevy::reflect::TypeRegistry;
+use bevy::scene::serde::SceneDeserializer;
+use bevy::scene::{DynamicEntity, DynamicScene, DynamicSceneBuilder, SceneFilter};
+use bevy::time::Stopwatch;
+use bevy_console::PrintConsoleLine;
+use bevy_ggrs::{ggrs::PlayerType, *};
+use bevy_hanabi::EffectAsset;
+use serde::de::DeserializeSeed;
+
+use crate::audio::RollbackSound;
+use crate::debug::DebugTransform;
+use crate::network::{ggrs_config::PLAYER_COUNT, rollback::RollbackTypes, GgrsConfig};
+use crate::player::effect::trail::{spawn_trail_effect, Trail};
+use crate::player::perk::Perk;
+use crate::player::shooting::bullet::{bullet_visuals, Bullet};
+use crate::player::shooting::flare::{flare_visuals, Flare};
+use crate::player::shooting::rocket::{rocket_visuals, Rocket};
+use crate::player::shooting::rocket_explosion::{rocket_explosion_visuals, RocketExplosion};
+use crate::player::{LocalPlayerHandle, PersistentPlayerStats, PlayerStats};
+use crate::world::{MatchRules, Seed, SeedHandle, Seeds};
+use crate::{GameAssets, GameState, RollbackState};
+
+const SNAPSHOT_DIR: &str = "snapshots";
+
+/// The state of a match at one frame, together with the setup of the match
+/// that isn't part of the rollback state.
+pub struct Snapshot {
+    seed: u32,
+    match_rules: MatchRules,
+    scene: DynamicScene,
+}
+
+/// The snapshot that the local session resumes from once the round is set up.
+#[derive(Resource)]
+pub struct PendingSnapshot(pub Snapshot);
+
+#[derive(Event)]
+pub struct SaveSnapshot {
+    pub name: String,
+}
+
+fn snapshot_path(name: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(format!(
+            "invalid snapshot name '{}', only use letters, digits, '_' and '-'",
+            name
+        ));
+    }
+    Ok(PathBuf::from(SNAPSHOT_DIR).join(format!("{}.snapshot", name)))
+}
+
+/// Rollback components that are left out, they are either UI, derived from other components
+/// or transient sound effects.
+fn saved_components(rollback_types: &RollbackTypes) -> Vec<TypeId> {
+    let skipped = [
+        TypeId::of::<Style>(),
+        TypeId::of::<DebugTransform>(),
+        TypeId::of::<RollbackSound>(),
+    ];
+    rollback_types
+        .components
+        .iter()
+        .copied()
+        .filter(|type_id| !skipped.contains(type_id))
+        .collect()
+}
+
+impl Snapshot {
+    /// The file starts with the seed and the match rules, followed by the scene in RON.
+    fn to_file(&self, registry: &AppTypeRegistry) -> Result<String, String> {
+        let scene = self
+            .scene
+            .serialize_ron(&registry.0)
+            .map_err(|e| e.to_string())?;
+        Ok(format!(
+            "seed {}\nrules {}\n{}",
+            self.seed, self.match_rules, scene
+        ))
+    }
+
+    pub fn load(name: &str, registry: &TypeRegistry) -> Result<Snapshot, String> {
+        let path = snapshot_path(name)?;
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read '{}', {}", path.display(), e))?;
+
+        let mut lines = content.splitn(3, '\n');
+        let seed = lines
+            .next()
+            .and_then(|line| line.strip_prefix("seed "))
+            .and_then(|seed| seed.parse().ok())
+            .ok_or("the snapshot doesn't start with the seed")?;
+        let match_rules = lines
+            .next()
+            .and_then(|line| line.strip_prefix("rules "))
+            .and_then(MatchRules::from_str)
+            .ok_or("the second line of the snapshot isn't the match rules")?;
+
+        let mut deserializer = ron::de::Deserializer::from_str(lines.next().unwrap_or_default())
+            .map_err(|e| e.to_string())?;
+        let scene = SceneDeserializer {
+            type_registry: registry,
+        }
+        .deserialize(&mut deserializer)
+        .map_err(|e| format!("failed to parse '{}', {}", path.display(), e))?;
+
+        Ok(Snapshot {
+            seed,
+            match_rules,
+            scene,
+        })
+    }
+}
+
+fn extract_snapshot(world: &mut World) -> Snapshot {
+    let rollback_types = world.resource::<RollbackTypes>();
+    let component_filter = saved_components(rollback_types)
+        .into_iter()
+        .fold(SceneFilter::deny_all(), SceneFilter::allow_by_id);
+    let resource_filter = rollback_types
+        .resources
+        .iter()
+        .copied()
+        .chain([TypeId::of::<PersistentPlayerStats>()])
+        .fold(SceneFilter::deny_all(), SceneFilter::allow_by_id);
+
+    let mut rollbacks =
+        world.query_filtered::<(Entity, &Rollback), (Without<Node>, Without<Trail>)>();
+    let rollback_ids: HashMap<Entity, u32> = rollbacks
+        .iter(world)
+        .map(|(entity, rollback)| (entity, rollback.id()))
+        .collect();
+
+    let mut builder = DynamicSceneBuilder::from_world(world);
+    builder
+        .with_filter(component_filter)
+        .with_resource_filter(resource_filter)
+        .extract_entities(rollback_ids.keys().copied())
+        .extract_resources();
+    let mut scene = builder.build();
+
+    // Entities are identified by their rollback id, it gives the order in which they were spawned
+    scene
+        .entities
+        .retain(|dynamic_entity| !dynamic_entity.components.is_empty());
+    for dynamic_entity in &mut scene.entities {
+        dynamic_entity.entity = Entity::from_raw(rollback_ids[&dynamic_entity.entity]);
+    }
+
+    Snapshot {
+        seed: world.resource::<Seed>().seed as u32,
+        match_rules: world.resource::<MatchRules>().clone(),
+        scene,
+    }
+}
+
+fn save_snapshot(world: &mut World, name: &str) -> Result<PathBuf, String> {
+    let path = snapshot_path(name)?;
+    let snapshot = extract_snapshot(world);
+    let content = snapshot.to_file(world.resource::<AppTypeRegistry>())?;
+
+    fs::create_dir_all(SNAPSHOT_DIR).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+pub fn save_snapshots(world: &mut World) {
+    let requests: Vec<SaveSnapshot> = world
+        .resource_mut::<Events<SaveSnapshot>>()
+        .drain()
+        .collect();
+    for request in requests {
+        let line = match save_snapshot(world, &request.name) {
+            Ok(path) => {
+                info!("saved snapshot {}", path.display());
+                format!("saved snapshot to {}", path.display())
+            }
+            Err(err) => {
+                error!("failed to save snapshot '{}', {}", request.name, err);
+                format!("failed to save snapshot, {}", err)
+            }
+        };
+        world.send_event(PrintConsoleLine::new(line.into()));
+    }
+}
+
+fn write_resources<'a>(
+    world: &mut World,
+    registry: &TypeRegistry,
+    resources: impl Iterator<Item = &'a Box<dyn Reflect>>,
+) {
+    for resource in resources {
+        let Some(reflect_resource) = registry
+            .get_with_name(resource.type_name())
+            .and_then(|registration| registration.data::<ReflectResource>())
+        else {
+            warn!("unknown resource '{}' in snapshot", resource.type_name());
+            continue;
+        };
+        reflect_resource.apply_or_insert(world, &**resource);
+    }
+}
+
+/// Start a local session with the setup of the snapshot,
+/// the round that gets set up is the one of the snapshot.
+pub fn start_snapshot_session(world: &mut World) {
+    // Without rollbacks, they would undo the snapshot
+    let mut session_builder = GgrsConfig::new_builder().with_check_distance(0);
+    for handle in 0..PLAYER_COUNT {
+        session_builder = session_builder
+            .add_player(PlayerType::Local, handle)
+            .expect("failed to add player");
+    }
+    let session = session_builder
+        .start_synctest_session()
+        .expect("failed to start session");
+    world.insert_resource(Session::SyncTest(session));
+    world.insert_resource(LocalPlayerHandle(0));
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    world.resource_scope(|world, pending: Mut<PendingSnapshot>| {
+        let snapshot = &pending.0;
+        world.resource_mut::<Seeds>().0 = (0..PLAYER_COUNT)
+            .map(|_| SeedHandle {
+                handle: None,
+                seed: snapshot.seed,
+            })
+            .collect();
+        world.insert_resource(snapshot.match_rules.clone());
+
+        // The map depends on the rounds played and the score,
+        // the state itself has to go through the round start first
+        let state = type_name::<State<RollbackState>>();
+        write_resources(
+            world,
+            &registry,
+            snapshot
+                .scene
+                .resources
+                .iter()
+                .filter(|resource| resource.type_name() != state),
+        );
+    });
+
+    info!("starting local session from snapshot");
+    world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::InRollbackGame);
+    world
+        .resource_mut::<NextState<RollbackState>>()
+        .set(RollbackState::RoundStart);
+}
+
+/// Spawn an entity that only exists in the snapshot (projectiles and explosions),
+/// with the visuals and the trail it gets when it's spawned normally.
+fn spawn_rollback_entity(world: &mut World, dynamic_entity: &DynamicEntity) -> Entity {
+    let component = |name: &str| {
+        dynamic_entity
+            .components
+            .iter()
+            .find(|component| component.type_name() == name)
+    };
+    let transform = component(type_name::<Transform>())
+        .and_then(|transform| Transform::from_reflect(&**transform))
+        .unwrap_or_default();
+
+    let mut queue = CommandQueue::default();
+    let entity = world.resource_scope(|world, mut effects: Mut<Assets<EffectAsset>>| {
+        let mut commands = Commands::new(&mut queue, world);
+        let assets = world.resource::<GameAssets>();
+        let mut entity_commands = commands.spawn(DebugTransform::new(&transform));
+        let mut trail = false;
+        if component(type_name::<Bullet>()).is_some() {
+            entity_commands.insert(bullet_visuals(assets, transform));
+        } else if let Some(rocket) =
+            component(type_name::<Rocket>()).and_then(|rocket| Rocket::from_reflect(&**rocket))
+        {
+            entity_commands.insert(rocket_visuals(assets, rocket.handle, transform));
+            trail = true;
+        } else if component(type_name::<Flare>()).is_some() {
+            entity_commands.insert(flare_visuals(assets, transform));
+            trail = true;
+        } else if component(type_name::<RocketExplosion>()).is_some() {
+            entity_commands.insert(rocket_explosion_visuals(assets, transform));
+        } else {
+            entity_commands.insert(SpatialBundle::from_transform(transform));
+        }
+        let entity = entity_commands.add_rollback().id();
+
+        if trail {
+            let trail_effect = spawn_trail_effect(&mut commands, &mut effects, Vec3::ZERO);
+            commands.entity(entity).push_children(&[trail_effect]);
+        }
+        entity
+    });
+    queue.apply(world);
+    entity
+}
+
+/// Replace the state of the round that was just set up with the one of the snapshot.
+///
+/// Entities of the round are matched with the ones of the snapshot by their components,
+/// in the order they were spawned in. This works because the same map gets spawned
+/// the same way, only projectiles and explosions get spawned and despawned.
+pub fn apply_snapshot(world: &mut World) {
+    if *world.resource::<State<RollbackState>>().get() == RollbackState::Setup {
+        return;
+    }
+    let Some(PendingSnapshot(snapshot)) = world.remove_resource::<PendingSnapshot>() else {
+        return;
+    };
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    write_resources(world, &registry, snapshot.scene.resources.iter());
+
+    let saved_components = saved_components(world.resource::<RollbackTypes>());
+    let mut rollbacks =
+        world.query_filtered::<(Entity, &Rollback), (Without<Node>, Without<Trail>)>();
+    let mut entities: Vec<(u32, Entity)> = rollbacks
+        .iter(world)
+        .map(|(entity, rollback)| (rollback.id(), entity))
+        .collect();
+    entities.sort_unstable();
+
+    let mut existing: HashMap<Vec<String>, VecDeque<Entity>> = HashMap::new();
+    for (_, entity) in entities {
+        let entity_ref = world.entity(entity);
+        let mut signature: Vec<String> = saved_components
+            .iter()
+            .filter(|type_id| entity_ref.contains_type_id(**type_id))
+            .filter_map(|type_id| registry.get(*type_id))
+            .map(|registration| registration.type_name().to_string())
+            .collect();
+        if signature.is_empty() {
+            continue;
+        }
+        signature.sort_unstable();
+        existing.entry(signature).or_default().push_back(entity);
+    }
+
+    let mut dynamic_entities: Vec<&DynamicEntity> = snapshot.scene.entities.iter().collect();
+    dynamic_entities.sort_unstable_by_key(|dynamic_entity| dynamic_entity.entity.index());
+
+    let mut spawned = 0;
+    for dynamic_entity in dynamic_entities {
+        let mut signature: Vec<String> = dynamic_entity
+            .components
+            .iter()
+            .map(|component| component.type_name().to_string())
+            .collect();
+        signature.sort_unstable();
+
+        let entity = match existing.get_mut(&signature).and_then(VecDeque::pop_front) {
+            Some(entity) => entity,
+            None => {
+                spawned += 1;
+                spawn_rollback_entity(world, dynamic_entity)
+            }
+        };
+
+        let mut entity_mut = world.entity_mut(entity);
+        for component in &dynamic_entity.components {
+            let Some(reflect_component) = registry
+                .get_with_name(component.type_name())
+                .and_then(|registration| registration.data::<ReflectComponent>())
+            else {
+                warn!("unknown component '{}' in snapshot", component.type_name());
+                continue;
+            };
+            reflect_component.apply_or_insert(&mut entity_mut, &**component);
+        }
+        // Not part of the snapshot, mirror the restored transform
+        if let Some(transform) = entity_mut.get::<Transform>().copied() {
+            entity_mut.insert(DebugTransform::new(&transform));
+        }
+    }
+
+    let mut despawned = 0;
+    for entity in existing.into_values().flatten() {
+        despawned += 1;
+        world.entity_mut(entity).despawn_recursive();
+    }
+    info!(
+        "resumed from snapshot, spawned {} and despawned {} entities",
+        spawned, despawned
+    );
+}
+
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        // The types inside of the rollback components and resources,
+        // needed to read them back from a snapshot
+        app.register_type::<PersistentPlayerStats>()
+            .register_type::<PlayerStats>()
+            .register_type::<[PlayerStats; PLAYER_COUNT]>()
+            .register_type::<Perk>()
+            .register_type::<Vec<Perk>>()
+            .register_type::<[Vec<Perk>; PLAYER_COUNT]>()
+            .register_type::<Option<Vec3>>()
+            .register_type::<Option<usize>>()
+            .register_type::<Option<u32>>()
+            .register_type::<[u32; PLAYER_COUNT]>()
+            .register_type::<[Option<usize>; PLAYER_COUNT]>()
+            .register_type::<[Option<u32>; PLAYER_COUNT]>()
+            .register_type::<Timer>()
+            .register_type::<TimerMode>()
+            .register_type::<Stopwatch>()
+            .register_type::<RollbackState>()
+            .add_event::<SaveSnapshot>()
+            .add_systems(
+                Update,
+                (
+                    save_snapshots.run_if(in_state(GameState::InRollbackGame)),
+                    start_snapshot_session
+                        .run_if(in_state(GameState::Matchmaking))
+                        .run_if(resource_exists::<PendingSnapshot>()),
+                    apply_snapshot
+                        .run_if(in_state(GameState::InRollbackGame))
+                        .run_if(resource_exists::<PendingSnapshot>()),
+                ),
+            );
+    }
+}
@@ -1,12 +1,18 @@
+use std::hash::{Hash, Hasher};
+
 use bevy::prelude::*;
 
+use super::math;
+
+/// The rotation that points the x axis in `direction`.
 pub fn quat_from_vec3(direction: Vec3) -> Quat {
-    Quat::from_euler(
-        EulerRot::XYZ,
-        0.0,
-        0.0,
-        Vec2::X.angle_between(direction.truncate()),
-    )
+    math::rotation_z(math::atan2(direction.y, direction.x))
+}
+
+/// Timers aren't `Hash`, the elapsed time is what diverges between peers.
+pub fn hash_timer<H: Hasher>(timer: &Timer, state: &mut H) {
+    timer.duration().hash(state);
+    timer.elapsed().hash(state);
 }
 
 #[derive(Component)]
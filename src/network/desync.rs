@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy_ggrs::ggrs::PlayerType;
+use bevy_ggrs::Rollback;
+use chrono::Utc;
+
+use super::rollback::RollbackTypes;
+use super::socket::AceSocket;
+
+const DESYNC_DIRECTORY: &str = "desync";
+// Desyncs are only detected every few frames and the checksums of our peer arrive late
+const HISTORY_FRAMES: usize = 128;
+// Keep the reliable messages small
+const ENTRIES_PER_MESSAGE: usize = 100;
+
+/// Number of frames that were simulated in this session,
+/// this matches the frames GGRS refers to in `DesyncDetected`.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct SimulationFrame(i32);
+
+#[derive(Event)]
+pub struct DesyncEvent {
+    pub frame: i32,
+}
+
+/// A message on the reliable channel that isn't meant for the desync report.
+#[derive(Event)]
+pub struct ReliableMessage {
+    pub message: String,
+}
+
+/// The hash of one component of a rollback entity, or of a rollback resource.
+struct ChecksumEntry {
+    /// The rollback id of the entity, `None` for resources
+    rollback: Option<u32>,
+    type_name: &'static str,
+    hash: u64,
+}
+
+impl ChecksumEntry {
+    fn line(&self) -> String {
+        match self.rollback {
+            Some(id) => format!("entity {} {}\t{:016x}", id, self.type_name, self.hash),
+            None => format!("resource {}\t{:016x}", self.type_name, self.hash),
+        }
+    }
+}
+
+/// The checksums of the last simulated frames, newest last.
+#[derive(Resource, Default)]
+pub struct ChecksumHistory(VecDeque<(i32, Vec<ChecksumEntry>)>);
+
+impl ChecksumHistory {
+    fn insert(&mut self, frame: i32, entries: Vec<ChecksumEntry>) {
+        // After a rollback the frames get simulated again
+        self.0.retain(|(f, _)| *f < frame);
+        self.0.push_back((frame, entries));
+        while self.0.len() > HISTORY_FRAMES {
+            self.0.pop_front();
+        }
+    }
+
+    fn lines(&self, frame: i32) -> Option<Vec<String>> {
+        self.0
+            .iter()
+            .find(|(f, _)| *f == frame)
+            .map(|(_, entries)| entries.iter().map(ChecksumEntry::line).collect())
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct DesyncReport {
+    /// Prefix of the files of this session
+    name: String,
+    /// Once the state diverged all following checksums differ too, we only report the first one
+    reported: bool,
+    /// Checksums that our peer is still sending, by frame
+    remote: HashMap<i32, Vec<String>>,
+}
+
+impl DesyncReport {
+    fn write(&self, frame: i32, suffix: &str, lines: &[String]) -> PathBuf {
+        let path = PathBuf::from(DESYNC_DIRECTORY)
+            .join(format!("{}-frame-{}-{}.txt", self.name, frame, suffix));
+        let result = fs::create_dir_all(DESYNC_DIRECTORY)
+            .and_then(|_| fs::write(&path, lines.join("\n") + "\n"));
+        match result {
+            Ok(()) => info!("wrote desync report {}", path.display()),
+            Err(err) => error!("failed to write {}, {}", path.display(), err),
+        }
+        path
+    }
+}
+
+/// Send the message to all remote players over the reliable channel.
+pub(crate) fn send_to_remotes(socket: &mut AceSocket, message: &str) {
+    for player in socket.players() {
+        if let PlayerType::Remote(peer_id) = player {
+            socket.send_tcp_message(peer_id, message);
+        }
+    }
+}
+
+/// Compare the checksum lines of both peers, returns one line per divergent component.
+fn diff_checksums(local: &[String], remote: &[String]) -> Vec<String> {
+    let parse = |lines: &[String]| -> BTreeMap<String, String> {
+        lines
+            .iter()
+            .filter_map(|line| line.rsplit_once('\t'))
+            .map(|(key, hash)| (key.to_string(), hash.to_string()))
+            .collect()
+    };
+    let local = parse(local);
+    let remote = parse(remote);
+
+    let mut diff = Vec::new();
+    for (key, hash) in &local {
+        match remote.get(key) {
+            Some(remote_hash) if remote_hash == hash => {}
+            Some(remote_hash) => {
+                diff.push(format!("{}: local {} remote {}", key, hash, remote_hash))
+            }
+            None => diff.push(format!("{}: only local", key)),
+        }
+    }
+    for key in remote.keys().filter(|key| !local.contains_key(*key)) {
+        diff.push(format!("{}: only remote", key));
+    }
+    diff
+}
+
+/// Hash the rollback components (see `RollbackTypes`) of every rollback entity
+/// and the rollback resources, the same way `bevy_ggrs` builds its checksum.
+///
+/// This runs before the rest of the frame, so the state is the one GGRS saved
+/// (and computed the checksum of) after the previous frame.
+pub fn record_checksums(world: &mut World) {
+    let frame = world.resource::<SimulationFrame>().0;
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    let rollback_types = world.resource::<RollbackTypes>();
+    let components = rollback_types.components.clone();
+    let resources = rollback_types.resources.clone();
+
+    let mut entries = Vec::new();
+    let mut rollbacks = world.query::<(Entity, &Rollback)>();
+    for (entity, rollback) in rollbacks.iter(world) {
+        let entity_ref = world.entity(entity);
+        for type_id in &components {
+            let Some(registration) = registry.get(*type_id) else {
+                continue;
+            };
+            let Some(hash) = registration
+                .data::<ReflectComponent>()
+                .and_then(|reflect_component| reflect_component.reflect(entity_ref))
+                .and_then(|component| component.reflect_hash())
+            else {
+                continue;
+            };
+            entries.push(ChecksumEntry {
+                rollback: Some(rollback.id()),
+                type_name: registration.type_name(),
+                hash,
+            });
+        }
+    }
+
+    for type_id in &resources {
+        let Some(registration) = registry.get(*type_id) else {
+            continue;
+        };
+        let Some(hash) = registration
+            .data::<ReflectResource>()
+            .and_then(|reflect_resource| reflect_resource.reflect(world))
+            .and_then(|resource| resource.reflect_hash())
+        else {
+            continue;
+        };
+        entries.push(ChecksumEntry {
+            rollback: None,
+            type_name: registration.type_name(),
+            hash,
+        });
+    }
+
+    world
+        .resource_mut::<ChecksumHistory>()
+        .insert(frame, entries);
+    world.resource_mut::<SimulationFrame>().0 += 1;
+}
+
+/// Dump the checksums of the frame that diverged and send them to our peer.
+pub fn report_desync(
+    mut socket: ResMut<AceSocket>,
+    history: Res<ChecksumHistory>,
+    mut report: ResMut<DesyncReport>,
+    mut ev_desync: EventReader<DesyncEvent>,
+) {
+    for ev in ev_desync.iter() {
+        if report.reported {
+            continue;
+        }
+        report.reported = true;
+
+        let Some(lines) = history.lines(ev.frame) else {
+            error!(
+                "the checksums of frame {} aren't recorded anymore",
+                ev.frame
+            );
+            continue;
+        };
+        report.write(ev.frame, "local", &lines);
+
+        for chunk in lines.chunks(ENTRIES_PER_MESSAGE) {
+            send_to_remotes(
+                &mut socket,
+                &format!("desync {}\n{}", ev.frame, chunk.join("\n")),
+            );
+        }
+        send_to_remotes(&mut socket, &format!("desync_end {}", ev.frame));
+    }
+}
+
+fn receive_remote_checksums(
+    report: &mut DesyncReport,
+    history: &ChecksumHistory,
+    message: &str,
+) -> bool {
+    if let Some((frame, lines)) = message
+        .strip_prefix("desync ")
+        .and_then(|rest| rest.split_once('\n'))
+    {
+        let Ok(frame) = frame.parse::<i32>() else {
+            warn!("desync message failed to parse");
+            return true;
+        };
+        report
+            .remote
+            .entry(frame)
+            .or_default()
+            .extend(lines.lines().map(str::to_string));
+        return true;
+    }
+
+    let Some(frame) = message.strip_prefix("desync_end ") else {
+        return false;
+    };
+    let Some((frame, remote_lines)) = frame
+        .parse::<i32>()
+        .ok()
+        .and_then(|frame| Some((frame, report.remote.remove(&frame)?)))
+    else {
+        warn!("received the end of unknown checksums, '{}'", message);
+        return true;
+    };
+    report.write(frame, "remote", &remote_lines);
+
+    let Some(local_lines) = history.lines(frame) else {
+        error!(
+            "can't compare the checksums of our peer, frame {} isn't recorded anymore",
+            frame
+        );
+        return true;
+    };
+    let diff = diff_checksums(&local_lines, &remote_lines);
+    let path = report.write(frame, "diff", &diff);
+    error!(
+        "{} components diverged in frame {}, see {}",
+        diff.len(),
+        frame,
+        path.display()
+    );
+    for line in &diff {
+        error!("{}", line);
+    }
+    true
+}
+
+/// Handle the checksums of our peer, all other messages are passed on as `ReliableMessage`.
+pub fn route_reliable_messages(
+    mut socket: ResMut<AceSocket>,
+    history: Res<ChecksumHistory>,
+    mut report: ResMut<DesyncReport>,
+    mut ev_reliable_message: EventWriter<ReliableMessage>,
+) {
+    for (_, message) in socket.receive_tcp_message() {
+        if !receive_remote_checksums(&mut report, &history, &message) {
+            ev_reliable_message.send(ReliableMessage { message });
+        }
+    }
+}
+
+pub fn reset_desync_report(mut commands: Commands) {
+    commands.insert_resource(SimulationFrame::default());
+    commands.insert_resource(ChecksumHistory::default());
+    commands.insert_resource(DesyncReport {
+        name: Utc::now().format("%Y-%m-%d-%H-%M-%S").to_string(),
+        ..default()
+    });
+}
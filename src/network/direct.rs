@@ -0,0 +1,413 @@
+//! Direct connections over plain UDP, for LAN games without the matchbox and STUN/TURN servers.
+//!
+//! One player hosts on a port, the other joins with `ip:port`.
+//! GGRS messages and the reliable messages (seed, rules, commands, ...) share the socket,
+//! the reliable ones get resent until our peer acknowledges them
+//! (or until we give up on our peer, it disconnected then).
+
+use std::collections::{BTreeMap, VecDeque};
+use std::io::ErrorKind;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy_ggrs::ggrs::{self, PlayerType};
+use bevy_matchbox::prelude::PeerId;
+use bincode::{deserialize, serialize};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const RESEND_INTERVAL: Duration = Duration::from_millis(100);
+// Our peer is gone if it doesn't acknowledge a message after this many resends (5 seconds)
+const MAX_RESENDS: u32 = 50;
+const HELLO_INTERVAL: Duration = Duration::from_millis(250);
+// Large enough for any UDP datagram
+const RECEIVE_BUFFER_SIZE: usize = 65536;
+
+/// There is only ever one peer, it gets the same id on both sides.
+const DIRECT_PEER: PeerId = PeerId(Uuid::from_u128(1));
+
+#[derive(Serialize, Deserialize)]
+enum Packet {
+    /// The guest asks to join
+    Hello,
+    /// The host accepted the guest
+    Welcome,
+    Ggrs(ggrs::Message),
+    Reliable {
+        seq: u32,
+        message: String,
+    },
+    Ack {
+        seq: u32,
+    },
+}
+
+#[derive(Debug)]
+struct PendingMessage {
+    seq: u32,
+    message: String,
+    last_sent: Instant,
+    resends: u32,
+}
+
+/// Resends our reliable messages until they are acknowledged
+/// and delivers the ones of our peer in the order they were sent.
+#[derive(Debug, Default)]
+struct ReliableChannel {
+    /// Our reliable messages that weren't acknowledged yet
+    pending: VecDeque<PendingMessage>,
+    next_seq: u32,
+    /// Reliable messages of our peer that arrived before the ones sent earlier
+    out_of_order: BTreeMap<u32, String>,
+    next_expected: u32,
+    received: Vec<String>,
+}
+
+impl ReliableChannel {
+    /// The packet that carries the message to our peer.
+    fn send(&mut self, message: &str, now: Instant) -> Packet {
+        let pending = PendingMessage {
+            seq: self.next_seq,
+            message: message.to_string(),
+            last_sent: now,
+            resends: 0,
+        };
+        self.next_seq += 1;
+        let packet = Packet::Reliable {
+            seq: pending.seq,
+            message: pending.message.clone(),
+        };
+        self.pending.push_back(pending);
+        packet
+    }
+
+    /// Returns the ack for our peer, duplicates get acknowledged again but only delivered once.
+    fn receive(&mut self, seq: u32, message: String) -> Packet {
+        if seq >= self.next_expected {
+            self.out_of_order.insert(seq, message);
+        }
+        while let Some(message) = self.out_of_order.remove(&self.next_expected) {
+            self.received.push(message);
+            self.next_expected += 1;
+        }
+        Packet::Ack { seq }
+    }
+
+    fn acknowledge(&mut self, seq: u32) {
+        self.pending.retain(|pending| pending.seq != seq);
+    }
+
+    /// The packets of the messages that weren't acknowledged in time.
+    /// Once a message was resent `MAX_RESENDS` times we give up on all of them and return `None`.
+    fn resend(&mut self, now: Instant) -> Option<Vec<Packet>> {
+        if self
+            .pending
+            .iter()
+            .any(|pending| pending.resends >= MAX_RESENDS)
+        {
+            self.pending.clear();
+            return None;
+        }
+
+        let packets = self
+            .pending
+            .iter_mut()
+            .filter(|pending| now - pending.last_sent >= RESEND_INTERVAL)
+            .map(|pending| {
+                pending.last_sent = now;
+                pending.resends += 1;
+                Packet::Reliable {
+                    seq: pending.seq,
+                    message: pending.message.clone(),
+                }
+            })
+            .collect();
+        Some(packets)
+    }
+}
+
+#[derive(Debug)]
+pub struct DirectSocket {
+    socket: UdpSocket,
+    host: bool,
+    /// The host only knows the address of the guest once it said hello
+    peer: Option<SocketAddr>,
+    /// Where the guest sends its hello to
+    host_addr: Option<SocketAddr>,
+    connected: bool,
+    /// Our peer stopped acknowledging our messages, the socket stays closed
+    disconnected: bool,
+    last_hello: Option<Instant>,
+    ggrs_messages: Vec<ggrs::Message>,
+    reliable: ReliableChannel,
+    buffer: Vec<u8>,
+}
+
+impl DirectSocket {
+    fn new(socket: UdpSocket, host: bool, host_addr: Option<SocketAddr>) -> Self {
+        Self {
+            socket,
+            host,
+            peer: host_addr,
+            host_addr,
+            connected: false,
+            disconnected: false,
+            last_hello: None,
+            ggrs_messages: Vec::new(),
+            reliable: ReliableChannel::default(),
+            buffer: vec![0; RECEIVE_BUFFER_SIZE],
+        }
+    }
+
+    pub fn host(port: u16) -> Result<Self, String> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))
+            .map_err(|e| format!("failed to host on port {}, {}", port, e))?;
+        socket.set_nonblocking(true).map_err(|e| e.to_string())?;
+        info!("hosting a direct match on port {}", port);
+        Ok(Self::new(socket, true, None))
+    }
+
+    pub fn join(address: &str) -> Result<Self, String> {
+        let host_addr = address
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .ok_or_else(|| format!("invalid address '{}', expected ip:port", address))?;
+        let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(|e| e.to_string())?;
+        socket.set_nonblocking(true).map_err(|e| e.to_string())?;
+        info!("joining the direct match of {}", host_addr);
+        Ok(Self::new(socket, false, Some(host_addr)))
+    }
+
+    fn send_packet(&self, packet: &Packet, addr: SocketAddr) {
+        let bytes = serialize(packet).expect("failed to serialize packet");
+        if let Err(err) = self.socket.send_to(&bytes, addr) {
+            // UDP is unreliable anyway, GGRS and the reliable messages recover from it
+            warn!("failed to send packet to {}, {}", addr, err);
+        }
+    }
+
+    fn send_to_peer(&self, packet: &Packet) {
+        if let Some(peer) = self.peer.filter(|_| self.connected) {
+            self.send_packet(packet, peer);
+        }
+    }
+
+    fn handle_packet(&mut self, packet: Packet, from: SocketAddr) {
+        match packet {
+            Packet::Hello if self.host => {
+                if self.peer.is_some_and(|peer| peer != from) {
+                    warn!("{} tried to join, but the match is full", from);
+                    return;
+                }
+                if !self.connected {
+                    info!("{} joined", from);
+                }
+                self.peer = Some(from);
+                self.connected = true;
+                // Our welcome might get lost, the guest keeps saying hello until it arrives
+                self.send_packet(&Packet::Welcome, from);
+            }
+            Packet::Welcome if !self.host && Some(from) == self.host_addr => {
+                if !self.connected {
+                    info!("connected to {}", from);
+                }
+                self.connected = true;
+            }
+            _ if !self.connected || Some(from) != self.peer => {}
+            Packet::Ggrs(message) => self.ggrs_messages.push(message),
+            Packet::Reliable { seq, message } => {
+                let ack = self.reliable.receive(seq, message);
+                self.send_packet(&ack, from);
+            }
+            Packet::Ack { seq } => self.reliable.acknowledge(seq),
+            Packet::Hello | Packet::Welcome => {}
+        }
+    }
+
+    /// Receive everything that arrived and resend what our peer didn't acknowledge yet.
+    fn poll(&mut self) {
+        if self.disconnected {
+            return;
+        }
+
+        loop {
+            match self.socket.recv_from(&mut self.buffer) {
+                Ok((len, from)) => match deserialize::<Packet>(&self.buffer[..len]) {
+                    Ok(packet) => self.handle_packet(packet, from),
+                    Err(_) => warn!("received invalid packet from {}", from),
+                },
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                // On some platforms an unreachable peer shows up as an error here
+                Err(err) if err.kind() == ErrorKind::ConnectionReset => continue,
+                Err(err) => {
+                    error!("failed to receive packets, {}", err);
+                    break;
+                }
+            }
+        }
+
+        let now = Instant::now();
+        if !self.connected {
+            if let Some(host_addr) = self.host_addr {
+                if !self
+                    .last_hello
+                    .is_some_and(|last| now - last < HELLO_INTERVAL)
+                {
+                    self.send_packet(&Packet::Hello, host_addr);
+                    self.last_hello = Some(now);
+                }
+            }
+            return;
+        }
+
+        match self.reliable.resend(now) {
+            Some(packets) => {
+                for packet in packets {
+                    self.send_to_peer(&packet);
+                }
+            }
+            None => {
+                error!("our peer stopped acknowledging our messages, it disconnected");
+                self.connected = false;
+                self.disconnected = true;
+            }
+        }
+    }
+
+    pub fn disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    pub fn update_peers(&mut self) {
+        self.poll();
+    }
+
+    /// The host is always the first player.
+    pub fn players(&self) -> Vec<PlayerType<PeerId>> {
+        if !self.connected {
+            return vec![PlayerType::Local];
+        }
+        if self.host {
+            vec![PlayerType::Local, PlayerType::Remote(DIRECT_PEER)]
+        } else {
+            vec![PlayerType::Remote(DIRECT_PEER), PlayerType::Local]
+        }
+    }
+
+    pub fn send_reliable(&mut self, message: &str) {
+        let packet = self.reliable.send(message, Instant::now());
+        self.send_to_peer(&packet);
+    }
+
+    pub fn receive_reliable(&mut self) -> Vec<(PeerId, String)> {
+        self.poll();
+        self.reliable
+            .received
+            .drain(..)
+            .map(|message| (DIRECT_PEER, message))
+            .collect()
+    }
+}
+
+/// The GGRS side of a `DirectSocket`, the reliable messages keep using the same socket.
+#[derive(Debug, Clone)]
+pub struct DirectGgrsSocket(pub Arc<Mutex<DirectSocket>>);
+
+impl DirectGgrsSocket {
+    pub fn lock(&self) -> MutexGuard<'_, DirectSocket> {
+        // if the lock is poisoned, we're already doomed, time to panic
+        self.0.lock().expect("failed to lock direct socket")
+    }
+}
+
+impl ggrs::NonBlockingSocket<PeerId> for DirectGgrsSocket {
+    fn send_to(&mut self, msg: &ggrs::Message, _addr: &PeerId) {
+        self.lock().send_to_peer(&Packet::Ggrs(msg.clone()));
+    }
+
+    fn receive_all_messages(&mut self) -> Vec<(PeerId, ggrs::Message)> {
+        let mut socket = self.lock();
+        socket.poll();
+        socket
+            .ggrs_messages
+            .drain(..)
+            .map(|message| (DIRECT_PEER, message))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::misc::GameRng;
+
+    /// Drops some of the packets and shuffles the rest.
+    fn bad_network(rng: &mut GameRng, mut packets: Vec<Packet>) -> Vec<Packet> {
+        packets.retain(|_| rng.gen::<f32>() >= 0.3);
+        packets.shuffle(rng);
+        packets
+    }
+
+    #[test]
+    fn reliable_messages_arrive_in_order_despite_loss_and_reordering() {
+        let mut rng = GameRng::seed_from_u64(0);
+        let mut sender = ReliableChannel::default();
+        let mut receiver = ReliableChannel::default();
+
+        let messages: Vec<String> = (0..50).map(|i| format!("message {}", i)).collect();
+        let mut now = Instant::now();
+        let mut in_flight: Vec<Packet> = messages
+            .iter()
+            .map(|message| sender.send(message, now))
+            .collect();
+
+        for _ in 0..100 {
+            let mut acks = Vec::new();
+            for packet in bad_network(&mut rng, in_flight) {
+                match packet {
+                    Packet::Reliable { seq, message } => acks.push(receiver.receive(seq, message)),
+                    _ => panic!("only reliable messages are sent"),
+                }
+            }
+            for ack in bad_network(&mut rng, acks) {
+                match ack {
+                    Packet::Ack { seq } => sender.acknowledge(seq),
+                    _ => panic!("only acks are sent back"),
+                }
+            }
+
+            now += RESEND_INTERVAL;
+            in_flight = sender.resend(now).expect("gave up on our peer");
+            if sender.pending.is_empty() {
+                break;
+            }
+        }
+
+        assert!(
+            sender.pending.is_empty(),
+            "messages were never acknowledged"
+        );
+        assert_eq!(receiver.received, messages);
+    }
+
+    #[test]
+    fn unacknowledged_messages_are_given_up_after_the_retry_limit() {
+        let mut sender = ReliableChannel::default();
+        let mut now = Instant::now();
+        sender.send("lost", now);
+
+        for _ in 0..MAX_RESENDS {
+            now += RESEND_INTERVAL;
+            assert_eq!(sender.resend(now).map(|packets| packets.len()), Some(1));
+        }
+        now += RESEND_INTERVAL;
+        assert!(sender.resend(now).is_none());
+        assert!(sender.pending.is_empty());
+    }
+}
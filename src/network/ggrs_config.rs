@@ -14,7 +14,7 @@ pub const MAX_FRAME_BEHIND: usize = 40;
 pub struct GgrsConfig;
 
 impl Config for GgrsConfig {
-    type Input = u8;
+    type Input = u16;
     type State = u8;
     type Address = PeerId;
 }
@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+
+use super::desync::send_to_remotes;
+use super::socket::AceSocket;
+use crate::misc::Settings;
+use crate::player::LocalPlayerHandle;
+use crate::world::{MatchRules, RulesProposal};
+use crate::{GameState, RollbackState};
+
+/// The lobby between matchmaking and the first round.
+/// Both players ban and pick maps and vote on the playlist mode,
+/// once both locked in their choices the rules are agreed on and the match starts.
+#[derive(Resource, Default)]
+pub struct Lobby {
+    /// We sent our choices to our peer, they can't change anymore
+    pub locked_in: bool,
+    /// The choices of our peer, they only arrive once our peer locked in
+    pub remote_rules: Option<RulesProposal>,
+}
+
+/// Send our choices to our peer, the rules the match is played with
+/// are decided once both players did this.
+pub fn lock_in(lobby: &mut Lobby, socket: &mut AceSocket, settings: &Settings) {
+    if lobby.locked_in {
+        return;
+    }
+    send_to_remotes(socket, &settings.rules.to_string());
+    lobby.locked_in = true;
+    info!("locked in our choices, {}", settings.rules);
+}
+
+pub fn receive_lobby_messages(mut socket: ResMut<AceSocket>, mut lobby: ResMut<Lobby>) {
+    for (_, message) in socket.receive_tcp_message() {
+        match RulesProposal::from_str(&message) {
+            Some(remote_rules) => {
+                info!("our peer locked in, {}", remote_rules);
+                lobby.remote_rules = Some(remote_rules);
+            }
+            None => warn!("unexpected message in the lobby, '{}'", message),
+        }
+    }
+}
+
+pub fn start_match(
+    lobby: Res<Lobby>,
+    settings: Res<Settings>,
+    local_handle: Res<LocalPlayerHandle>,
+    mut match_rules: ResMut<MatchRules>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_rollback_state: ResMut<NextState<RollbackState>>,
+) {
+    if !lobby.locked_in {
+        return;
+    }
+    let Some(remote_rules) = &lobby.remote_rules else {
+        return;
+    };
+
+    // The proposal of the player with handle 0 always goes first, so both peers agree on the same rules
+    *match_rules = if local_handle.0 == 0 {
+        MatchRules::agree(&settings.rules, remote_rules)
+    } else {
+        MatchRules::agree(remote_rules, &settings.rules)
+    };
+    info!("agreed on the match rules, {}", *match_rules);
+
+    next_game_state.set(GameState::InRollbackGame);
+    next_rollback_state.set(RollbackState::RoundStart);
+}
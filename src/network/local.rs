@@ -0,0 +1,143 @@
+//! Local matches against an idle peer in the same process, to test the netcode
+//! (predictions, rollbacks and the conditions of `netsim`) without a second instance.
+//!
+//! Both sessions are connected with a `LoopbackSocket`, the peer session has no world,
+//! it only sends empty inputs.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_ggrs::ggrs::{
+    DesyncDetection, GGRSError, GGRSRequest, P2PSession, PlayerType, SessionState,
+};
+use bevy_ggrs::Session;
+use bevy_matchbox::prelude::PeerId;
+use uuid::Uuid;
+
+use super::ggrs_config::{GGRS_FPS, PLAYER_COUNT};
+use super::netsim::{LoopbackSocket, NetworkConditions, SimulatedSocket};
+use super::GgrsConfig;
+use crate::player::LocalPlayerHandle;
+use crate::world::{SeedHandle, Seeds};
+use crate::GameState;
+
+const LOCAL_PEER: PeerId = PeerId(Uuid::from_u128(1));
+const LOOPBACK_PEER: PeerId = PeerId(Uuid::from_u128(2));
+// We always control the first plane
+const LOCAL_HANDLE: usize = 0;
+const LOOPBACK_HANDLE: usize = 1;
+
+/// Start a local match instead of looking for an opponent once the matchmaking starts.
+#[derive(Resource)]
+pub struct PendingLocalMatch;
+
+/// The session of the idle peer, it runs next to ours at the same FPS.
+#[derive(Resource)]
+pub struct LoopbackPeer {
+    session: P2PSession<GgrsConfig>,
+    /// Time that passed but wasn't simulated yet
+    accumulator: Duration,
+}
+
+fn start_session(
+    socket: LoopbackSocket<PeerId>,
+    network_conditions: NetworkConditions,
+    local_handle: usize,
+    remote: PeerId,
+    desync_detection: DesyncDetection,
+) -> P2PSession<GgrsConfig> {
+    let mut session_builder =
+        GgrsConfig::new_builder().with_desync_detection_mode(desync_detection);
+    for handle in 0..PLAYER_COUNT {
+        let player = if handle == local_handle {
+            PlayerType::Local
+        } else {
+            PlayerType::Remote(remote)
+        };
+        session_builder = session_builder
+            .add_player(player, handle)
+            .expect("failed to add player");
+    }
+    session_builder
+        .start_p2p_session(SimulatedSocket::new(socket, network_conditions))
+        .expect("failed to start session")
+}
+
+pub fn start_local_match(
+    mut commands: Commands,
+    mut seeds: ResMut<Seeds>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    network_conditions: Res<NetworkConditions>,
+) {
+    commands.remove_resource::<PendingLocalMatch>();
+    if !network_conditions.is_perfect() {
+        warn!("simulating a bad network, {}", *network_conditions);
+    }
+
+    // Both directions go through the simulated network
+    let (local_socket, loopback_socket) = LoopbackSocket::pair(LOCAL_PEER, LOOPBACK_PEER);
+    let session = start_session(
+        local_socket,
+        *network_conditions,
+        LOCAL_HANDLE,
+        LOOPBACK_PEER,
+        DesyncDetection::On { interval: 10 },
+    );
+    // The peer has no state, there are no checksums to compare
+    let loopback_session = start_session(
+        loopback_socket,
+        *network_conditions,
+        LOOPBACK_HANDLE,
+        LOCAL_PEER,
+        DesyncDetection::Off,
+    );
+
+    // The peer can't pick a seed, it plays on ours
+    let seed = seeds.0[0].seed;
+    seeds.0.push(SeedHandle {
+        handle: Some(LOOPBACK_PEER),
+        seed,
+    });
+
+    commands.insert_resource(Session::P2P(session));
+    commands.insert_resource(LoopbackPeer {
+        session: loopback_session,
+        accumulator: Duration::ZERO,
+    });
+    commands.insert_resource(LocalPlayerHandle(LOCAL_HANDLE));
+    info!("started a local match against an idle peer");
+    next_game_state.set(GameState::InRollbackGame);
+}
+
+pub fn advance_loopback_peer(mut peer: ResMut<LoopbackPeer>, time: Res<Time>) {
+    let peer = &mut *peer;
+    peer.session.poll_remote_clients();
+    for event in peer.session.events() {
+        debug!("loopback peer: {:?}", event);
+    }
+    if peer.session.current_state() != SessionState::Running {
+        return;
+    }
+
+    let frame_time = Duration::from_secs_f64(1.0 / GGRS_FPS as f64);
+    peer.accumulator += time.delta();
+    while peer.accumulator >= frame_time {
+        peer.accumulator -= frame_time;
+        if let Err(err) = peer.session.add_local_input(LOOPBACK_HANDLE, 0) {
+            error!("failed to add the input of the loopback peer: {}", err);
+            return;
+        }
+        match peer.session.advance_frame() {
+            Ok(requests) => {
+                for request in requests {
+                    if let GGRSRequest::SaveGameState { cell, frame } = request {
+                        cell.save(frame, None, None);
+                    }
+                }
+            }
+            // Waiting for our inputs, like a remote peer would
+            Err(GGRSError::PredictionThreshold) => {}
+            Err(err) => error!("failed to advance the loopback peer: {}", err),
+        }
+    }
+}
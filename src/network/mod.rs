@@ -1,23 +1,42 @@
+pub mod desync;
+pub mod direct;
 pub mod ggrs_config;
+pub mod lobby;
+pub mod local;
+pub mod netsim;
+pub mod rollback;
 pub mod session;
 pub mod session_event;
 pub mod session_stats;
+pub mod signalling;
 pub mod socket;
 
 use bevy_ggrs::GgrsSchedule;
 pub use ggrs_config::GgrsConfig;
 
+/// The UDP port of `host` and the TCP port of the signalling server.
+pub const DEFAULT_PORT: u16 = 3536;
+
 use bevy::prelude::*;
 
 use crate::{
+    misc::snapshot::PendingSnapshot,
     player::spawning::despawn_players,
     ui::round_start_screen::round_start_timeout,
     world::{check_rematch, round_end_timeout},
     GameState, RollbackState,
 };
+use desync::{
+    record_checksums, report_desync, reset_desync_report, route_reliable_messages, ChecksumHistory,
+    DesyncEvent, DesyncReport, ReliableMessage,
+};
+use lobby::{receive_lobby_messages, start_match, Lobby};
+use local::{advance_loopback_peer, start_local_match, LoopbackPeer, PendingLocalMatch};
+use netsim::NetworkConditions;
 use session::{check_ready_state, start_matchbox_socket, wait_for_players, wait_for_seed, Ready};
 use session_event::{
-    change_game_state, change_rollback_state, handle_session_events, SessionEvent,
+    change_game_state, change_rollback_state, handle_session_events, handle_socket_disconnect,
+    SessionEvent,
 };
 use socket::AceSocket;
 
@@ -30,22 +49,70 @@ impl Plugin for AceNetworkPlugin {
         app.add_systems(
             Update,
             (
-                wait_for_players.run_if(in_state(GameState::Matchmaking)),
+                wait_for_players
+                    .run_if(in_state(GameState::Matchmaking))
+                    .run_if(resource_exists::<AceSocket>()),
                 wait_for_seed
                     .run_if(in_state(GameState::Matchmaking))
                     .run_if(resource_exists::<AceSocket>()),
                 check_ready_state.run_if(in_state(GameState::Matchmaking)),
-                handle_session_events.run_if(in_state(GameState::InRollbackGame)),
+                start_local_match
+                    .run_if(in_state(GameState::Matchmaking))
+                    .run_if(resource_exists::<PendingLocalMatch>()),
+                advance_loopback_peer
+                    .run_if(in_state(GameState::InRollbackGame))
+                    .run_if(resource_exists::<LoopbackPeer>()),
+                receive_lobby_messages
+                    .run_if(in_state(GameState::Lobby))
+                    .run_if(resource_exists::<AceSocket>()),
+                start_match
+                    .run_if(in_state(GameState::Lobby))
+                    .after(receive_lobby_messages),
+                // Our peer can leave while we are still in the lobby
+                handle_session_events.run_if(
+                    in_state(GameState::Lobby).or_else(in_state(GameState::InRollbackGame)),
+                ),
+                handle_socket_disconnect
+                    .run_if(in_state(GameState::Lobby).or_else(in_state(GameState::InRollbackGame)))
+                    .run_if(resource_exists::<AceSocket>()),
                 update_session_stats
                     .run_if(in_state(GameState::InRollbackGame))
                     .after(handle_session_events),
-                change_game_state.run_if(in_state(GameState::InRollbackGame)),
+                change_game_state.run_if(
+                    in_state(GameState::Lobby).or_else(in_state(GameState::InRollbackGame)),
+                ),
+                route_reliable_messages
+                    .run_if(in_state(GameState::InRollbackGame))
+                    .run_if(resource_exists::<AceSocket>()),
+                report_desync
+                    .run_if(in_state(GameState::InRollbackGame))
+                    .run_if(resource_exists::<AceSocket>())
+                    .after(handle_session_events),
             ),
         )
         .init_resource::<Ready>()
+        .init_resource::<Lobby>()
         .init_resource::<SessionStats>()
+        .init_resource::<NetworkConditions>()
+        .init_resource::<ChecksumHistory>()
+        .init_resource::<DesyncReport>()
         .add_event::<SessionEvent>()
-        .add_systems(OnEnter(GameState::Matchmaking), start_matchbox_socket)
+        .add_event::<DesyncEvent>()
+        .add_event::<ReliableMessage>()
+        .add_systems(
+            OnEnter(GameState::Matchmaking),
+            start_matchbox_socket
+                // Snapshots are played in a local session
+                .run_if(not(resource_exists::<PendingSnapshot>()))
+                // Direct connections bring their own socket
+                .run_if(not(resource_exists::<AceSocket>()))
+                .run_if(not(resource_exists::<PendingLocalMatch>())),
+        )
+        .add_systems(OnEnter(GameState::InRollbackGame), reset_desync_report)
+        .add_systems(
+            GgrsSchedule,
+            record_checksums.before(apply_state_transition::<RollbackState>),
+        )
         .add_systems(
             GgrsSchedule,
             change_rollback_state
@@ -0,0 +1,304 @@
+//! Simulated bad networks, to test rollbacks and the prediction window without the internet.
+//!
+//! `SimulatedSocket` wraps any GGRS socket and delays, drops and reorders the messages
+//! it receives, `LoopbackSocket` connects sessions in the same process.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy_ggrs::ggrs::{Message, NonBlockingSocket};
+use rand::{Rng, SeedableRng};
+
+use crate::misc::GameRng;
+
+// Reordered messages are held back this much longer, so the ones sent after them overtake them
+const REORDER_DELAY: Duration = Duration::from_millis(50);
+
+/// The network conditions that get simulated on the messages we receive.
+/// Set them on both peers to make both directions bad.
+#[derive(Resource, Clone, Copy, Default, Debug, PartialEq)]
+pub struct NetworkConditions {
+    /// One way delay of every message
+    pub latency: Duration,
+    /// Random extra delay, up to this much
+    pub jitter: Duration,
+    /// Fraction of messages that get dropped, from 0.0 to 1.0
+    pub packet_loss: f32,
+    /// Fraction of messages that arrive after messages that were sent later, from 0.0 to 1.0
+    pub reorder: f32,
+}
+
+impl Display for NetworkConditions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "latency {}ms, jitter {}ms, packet loss {}%, reorder {}%",
+            self.latency.as_millis(),
+            self.jitter.as_millis(),
+            self.packet_loss * 100.0,
+            self.reorder * 100.0
+        )
+    }
+}
+
+impl NetworkConditions {
+    pub fn is_perfect(&self) -> bool {
+        *self == NetworkConditions::default()
+    }
+
+    /// How long the message takes to arrive, `None` if it gets lost.
+    fn delay(&self, rng: &mut GameRng) -> Option<Duration> {
+        if rng.gen::<f32>() < self.packet_loss {
+            return None;
+        }
+
+        let mut delay = self.latency + self.jitter.mul_f32(rng.gen());
+        if rng.gen::<f32>() < self.reorder {
+            delay += REORDER_DELAY;
+        }
+        Some(delay)
+    }
+}
+
+/// Applies the `NetworkConditions` to the messages the inner socket receives.
+pub struct SimulatedSocket<S, A> {
+    inner: S,
+    conditions: NetworkConditions,
+    rng: GameRng,
+    /// Messages that didn't arrive yet, with the time they arrive at
+    in_flight: Vec<(Instant, A, Message)>,
+}
+
+impl<S, A> SimulatedSocket<S, A> {
+    pub fn new(inner: S, conditions: NetworkConditions) -> Self {
+        Self::with_seed(inner, conditions, rand::random())
+    }
+
+    pub fn with_seed(inner: S, conditions: NetworkConditions, seed: u64) -> Self {
+        Self {
+            inner,
+            conditions,
+            rng: GameRng::seed_from_u64(seed),
+            in_flight: Vec::new(),
+        }
+    }
+}
+
+impl<S, A> NonBlockingSocket<A> for SimulatedSocket<S, A>
+where
+    S: NonBlockingSocket<A>,
+    A: Clone + PartialEq + Eq + Hash + Send + Sync,
+{
+    fn send_to(&mut self, msg: &Message, addr: &A) {
+        self.inner.send_to(msg, addr);
+    }
+
+    fn receive_all_messages(&mut self) -> Vec<(A, Message)> {
+        let messages = self.inner.receive_all_messages();
+        if self.conditions.is_perfect() && self.in_flight.is_empty() {
+            return messages;
+        }
+
+        let now = Instant::now();
+        for (addr, msg) in messages {
+            if let Some(delay) = self.conditions.delay(&mut self.rng) {
+                self.in_flight.push((now + delay, addr, msg));
+            }
+        }
+
+        // Stable sort, messages with the same arrival time keep their order
+        self.in_flight.sort_by_key(|(arrival, _, _)| *arrival);
+        let arrived = self
+            .in_flight
+            .iter()
+            .take_while(|(arrival, _, _)| *arrival <= now)
+            .count();
+        self.in_flight
+            .drain(..arrived)
+            .map(|(_, addr, msg)| (addr, msg))
+            .collect()
+    }
+}
+
+type Mailboxes<A> = Arc<Mutex<HashMap<A, VecDeque<(A, Message)>>>>;
+
+/// A socket that delivers messages to the other `LoopbackSocket`s it was created with,
+/// in the same process. Combine it with `SimulatedSocket` to get a bad network.
+pub struct LoopbackSocket<A> {
+    addr: A,
+    mailboxes: Mailboxes<A>,
+}
+
+impl<A: Clone + Eq + Hash> LoopbackSocket<A> {
+    /// Two sockets that are connected to each other.
+    pub fn pair(first: A, second: A) -> (Self, Self) {
+        let mailboxes: Mailboxes<A> = Arc::default();
+        {
+            let mut boxes = mailboxes.lock().expect("failed to lock mailboxes");
+            boxes.insert(first.clone(), VecDeque::new());
+            boxes.insert(second.clone(), VecDeque::new());
+        }
+        (
+            Self {
+                addr: first,
+                mailboxes: mailboxes.clone(),
+            },
+            Self {
+                addr: second,
+                mailboxes,
+            },
+        )
+    }
+}
+
+impl<A> NonBlockingSocket<A> for LoopbackSocket<A>
+where
+    A: Clone + PartialEq + Eq + Hash + Send + Sync,
+{
+    fn send_to(&mut self, msg: &Message, addr: &A) {
+        let mut mailboxes = self.mailboxes.lock().expect("failed to lock mailboxes");
+        match mailboxes.get_mut(addr) {
+            Some(mailbox) => mailbox.push_back((self.addr.clone(), msg.clone())),
+            None => warn!("loopback message to an unknown address"),
+        }
+    }
+
+    fn receive_all_messages(&mut self) -> Vec<(A, Message)> {
+        let mut mailboxes = self.mailboxes.lock().expect("failed to lock mailboxes");
+        mailboxes
+            .get_mut(&self.addr)
+            .map(|mailbox| mailbox.drain(..).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::thread::sleep;
+
+    use bevy_ggrs::ggrs::{
+        Config, DesyncDetection, GGRSError, GGRSEvent, GGRSRequest, P2PSession, PlayerType,
+        SessionBuilder, SessionState,
+    };
+
+    use super::*;
+
+    struct TestConfig;
+
+    impl Config for TestConfig {
+        type Input = u8;
+        type State = u64;
+        type Address = usize;
+    }
+
+    // The frame at which the states of both peers get compared
+    const FRAMES: i32 = 300;
+    const PREDICTION_WINDOW: usize = 8;
+
+    fn start_session(
+        socket: SimulatedSocket<LoopbackSocket<usize>, usize>,
+        local: usize,
+    ) -> P2PSession<TestConfig> {
+        let mut builder = SessionBuilder::<TestConfig>::new()
+            .with_num_players(2)
+            .with_max_prediction_window(PREDICTION_WINDOW)
+            .with_desync_detection_mode(DesyncDetection::On { interval: 10 });
+        for handle in 0..2 {
+            let player = if handle == local {
+                PlayerType::Local
+            } else {
+                PlayerType::Remote(handle)
+            };
+            builder = builder.add_player(player, handle).unwrap();
+        }
+        builder.start_p2p_session(socket).unwrap()
+    }
+
+    /// `history` holds the state at the start of each frame, rollbacks overwrite it.
+    fn handle_requests(
+        state: &mut u64,
+        history: &mut BTreeMap<i32, u64>,
+        requests: Vec<GGRSRequest<TestConfig>>,
+    ) {
+        for request in requests {
+            match request {
+                GGRSRequest::SaveGameState { cell, frame } => {
+                    cell.save(frame, Some(*state), Some(*state as u128));
+                    history.insert(frame, *state);
+                }
+                GGRSRequest::LoadGameState { cell, .. } => {
+                    *state = cell.load().unwrap();
+                }
+                GGRSRequest::AdvanceFrame { inputs } => {
+                    for (handle, (input, _)) in inputs.iter().enumerate() {
+                        *state = state
+                            .wrapping_mul(31)
+                            .wrapping_add(*input as u64 * (handle as u64 + 1));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sessions_stay_in_sync_on_a_bad_network() {
+        let conditions = NetworkConditions {
+            latency: Duration::from_millis(40),
+            jitter: Duration::from_millis(20),
+            packet_loss: 0.1,
+            reorder: 0.1,
+        };
+        let (first, second) = LoopbackSocket::pair(0, 1);
+        let mut sessions = [
+            start_session(SimulatedSocket::with_seed(first, conditions, 1), 0),
+            start_session(SimulatedSocket::with_seed(second, conditions, 2), 1),
+        ];
+        let mut states = [0u64; 2];
+        let mut histories = [BTreeMap::new(), BTreeMap::new()];
+        let mut frames = [0; 2];
+        // A peer can't predict further than the prediction window,
+        // so once it got this far it confirmed and corrected frame `FRAMES`
+        let last_frame = FRAMES + 2 * PREDICTION_WINDOW as i32;
+
+        let start = Instant::now();
+        while frames.iter().any(|&frame| frame < last_frame) {
+            assert!(
+                start.elapsed() < Duration::from_secs(30),
+                "sessions stalled"
+            );
+            for (handle, session) in sessions.iter_mut().enumerate() {
+                session.poll_remote_clients();
+                for event in session.events() {
+                    assert!(
+                        !matches!(event, GGRSEvent::DesyncDetected { .. }),
+                        "{:?}",
+                        event
+                    );
+                }
+                if session.current_state() != SessionState::Running || frames[handle] >= last_frame
+                {
+                    continue;
+                }
+
+                let input = (frames[handle] as u8).wrapping_mul(handle as u8 + 3);
+                session.add_local_input(handle, input).unwrap();
+                match session.advance_frame() {
+                    Ok(requests) => {
+                        handle_requests(&mut states[handle], &mut histories[handle], requests);
+                        frames[handle] += 1;
+                    }
+                    Err(GGRSError::PredictionThreshold) => {}
+                    Err(err) => panic!("{}", err),
+                }
+            }
+            sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(histories[0][&FRAMES], histories[1][&FRAMES]);
+    }
+}
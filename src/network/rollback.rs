@@ -0,0 +1,80 @@
+use std::any::TypeId;
+
+use bevy::prelude::*;
+use bevy::reflect::GetTypeRegistration;
+
+/// The types that are part of the rollback state, used by the snapshots.
+#[derive(Resource, Default)]
+pub struct RollbackTypes {
+    pub components: Vec<TypeId>,
+    pub resources: Vec<TypeId>,
+}
+
+/// `bevy_ggrs` keeps the types registered with `GgrsPlugin` in its own type registry,
+/// we register them in the one of the app as well (for the desync report and snapshots).
+pub trait RegisterRollbackTypes {
+    fn register_rollback_component_type<T>(&mut self) -> &mut Self
+    where
+        T: Component + Reflect + FromWorld + GetTypeRegistration;
+
+    fn register_rollback_resource_type<T>(&mut self) -> &mut Self
+    where
+        T: Resource + Reflect + FromWorld + GetTypeRegistration;
+}
+
+impl RegisterRollbackTypes for App {
+    fn register_rollback_component_type<T>(&mut self) -> &mut Self
+    where
+        T: Component + Reflect + FromWorld + GetTypeRegistration,
+    {
+        self.init_resource::<RollbackTypes>()
+            .register_type::<T>()
+            .register_type_data::<T, ReflectComponent>();
+        self.world
+            .resource_mut::<RollbackTypes>()
+            .components
+            .push(TypeId::of::<T>());
+        self
+    }
+
+    fn register_rollback_resource_type<T>(&mut self) -> &mut Self
+    where
+        T: Resource + Reflect + FromWorld + GetTypeRegistration,
+    {
+        self.init_resource::<RollbackTypes>()
+            .register_type::<T>()
+            .register_type_data::<T, ReflectResource>();
+        self.world
+            .resource_mut::<RollbackTypes>()
+            .resources
+            .push(TypeId::of::<T>());
+        self
+    }
+}
+
+/// Register rollback resources and components with the given `GgrsPlugin`
+/// and in the type registry of the app, returns the plugin.
+/// Listing each type once keeps the rollback state of `bevy_ggrs`, the desync report
+/// and our snapshots in sync.
+#[macro_export]
+macro_rules! register_rollback_types {
+    (
+        $app:ident,
+        $ggrs_plugin:expr,
+        resources: [$($resource:ty),* $(,)?],
+        components: [$($component:ty),* $(,)?] $(,)?
+    ) => {{
+        use $crate::network::rollback::RegisterRollbackTypes;
+
+        let ggrs_plugin = $ggrs_plugin;
+        $(
+            let ggrs_plugin = ggrs_plugin.register_rollback_resource::<$resource>();
+            $app.register_rollback_resource_type::<$resource>();
+        )*
+        $(
+            let ggrs_plugin = ggrs_plugin.register_rollback_component::<$component>();
+            $app.register_rollback_component_type::<$component>();
+        )*
+        ggrs_plugin
+    }};
+}
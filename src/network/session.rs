@@ -3,27 +3,39 @@ use bevy_ggrs::{ggrs::PlayerType, *};
 use bevy_matchbox::matchbox_socket::WebRtcSocket;
 
 use super::ggrs_config::PLAYER_COUNT;
+use super::lobby::Lobby;
+use super::netsim::{NetworkConditions, SimulatedSocket};
 use super::socket::AceSocket;
 use super::GgrsConfig;
 use crate::assets::TurnCredentials;
+use crate::misc::Balance;
 use crate::network::ggrs_config::get_rtc_ice_server_config;
 use crate::player::LocalPlayerHandle;
-use crate::world::{SeedHandle, Seeds};
-use crate::{GameAssets, GameState, RollbackState};
+use crate::world::{RulesProposal, SeedHandle, Seeds};
+use crate::{GameAssets, GameState};
+
+pub const DEFAULT_MATCHBOX_SERVER: &str = "wss://rancic.org/matchmaking";
+
+/// The signalling server to find opponents on, see `signalling` to host one.
+#[derive(Resource)]
+pub struct MatchboxServer(pub String);
 
 #[derive(Resource, Default)]
 pub struct Ready {
     connection_ready: bool,
     local_ready: bool,
     remote_ready: bool,
+    /// Our peer uses a different balance, playing together would desync
+    pub balance_mismatch: bool,
 }
 
 pub fn start_matchbox_socket(
     mut commands: Commands,
     credentials: Res<Assets<TurnCredentials>>,
     assets: Res<GameAssets>,
+    matchbox_server: Res<MatchboxServer>,
 ) {
-    let room_url = format!("wss://rancic.org/matchmaking?next={}", PLAYER_COUNT);
+    let room_url = format!("{}?next={}", matchbox_server.0, PLAYER_COUNT);
     info!("connection to matchbox server: {}", room_url);
 
     let credentials = credentials.get(&assets.turn_credentials);
@@ -44,13 +56,13 @@ pub fn wait_for_players(
     mut socket: ResMut<AceSocket>,
     mut ready: ResMut<Ready>,
     seed: Res<Seeds>,
+    balance: Res<Balance>,
+    network_conditions: Res<NetworkConditions>,
 ) {
-    if socket.inner_mut().get_channel(0).is_err() {
+    if !socket.update_peers() {
         return;
     }
 
-    let _new_peers = socket.inner_mut().update_peers();
-
     let players = socket.players();
 
     if players.len() < PLAYER_COUNT {
@@ -72,6 +84,8 @@ pub fn wait_for_players(
 
         match player {
             PlayerType::Remote(peer_id) => {
+                // The reliable channel is ordered, so the balance arrives before the seed
+                socket.send_tcp_message(peer_id, &format!("balance {}", balance.hash()));
                 socket.send_tcp_message(peer_id, &seed.0[0].seed.to_string());
             }
             PlayerType::Local => {
@@ -81,15 +95,15 @@ pub fn wait_for_players(
         };
     }
 
-    // move the channel out of the socket (required because GGRS takes ownership of it)
-    let channel = socket
-        .inner_mut()
-        .take_channel(AceSocket::GGRS_CHANNEL)
-        .unwrap();
+    let ggrs_socket = socket.take_ggrs_socket();
+
+    if !network_conditions.is_perfect() {
+        warn!("simulating a bad network, {}", *network_conditions);
+    }
 
     // start the GGRS session
     let ggrs_session = session_builder
-        .start_p2p_session(channel)
+        .start_p2p_session(SimulatedSocket::new(ggrs_socket, *network_conditions))
         .expect("failed to start session");
 
     commands.insert_resource(Session::P2P(ggrs_session));
@@ -100,6 +114,8 @@ pub fn wait_for_seed(
     mut seeds: ResMut<Seeds>,
     mut socket: ResMut<AceSocket>,
     mut ready: ResMut<Ready>,
+    mut lobby: ResMut<Lobby>,
+    balance: Res<Balance>,
 ) {
     if !ready.connection_ready {
         return;
@@ -119,6 +135,29 @@ pub fn wait_for_seed(
             continue;
         }
 
+        // Hash of the balance of our peer, we can't play together if it differs from ours
+        if let Some(remote_hash) = seed.1.strip_prefix("balance ") {
+            if remote_hash.parse::<u64>().ok() != Some(balance.hash()) {
+                error!(
+                    "our peer uses a different balance, theirs: {}, ours: {}",
+                    remote_hash,
+                    balance.hash()
+                );
+                ready.balance_mismatch = true;
+            }
+            continue;
+        }
+
+        // Our peer was faster and already locked in its choices in the lobby
+        if let Some(remote_rules) = RulesProposal::from_str(&seed.1) {
+            lobby.remote_rules = Some(remote_rules);
+            continue;
+        }
+
+        if ready.balance_mismatch {
+            continue;
+        }
+
         // Normal seed
         seeds.0.push(SeedHandle {
             handle: Some(seed.0),
@@ -137,17 +176,12 @@ pub fn wait_for_seed(
     }
 }
 
-pub fn check_ready_state(
-    ready: Res<Ready>,
-    mut next_game_state: ResMut<NextState<GameState>>,
-    mut next_rollback_state: ResMut<NextState<RollbackState>>,
-) {
+pub fn check_ready_state(ready: Res<Ready>, mut next_game_state: ResMut<NextState<GameState>>) {
     if ready.local_ready && ready.remote_ready {
         if !ready.connection_ready {
             // sanity check, should never trigger
             panic!("conneciton is not established but we are ready to play?")
         }
-        next_game_state.set(GameState::InRollbackGame);
-        next_rollback_state.set(RollbackState::RoundStart);
+        next_game_state.set(GameState::Lobby);
     }
 }
@@ -3,6 +3,8 @@ use bevy_ggrs::{ggrs::GGRSEvent as GgrsEvent, *};
 
 use crate::{GameState, RollbackState};
 
+use super::desync::DesyncEvent;
+use super::socket::AceSocket;
 use super::GgrsConfig;
 
 #[derive(Event)]
@@ -13,6 +15,7 @@ pub struct SessionEvent {
 fn handle_session_event(
     event: GgrsEvent<GgrsConfig>,
     ev_session_event: &mut EventWriter<SessionEvent>,
+    ev_desync: &mut EventWriter<DesyncEvent>,
 ) {
     match event {
         GgrsEvent::Synchronizing { .. } => {}
@@ -24,7 +27,9 @@ fn handle_session_event(
         GgrsEvent::NetworkInterrupted { .. } => {}
         GgrsEvent::NetworkResumed { .. } => {}
         GgrsEvent::WaitRecommendation { .. } => {}
-        GgrsEvent::DesyncDetected { .. } => {}
+        GgrsEvent::DesyncDetected { frame, .. } => {
+            ev_desync.send(DesyncEvent { frame });
+        }
     }
 }
 
@@ -41,18 +46,31 @@ fn debug_session_event(event: &GgrsEvent<GgrsConfig>) {
 pub fn handle_session_events(
     mut session: ResMut<Session<GgrsConfig>>,
     mut ev_session_event: EventWriter<SessionEvent>,
+    mut ev_desync: EventWriter<DesyncEvent>,
 ) {
     match session.as_mut() {
         Session::P2P(s) => {
             for event in s.events() {
                 debug_session_event(&event);
-                handle_session_event(event, &mut ev_session_event);
+                handle_session_event(event, &mut ev_session_event, &mut ev_desync);
             }
         }
+        // The local session of a snapshot doesn't have any peers
+        Session::SyncTest(_) => {}
         _ => panic!("Expecting a P2P Session."),
     }
 }
 
+/// Direct connections give up on our peer when it stops acknowledging the reliable messages.
+pub fn handle_socket_disconnect(
+    socket: Res<AceSocket>,
+    mut ev_session_event: EventWriter<SessionEvent>,
+) {
+    if socket.peer_disconnected() {
+        ev_session_event.send(SessionEvent { main_menu: true });
+    }
+}
+
 pub fn change_game_state(
     mut next_game_state: ResMut<NextState<GameState>>,
     mut ev_session_event: EventReader<SessionEvent>,
@@ -29,6 +29,7 @@ pub fn update_session_stats(
                 }
             }
         }
+        Session::SyncTest(_) => {}
         _ => panic!("Expecting a P2P Session."),
     }
 }
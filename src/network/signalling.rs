@@ -0,0 +1,315 @@
+//! A matchbox compatible signalling server, to self host matchmaking with `serve`.
+//!
+//! Peers connect with a websocket to `ws://<ip>:<port>/<room>?next=<n>`,
+//! the first `n` peers of a room get matched, the ones after them start a new match.
+//! The server only introduces the peers and relays their signals,
+//! the matches themselves run over WebRTC between the peers.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use async_std::net::{TcpListener, TcpStream};
+use async_std::task;
+use async_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use async_tungstenite::tungstenite::http::Uri;
+use async_tungstenite::tungstenite::Message;
+use bevy::log::{error, info, warn};
+use futures::channel::mpsc::{unbounded, UnboundedSender};
+use futures::{SinkExt, StreamExt};
+use matchbox_protocol::{PeerEvent, PeerId, PeerRequest};
+use serde_json::Value;
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+type SignalEvent = PeerEvent<Value>;
+type SignalRequest = PeerRequest<Value>;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct RoomKey {
+    id: String,
+    /// How many peers get matched together, `None` puts everyone in the same room
+    next: Option<usize>,
+}
+
+impl RoomKey {
+    fn from_uri(uri: &Uri) -> Self {
+        let next = uri.query().and_then(|query| {
+            query
+                .split('&')
+                .find_map(|param| param.strip_prefix("next="))
+                .and_then(|next| next.parse().ok())
+        });
+        Self {
+            id: uri.path().trim_start_matches('/').to_string(),
+            next,
+        }
+    }
+}
+
+struct Room {
+    key: RoomKey,
+    peers: Vec<PeerId>,
+    /// The match started, new peers go into a new room
+    full: bool,
+}
+
+struct Peer {
+    room: usize,
+    sender: UnboundedSender<Message>,
+}
+
+#[derive(Default)]
+struct Server {
+    peers: HashMap<PeerId, Peer>,
+    rooms: HashMap<usize, Room>,
+    next_room: usize,
+}
+
+impl Server {
+    fn send(&self, peer: PeerId, event: &SignalEvent) {
+        let Some(receiver) = self.peers.get(&peer) else {
+            return;
+        };
+        let text = serde_json::to_string(event).expect("failed to serialize event");
+        // The connection of the peer is closing, it will be removed soon
+        let _ = receiver.sender.unbounded_send(Message::Text(text));
+    }
+
+    fn join(&mut self, key: RoomKey, sender: UnboundedSender<Message>) -> PeerId {
+        let id = PeerId(Uuid::new_v4());
+        let room = match self
+            .rooms
+            .iter()
+            .find(|(_, room)| room.key == key && !room.full)
+        {
+            Some((&room, _)) => room,
+            None => {
+                self.next_room += 1;
+                self.rooms.insert(
+                    self.next_room,
+                    Room {
+                        key,
+                        peers: Vec::new(),
+                        full: false,
+                    },
+                );
+                self.next_room
+            }
+        };
+        self.peers.insert(id, Peer { room, sender });
+        self.send(id, &PeerEvent::IdAssigned(id));
+
+        let room = self.rooms.get_mut(&room).expect("room doesn't exist");
+        let others = room.peers.clone();
+        room.peers.push(id);
+        room.full = room.key.next.is_some_and(|next| room.peers.len() >= next);
+
+        // The peers that were already waiting initiate the connection
+        for other in others {
+            self.send(other, &PeerEvent::NewPeer(id));
+        }
+        id
+    }
+
+    fn leave(&mut self, id: PeerId) {
+        let Some(peer) = self.peers.remove(&id) else {
+            return;
+        };
+        let Some(room) = self.rooms.get_mut(&peer.room) else {
+            return;
+        };
+        room.peers.retain(|&other| other != id);
+        if room.peers.is_empty() {
+            self.rooms.remove(&peer.room);
+            return;
+        }
+
+        for other in room.peers.clone() {
+            self.send(other, &PeerEvent::PeerLeft(id));
+        }
+    }
+
+    fn handle_request(&self, sender: PeerId, request: SignalRequest) {
+        match request {
+            PeerRequest::Signal { receiver, data } => {
+                self.send(receiver, &PeerEvent::Signal { sender, data })
+            }
+            PeerRequest::KeepAlive => {}
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct SharedServer(Arc<Mutex<Server>>);
+
+impl SharedServer {
+    fn lock(&self) -> MutexGuard<'_, Server> {
+        // if the lock is poisoned, we're already doomed, time to panic
+        self.0.lock().expect("failed to lock signalling server")
+    }
+}
+
+async fn handle_connection(stream: TcpStream, server: SharedServer) {
+    let mut uri = None;
+    let callback = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        uri = Some(request.uri().clone());
+        Ok(response)
+    };
+    let ws = match async_tungstenite::accept_hdr_async(stream, callback).await {
+        Ok(ws) => ws,
+        Err(err) => {
+            warn!("websocket handshake failed, {}", err);
+            return;
+        }
+    };
+    let key = RoomKey::from_uri(&uri.expect("handshake without request"));
+
+    let (mut ws_sender, mut ws_receiver) = ws.split();
+    let (sender, mut receiver) = unbounded();
+    task::spawn(async move {
+        while let Some(message) = receiver.next().await {
+            if ws_sender.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let id = server.lock().join(key, sender);
+    info!("{} connected", id.0);
+
+    while let Some(Ok(message)) = ws_receiver.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        match serde_json::from_str::<SignalRequest>(&text) {
+            Ok(request) => server.lock().handle_request(id, request),
+            Err(_) => warn!("invalid request from {}", id.0),
+        }
+    }
+
+    server.lock().leave(id);
+    info!("{} disconnected", id.0);
+}
+
+/// Run the signalling server until the process gets killed.
+pub fn serve(port: u16) {
+    // Bevy doesn't run, so we set up the logging ourselves, `RUST_LOG` filters it like in the game
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+
+    task::block_on(async {
+        let listener = match TcpListener::bind(("0.0.0.0", port)).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!("failed to listen on port {}, {}", port, err);
+                return;
+            }
+        };
+        info!("signalling server listening on port {}", port);
+
+        let server = SharedServer::default();
+        let mut incoming = listener.incoming();
+        while let Some(stream) = incoming.next().await {
+            match stream {
+                Ok(stream) => {
+                    task::spawn(handle_connection(stream, server.clone()));
+                }
+                Err(err) => warn!("failed to accept connection, {}", err),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::mpsc::UnboundedReceiver;
+
+    use super::*;
+
+    fn connect(server: &mut Server, uri: &str) -> (PeerId, UnboundedReceiver<Message>) {
+        let (sender, receiver) = unbounded();
+        let key = RoomKey::from_uri(&uri.parse().expect("invalid uri"));
+        (server.join(key, sender), receiver)
+    }
+
+    fn events(receiver: &mut UnboundedReceiver<Message>) -> Vec<SignalEvent> {
+        let mut events = Vec::new();
+        while let Ok(Some(message)) = receiver.try_next() {
+            let Message::Text(text) = message else {
+                panic!("only text messages are sent");
+            };
+            events.push(serde_json::from_str(&text).expect("invalid event"));
+        }
+        events
+    }
+
+    #[test]
+    fn room_key_is_parsed_from_the_uri() {
+        let key = RoomKey::from_uri(&"/ace?next=2".parse().unwrap());
+        assert_eq!(key.id, "ace");
+        assert_eq!(key.next, Some(2));
+
+        let key = RoomKey::from_uri(&"/ace?other=1&next=3".parse().unwrap());
+        assert_eq!(key.next, Some(3));
+
+        let key = RoomKey::from_uri(&"/ace".parse().unwrap());
+        assert_eq!(key.next, None);
+
+        let key = RoomKey::from_uri(&"/ace?next=many".parse().unwrap());
+        assert_eq!(key.next, None);
+    }
+
+    #[test]
+    fn first_next_peers_share_a_room() {
+        let mut server = Server::default();
+        let (first, mut first_receiver) = connect(&mut server, "/ace?next=2");
+        let (second, mut second_receiver) = connect(&mut server, "/ace?next=2");
+
+        assert_eq!(server.peers[&first].room, server.peers[&second].room);
+        assert!(matches!(
+            events(&mut first_receiver).as_slice(),
+            [PeerEvent::IdAssigned(id), PeerEvent::NewPeer(new)] if *id == first && *new == second
+        ));
+        assert!(matches!(
+            events(&mut second_receiver).as_slice(),
+            [PeerEvent::IdAssigned(id)] if *id == second
+        ));
+    }
+
+    #[test]
+    fn next_peer_starts_a_new_room() {
+        let mut server = Server::default();
+        let (first, _first_receiver) = connect(&mut server, "/ace?next=2");
+        let (_, _second_receiver) = connect(&mut server, "/ace?next=2");
+        let (third, mut third_receiver) = connect(&mut server, "/ace?next=2");
+
+        assert_ne!(server.peers[&first].room, server.peers[&third].room);
+        assert_eq!(server.rooms[&server.peers[&third].room].peers, vec![third]);
+        assert!(matches!(
+            events(&mut third_receiver).as_slice(),
+            [PeerEvent::IdAssigned(id)] if *id == third
+        ));
+    }
+
+    #[test]
+    fn peer_left_reaches_the_remaining_peers() {
+        let mut server = Server::default();
+        let (first, _first_receiver) = connect(&mut server, "/ace?next=3");
+        let (_, mut second_receiver) = connect(&mut server, "/ace?next=3");
+        let (_, mut third_receiver) = connect(&mut server, "/ace?next=3");
+        events(&mut second_receiver);
+        events(&mut third_receiver);
+
+        server.leave(first);
+
+        assert!(!server.peers.contains_key(&first));
+        for receiver in [&mut second_receiver, &mut third_receiver] {
+            assert!(matches!(
+                events(receiver).as_slice(),
+                [PeerEvent::PeerLeft(id)] if *id == first
+            ));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use bincode::{deserialize, serialize};
 
@@ -6,29 +6,43 @@ use bevy::prelude::*;
 use bevy::tasks::IoTaskPool;
 use bevy_ggrs::ggrs;
 use bevy_ggrs::ggrs::PlayerType;
-use bevy_matchbox::matchbox_socket::{MessageLoopFuture, WebRtcSocket};
+use bevy_matchbox::matchbox_socket::{MessageLoopFuture, WebRtcChannel, WebRtcSocket};
 use bevy_matchbox::prelude::{MultipleChannels, PeerId};
 
+use super::direct::{DirectGgrsSocket, DirectSocket};
+
+#[derive(Debug)]
+enum Transport {
+    WebRtc(WebRtcSocket<MultipleChannels>),
+    Direct {
+        socket: DirectGgrsSocket,
+        /// The GGRS session shares the socket once it started
+        ggrs_taken: bool,
+    },
+}
+
 #[derive(Resource, Debug, Clone)]
-pub struct AceSocket(pub Arc<RwLock<WebRtcSocket<MultipleChannels>>>);
+pub struct AceSocket(Arc<RwLock<Transport>>);
+
+/// The socket GGRS runs on, it gets moved out of the `AceSocket` when the session starts.
+pub enum GgrsSocket {
+    WebRtc(WebRtcChannel),
+    Direct(DirectGgrsSocket),
+}
 
-impl ggrs::NonBlockingSocket<PeerId> for AceSocket {
+impl ggrs::NonBlockingSocket<PeerId> for GgrsSocket {
     fn send_to(&mut self, msg: &ggrs::Message, addr: &PeerId) {
-        self.0
-            .write()
-            // if the lock is poisoned, we're already doomed, time to panic
-            .expect("Failed to lock socket for sending!")
-            .channel(Self::GGRS_CHANNEL)
-            .send_to(msg, addr);
+        match self {
+            Self::WebRtc(channel) => channel.send_to(msg, addr),
+            Self::Direct(socket) => socket.send_to(msg, addr),
+        }
     }
 
     fn receive_all_messages(&mut self) -> Vec<(PeerId, ggrs::Message)> {
-        self.0
-            .write()
-            // if the lock is poisoned, we're already doomed, time to panic
-            .expect("Failed to lock socket for receiving!")
-            .channel(Self::GGRS_CHANNEL)
-            .receive_all_messages()
+        match self {
+            Self::WebRtc(channel) => channel.receive_all_messages(),
+            Self::Direct(socket) => socket.receive_all_messages(),
+        }
     }
 }
 
@@ -38,7 +52,16 @@ impl From<(WebRtcSocket<MultipleChannels>, MessageLoopFuture)> for AceSocket {
     ) -> Self {
         let task_pool = IoTaskPool::get();
         task_pool.spawn(message_loop_fut).detach();
-        AceSocket(Arc::new(RwLock::new(socket)))
+        AceSocket(Arc::new(RwLock::new(Transport::WebRtc(socket))))
+    }
+}
+
+impl From<DirectSocket> for AceSocket {
+    fn from(socket: DirectSocket) -> Self {
+        AceSocket(Arc::new(RwLock::new(Transport::Direct {
+            socket: DirectGgrsSocket(Arc::new(Mutex::new(socket))),
+            ggrs_taken: false,
+        })))
     }
 }
 
@@ -47,34 +70,92 @@ impl AceSocket {
     pub const RELIABLE_CHANNEL: usize = 1;
 
     pub fn send_tcp_message(&mut self, peer: PeerId, message: &str) {
-        let bytes = serialize(message).expect("failed to serialize string");
-        self.inner_mut()
-            .channel(Self::RELIABLE_CHANNEL)
-            .send(bytes.clone().into(), peer);
+        match &mut *self.inner_mut() {
+            Transport::WebRtc(socket) => {
+                let bytes = serialize(message).expect("failed to serialize string");
+                socket
+                    .channel(Self::RELIABLE_CHANNEL)
+                    .send(bytes.clone().into(), peer);
+            }
+            Transport::Direct { socket, .. } => socket.lock().send_reliable(message),
+        }
     }
 
     pub fn receive_tcp_message(&mut self) -> Vec<(PeerId, String)> {
-        self.inner_mut()
-            .channel(Self::RELIABLE_CHANNEL)
-            .receive()
-            .into_iter()
-            .map(|(id, packet)| {
-                let msg = deserialize(&packet).expect("failed to deserialize packet");
-                (id, msg)
-            })
-            .collect()
+        match &mut *self.inner_mut() {
+            Transport::WebRtc(socket) => socket
+                .channel(Self::RELIABLE_CHANNEL)
+                .receive()
+                .into_iter()
+                .map(|(id, packet)| {
+                    let msg = deserialize(&packet).expect("failed to deserialize packet");
+                    (id, msg)
+                })
+                .collect(),
+            Transport::Direct { socket, .. } => socket.lock().receive_reliable(),
+        }
+    }
+
+    /// Look for new peers, returns false once the GGRS socket was taken
+    /// (or before the socket is ready).
+    pub fn update_peers(&mut self) -> bool {
+        match &mut *self.inner_mut() {
+            Transport::WebRtc(socket) => {
+                if socket.get_channel(Self::GGRS_CHANNEL).is_err() {
+                    return false;
+                }
+                let _new_peers = socket.update_peers();
+                true
+            }
+            Transport::Direct { socket, ggrs_taken } => {
+                if *ggrs_taken {
+                    return false;
+                }
+                socket.lock().update_peers();
+                true
+            }
+        }
+    }
+
+    /// Whether we gave up on our peer, WebRTC disconnects are reported by the GGRS session.
+    pub fn peer_disconnected(&self) -> bool {
+        match &*self.inner() {
+            Transport::WebRtc(_) => false,
+            Transport::Direct { socket, .. } => socket.lock().disconnected(),
+        }
+    }
+
+    /// Move the GGRS socket out (required because GGRS takes ownership of it).
+    pub fn take_ggrs_socket(&mut self) -> GgrsSocket {
+        match &mut *self.inner_mut() {
+            Transport::WebRtc(socket) => GgrsSocket::WebRtc(
+                socket
+                    .take_channel(Self::GGRS_CHANNEL)
+                    .expect("the GGRS channel was already taken"),
+            ),
+            Transport::Direct { socket, ggrs_taken } => {
+                assert!(!*ggrs_taken, "the GGRS socket was already taken");
+                *ggrs_taken = true;
+                GgrsSocket::Direct(socket.clone())
+            }
+        }
     }
 
     pub fn players(&self) -> Vec<PlayerType<PeerId>> {
-        let Some(our_id) = self.inner().id() else {
+        let transport = self.inner();
+        let socket = match &*transport {
+            Transport::WebRtc(socket) => socket,
+            Transport::Direct { socket, .. } => return socket.lock().players(),
+        };
+
+        let Some(our_id) = socket.id() else {
             // we're still waiting for the server to initialize our id
             // no peers should be added at this point anyway
             return vec![PlayerType::Local];
         };
 
         // player order needs to be consistent order across all peers
-        let mut ids: Vec<_> = self
-            .inner()
+        let mut ids: Vec<_> = socket
             .connected_peers()
             .chain(std::iter::once(our_id))
             .collect();
@@ -91,14 +172,12 @@ impl AceSocket {
             .collect()
     }
 
-    #[allow(unused)]
-    pub fn inner(&self) -> RwLockReadGuard<'_, WebRtcSocket<MultipleChannels>> {
+    fn inner(&self) -> RwLockReadGuard<'_, Transport> {
         // we don't care about handling lock poisoning
         self.0.read().expect("Failed to lock socket for reading!")
     }
 
-    #[allow(unused)]
-    pub fn inner_mut(&mut self) -> RwLockWriteGuard<'_, WebRtcSocket<MultipleChannels>> {
+    fn inner_mut(&mut self) -> RwLockWriteGuard<'_, Transport> {
         // we don't care about handling lock poisoning
         self.0.write().expect("Failed to lock socket for writing!")
     }
@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+use crate::debug::DebugTransform;
+use crate::misc::{math, Balance};
+use crate::player::health::PlayerTookDamage;
+use crate::player::Player;
+use crate::world::{MatchRules, PlaneCollision};
+
+// Damage per unit of relative speed (in units per frame) when bouncing
+const RAM_DAMAGE_PER_SPEED: f32 = 60.0;
+// How hard the planes get pushed apart relative to their relative speed
+const BOUNCE_STRENGTH: f32 = 1.2;
+// Planes that barely touch still get pushed apart a little
+const MIN_BOUNCE_KNOCKBACK: f32 = 3.0;
+
+fn velocity(transform: &Transform, player: &Player) -> Vec2 {
+    math::forward(transform).truncate() * player.current_speed
+}
+
+fn take_damage(player: &mut Player, damage: u32) {
+    player.health = player.health.saturating_sub(damage);
+}
+
+/// Collide planes with each other, what happens depends on the match rules.
+/// Dodging planes fly through other planes.
+pub fn collide_players(
+    match_rules: Res<MatchRules>,
+    balance: Res<Balance>,
+    mut players: Query<(&mut Transform, &mut Player, &mut DebugTransform)>,
+    mut ev_player_took_damage: EventWriter<PlayerTookDamage>,
+) {
+    if match_rules.plane_collision == PlaneCollision::Disabled {
+        return;
+    }
+
+    let mut combinations = players.iter_combinations_mut();
+    while let Some([(mut t1, mut p1, mut d1), (mut t2, mut p2, mut d2)]) = combinations.fetch_next()
+    {
+        if p1.dodging || p2.dodging {
+            continue;
+        }
+        if p1.health == 0 || p2.health == 0 {
+            continue;
+        }
+
+        let offset = t2.translation.truncate() - t1.translation.truncate();
+        let distance = offset.length();
+        if distance >= 2.0 * balance.player_radius() {
+            continue;
+        }
+
+        match match_rules.plane_collision {
+            PlaneCollision::Disabled => {}
+            PlaneCollision::Destroy => {
+                p1.health = 0;
+                p2.health = 0;
+            }
+            PlaneCollision::Bounce => {
+                // The planes could be exactly on top of each other
+                let normal = if distance > 0.0 {
+                    offset / distance
+                } else {
+                    Vec2::X
+                };
+                let relative_speed = (velocity(&t1, &p1) - velocity(&t2, &p2)).length();
+
+                let damage = (relative_speed * RAM_DAMAGE_PER_SPEED) as u32;
+                take_damage(&mut p1, damage);
+                take_damage(&mut p2, damage);
+
+                let knockback = (relative_speed * BOUNCE_STRENGTH).max(MIN_BOUNCE_KNOCKBACK);
+                p1.knockback -= normal * knockback;
+                p2.knockback += normal * knockback;
+
+                // Separate the planes so that they don't collide again next frame
+                let overlap = 2.0 * balance.player_radius() - distance;
+                t1.translation -= (normal * overlap / 2.0).extend(0.0);
+                t2.translation += (normal * overlap / 2.0).extend(0.0);
+                d1.update(&t1);
+                d2.update(&t2);
+            }
+        }
+
+        ev_player_took_damage.send(PlayerTookDamage::new(*t1, p1.handle));
+        ev_player_took_damage.send(PlayerTookDamage::new(*t2, p2.handle));
+    }
+}
@@ -6,14 +6,18 @@ use bevy::prelude::*;
 use bevy_ggrs::{AddRollbackCommandExtension, PlayerInputs};
 
 use crate::audio::RollbackSound;
+use crate::debug::DebugTransform;
+use crate::misc::{math, Balance};
 use crate::network::ggrs_config::GGRS_FPS;
 use crate::GameAssets;
-use crate::{input::dodge, misc::utils::quat_from_vec3, network::GgrsConfig};
+use crate::{
+    input::dodge,
+    misc::utils::{hash_timer, quat_from_vec3},
+    network::GgrsConfig,
+};
 
 use super::Player;
 
-const DODGE_REFRESH_TIME: f32 = 0.50;
-
 #[derive(Component, Reflect, Default)]
 #[reflect(Hash)]
 pub struct DodgeTimer(Timer);
@@ -33,15 +37,15 @@ impl DodgeTimer {
 }
 
 impl DodgeRefreshTimer {
-    fn new(handle: usize) -> Self {
-        let timer = Timer::from_seconds(DODGE_REFRESH_TIME, TimerMode::Once);
+    fn new(handle: usize, refresh_time: f32) -> Self {
+        let timer = Timer::from_seconds(refresh_time, TimerMode::Once);
         Self { timer, handle }
     }
 }
 
 impl Hash for DodgeTimer {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.duration().as_secs_f32().to_bits().hash(state);
+        hash_timer(&self.0, state);
     }
 }
 
@@ -72,15 +76,22 @@ pub fn start_dodging(
     }
 }
 
-pub fn animate_dodges(mut players: Query<(&mut Transform, &mut Player, &DodgeTimer)>) {
-    for (mut transform, mut player, timer) in &mut players {
+pub fn animate_dodges(
+    mut players: Query<(
+        &mut Transform,
+        &mut Player,
+        &DodgeTimer,
+        &mut DebugTransform,
+    )>,
+) {
+    for (mut transform, mut player, timer, mut debug_transform) in &mut players {
         if timer.0.elapsed_secs() > player.stats.dodge_time {
-            transform.rotation = quat_from_vec3(transform.local_x());
+            transform.rotation = quat_from_vec3(math::forward(&transform));
             player.dodging = false;
-            continue;
+        } else {
+            math::rotate_local_x(&mut transform, 2.0 * PI / player.stats.dodge_time / 60.0);
         }
-
-        transform.rotate_local_x(2.0 * PI / player.stats.dodge_time / 60.0);
+        debug_transform.update(&transform);
     }
 }
 
@@ -122,12 +133,14 @@ pub fn tick_dodge_timers(
 pub fn spawn_plane_whites(
     commands: &mut Commands,
     assets: &Res<GameAssets>,
+    balance: &Balance,
     player: Entity,
     handle: usize,
 ) {
     let plane_white = commands
         .spawn((
-            DodgeRefreshTimer::new(handle),
+            DodgeRefreshTimer::new(handle, balance.dodge_refresh_time()),
+            DebugTransform::default(),
             SpriteBundle {
                 texture: assets.plane_white.clone(),
                 sprite: Sprite {
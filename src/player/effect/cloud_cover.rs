@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use bevy_hanabi::EffectSpawner;
+
+use super::trail::Trail;
+use crate::player::health::HealthBar;
+use crate::player::{LocalPlayerHandle, Player};
+use crate::world::map::zone::{inside_cloud, EnvironmentZone};
+
+// The opponent is barely visible inside of clouds
+const HIDDEN_OPPONENT_ALPHA: f32 = 0.1;
+// Let the local player know that they are hidden
+const HIDDEN_LOCAL_ALPHA: f32 = 0.6;
+
+/// Fade planes that hide in clouds, this is purely visual.
+/// The health bar and trails of the opponent would give them away, so hide those too.
+pub fn fade_players_in_clouds(
+    mut players: Query<(&Transform, &Player, &mut Sprite, Option<&Children>)>,
+    mut health_bars: Query<(&HealthBar, &mut Visibility)>,
+    mut trails: Query<&mut EffectSpawner, With<Trail>>,
+    zones: Query<&EnvironmentZone>,
+    local_handle: Option<Res<LocalPlayerHandle>>,
+) {
+    let local_handle = match local_handle {
+        Some(handle) => handle.0,
+        None => return,
+    };
+
+    for (transform, player, mut sprite, children) in &mut players {
+        if !inside_cloud(&zones, transform.translation.truncate()) {
+            sprite.color.set_a(1.0);
+            continue;
+        }
+
+        if player.handle == local_handle {
+            sprite.color.set_a(HIDDEN_LOCAL_ALPHA);
+            continue;
+        }
+
+        sprite.color.set_a(HIDDEN_OPPONENT_ALPHA);
+        for (health_bar, mut visibility) in &mut health_bars {
+            if health_bar.handle == player.handle {
+                *visibility = Visibility::Hidden;
+            }
+        }
+        if let Some(children) = children {
+            for &child in children.iter() {
+                if let Ok(mut trail) = trails.get_mut(child) {
+                    trail.set_active(false);
+                }
+            }
+        }
+    }
+}
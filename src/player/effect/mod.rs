@@ -2,6 +2,7 @@ pub mod damage;
 pub mod trail;
 
 mod bullet;
+mod cloud_cover;
 mod rocket;
 mod super_sonic;
 
@@ -43,6 +44,10 @@ impl Plugin for EffectPlugin {
                 .chain()
                 .run_if(in_state(GameState::InRollbackGame)),
         )
+        .add_systems(
+            Update,
+            cloud_cover::fade_players_in_clouds.run_if(in_state(GameState::InRollbackGame)),
+        )
         .add_systems(
             GgrsSchedule,
             (
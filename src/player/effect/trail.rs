@@ -4,10 +4,7 @@ use bevy::prelude::*;
 use bevy_ggrs::AddRollbackCommandExtension;
 use bevy_hanabi::prelude::*;
 
-use crate::{
-    network::ggrs_config::GGRS_FPS,
-    player::{Player, MIN_SPEED},
-};
+use crate::{debug::DebugTransform, misc::Balance, network::ggrs_config::GGRS_FPS, player::Player};
 
 const LEFT_TRAIL_OFFSET: Vec3 = Vec3::new(0.0, 30.0, -1.0);
 const RIGHT_TRAIL_OFFSET: Vec3 = Vec3::new(0.0, -30.0, -1.0);
@@ -31,7 +28,7 @@ pub struct DamageEffectSpawner;
 
 pub fn spawn_trail_effect(
     commands: &mut Commands,
-    effects: &mut ResMut<Assets<EffectAsset>>,
+    effects: &mut Assets<EffectAsset>,
     offset: Vec3,
 ) -> Entity {
     let mut color_gradient = Gradient::new();
@@ -78,13 +75,15 @@ pub fn spawn_trail_effect(
             }),
     );
 
+    let transform = Transform::from_translation(offset);
     commands
         .spawn((
             Trail,
             KillTimer::default(),
+            DebugTransform::new(&transform),
             ParticleEffectBundle {
                 effect: ParticleEffect::new(effect),
-                transform: Transform::from_translation(offset),
+                transform,
                 ..default()
             },
         ))
@@ -137,6 +136,7 @@ pub fn despawn_trails(
 pub fn toggle_plane_trail_visibilities(
     mut trails: Query<(&Parent, &mut EffectSpawner), With<Trail>>,
     players: Query<&Player>,
+    balance: Res<Balance>,
 ) {
     for (parent, mut trail) in &mut trails {
         let player = match players.get(parent.get()) {
@@ -144,6 +144,6 @@ pub fn toggle_plane_trail_visibilities(
             Err(_) => continue,
         };
 
-        trail.set_active(player.current_speed != MIN_SPEED);
+        trail.set_active(player.current_speed != balance.min_speed());
     }
 }
@@ -2,8 +2,9 @@ use bevy::prelude::*;
 use bevy_ggrs::AddRollbackCommandExtension;
 
 use crate::debug::DebugTransform;
-use crate::player::shooting::bullet::{Bullet, BULLET_RADIUS};
-use crate::player::{Player, PLAYER_RADIUS};
+use crate::misc::{math, Balance};
+use crate::player::shooting::bullet::Bullet;
+use crate::player::Player;
 use crate::world::CollisionEntity;
 
 use super::spawning::player_spawn_transform;
@@ -26,7 +27,7 @@ pub struct PlayerTookDamage {
 }
 
 impl PlayerTookDamage {
-    fn new(transform: Transform, handle: usize) -> Self {
+    pub fn new(transform: Transform, handle: usize) -> Self {
         Self { transform, handle }
     }
 }
@@ -35,6 +36,7 @@ pub fn damage_players(
     mut players: Query<(&Transform, &mut Player), Without<Bullet>>,
     mut bullets: Query<(&mut CollisionEntity, &Transform, &Bullet)>,
     mut ev_player_took_damge: EventWriter<PlayerTookDamage>,
+    balance: Res<Balance>,
 ) {
     for (player_transform, mut player) in &mut players {
         for (mut collision_entity, bullet_tranform, bullet) in &mut bullets {
@@ -56,7 +58,7 @@ pub fn damage_players(
                 player_transform.translation.truncate(),
                 bullet_tranform.translation.truncate(),
             );
-            if distance < PLAYER_RADIUS.powi(2) + BULLET_RADIUS.powi(2) {
+            if distance < math::powi(balance.player_radius(), 2) + math::powi(bullet.radius, 2) {
                 if player.health < bullet.damage {
                     player.health = 0;
                 } else {
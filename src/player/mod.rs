@@ -1,7 +1,9 @@
+pub mod collision;
 pub mod dodge;
 pub mod effect;
 pub mod health;
 pub mod movement;
+pub mod perk;
 pub mod shooting;
 pub mod spawning;
 
@@ -9,19 +11,17 @@ use std::hash::{Hash, Hasher};
 
 use bevy::prelude::*;
 use bevy_ggrs::*;
+use serde::Deserialize;
 
 use crate::input;
+use crate::misc::Balance;
 use crate::network::ggrs_config::{GGRS_FPS, PLAYER_COUNT};
 use crate::network::GgrsConfig;
-use crate::world::Rematch;
-use crate::RollbackState;
+use crate::world::{adjust_score, round_end_timeout, Rematch};
+use crate::{GameState, RollbackState};
 
 // Movement
-pub const MIN_SPEED: f32 = 200.0 / GGRS_FPS as f32;
 pub const DELTA_SPEED: f32 = 4.0 / GGRS_FPS as f32;
-pub const DELTA_STEERING: f32 = 3.5 / GGRS_FPS as f32;
-// Collision
-pub const PLAYER_RADIUS: f32 = 35.0;
 // Color
 pub const P1_COLOR: Color = Color::rgb(
     0xDF as f32 / 255.0,
@@ -34,7 +34,8 @@ pub const P2_COLOR: Color = Color::rgb(
     0xD9 as f32 / 255.0,
 );
 
-#[derive(Reflect, Clone)]
+/// The defaults are part of the `Balance`.
+#[derive(Reflect, Clone, Default, Debug, Deserialize)]
 pub struct PlayerStats {
     pub max_speed: f32,
     pub max_health: u32,
@@ -44,28 +45,24 @@ pub struct PlayerStats {
     pub rocket_reload_time: f32,
     pub dodge_time: f32,
     pub dodge_cooldown: f32,
+    pub flare_charges: u32,
+    pub bullet_size: f32,
 }
 
-impl Default for PlayerStats {
-    fn default() -> Self {
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct PersistentPlayerStats {
+    pub stats: [PlayerStats; PLAYER_COUNT],
+}
+
+impl PersistentPlayerStats {
+    pub fn new(balance: &Balance) -> Self {
         Self {
-            max_speed: 400.0 / 60.0,
-            max_health: 2000,
-            bullet_damage: 150,
-            bullet_reload_time: 0.25,
-            bullet_heat: 80,
-            rocket_reload_time: 2.5,
-            dodge_time: 0.5,
-            dodge_cooldown: 2.5,
+            stats: std::array::from_fn(|_| balance.player_stats()),
         }
     }
 }
 
-#[derive(Resource, Default)]
-pub struct PersistentPlayerStats {
-    pub stats: [PlayerStats; PLAYER_COUNT],
-}
-
 #[derive(Component, Reflect, Default)]
 #[reflect(Hash)]
 pub struct Player {
@@ -76,31 +73,70 @@ pub struct Player {
     pub heat: u32,
     pub overheated: bool,
     pub dodging: bool,
+    // Flares left for this round
+    pub flares: u32,
+    // Displacement per frame from explosions, decays over time
+    pub knockback: Vec2,
 
     pub stats: PlayerStats,
 }
 
 impl Player {
-    pub fn new(handle: usize, stats: PlayerStats) -> Player {
+    pub fn new(handle: usize, mut stats: PlayerStats, balance: &Balance) -> Player {
+        // Stats can be set freely with the console, but we can't be slower than the minimum
+        stats.max_speed = stats.max_speed.max(balance.min_speed());
         Player {
             handle,
-            current_speed: MIN_SPEED,
+            current_speed: balance.min_speed(),
             health: stats.max_health,
             heat: 0,
             overheated: false,
             dodging: false,
+            flares: stats.flare_charges,
+            knockback: Vec2::ZERO,
             stats,
         }
     }
 
-    pub fn speed_ratio(&self) -> f32 {
-        1.0 + (self.current_speed - MIN_SPEED) / (self.stats.max_speed - MIN_SPEED)
+    /// How much faster than the minimum speed the plane can get, never zero.
+    pub fn speed_range(&self, balance: &Balance) -> f32 {
+        // The balance and the console make sure that the max speed is above the min speed,
+        // but dividing by zero would put NaN into the rollback state
+        (self.stats.max_speed - balance.min_speed()).max(f32::EPSILON)
+    }
+
+    pub fn speed_ratio(&self, balance: &Balance) -> f32 {
+        1.0 + (self.current_speed - balance.min_speed()) / self.speed_range(balance)
+    }
+}
+
+impl Hash for PlayerStats {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.max_speed.to_bits().hash(state);
+        self.max_health.hash(state);
+        self.bullet_damage.hash(state);
+        self.bullet_reload_time.to_bits().hash(state);
+        self.bullet_heat.hash(state);
+        self.rocket_reload_time.to_bits().hash(state);
+        self.dodge_time.to_bits().hash(state);
+        self.dodge_cooldown.to_bits().hash(state);
+        self.flare_charges.hash(state);
+        self.bullet_size.to_bits().hash(state);
     }
 }
 
 impl Hash for Player {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        self.handle.hash(state);
         self.current_speed.to_bits().hash(state);
+        self.health.hash(state);
+        self.heat.hash(state);
+        self.overheated.hash(state);
+        self.dodging.hash(state);
+        self.flares.hash(state);
+        self.knockback.x.to_bits().hash(state);
+        self.knockback.y.to_bits().hash(state);
+        self.stats.hash(state);
     }
 }
 
@@ -142,7 +178,21 @@ impl Plugin for PlayerPlugin {
         .add_event::<health::PlayerTookDamage>()
         .add_event::<movement::ReachedMaxSpeed>()
         .init_resource::<PersistentPlayerStats>()
+        .init_resource::<perk::Perks>()
+        .add_systems(
+            OnEnter(RollbackState::RoundEnd),
+            perk::start_perk_draft.after(adjust_score),
+        )
+        .add_systems(OnExit(RollbackState::GameOver), perk::reset_perks)
+        .add_systems(OnEnter(GameState::MainMenu), perk::reset_perks)
         .add_plugins((shooting::ShootingPlugin, effect::EffectPlugin))
+        .add_systems(
+            GgrsSchedule,
+            perk::pick_perks
+                .before(round_end_timeout)
+                .run_if(in_state(RollbackState::RoundEnd))
+                .after(apply_state_transition::<RollbackState>),
+        )
         .add_systems(
             GgrsSchedule,
             check_rematch_state
@@ -189,6 +239,7 @@ impl Plugin for PlayerPlugin {
         .add_systems(
             GgrsSchedule,
             (
+                collision::collide_players,
                 health::damage_players,
                 health::move_health_bars,
                 health::fill_health_bars,
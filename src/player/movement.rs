@@ -3,8 +3,13 @@ use bevy_ggrs::*;
 
 use crate::debug::DebugTransform;
 use crate::input;
+use crate::misc::{math, Balance};
 use crate::network::GgrsConfig;
-use crate::player::{Player, DELTA_SPEED, DELTA_STEERING, MIN_SPEED};
+use crate::player::{Player, DELTA_SPEED};
+use crate::world::map::zone::{EnvironmentZone, ZoneForces};
+use crate::world::RoundFrame;
+
+const KNOCKBACK_DECAY: f32 = 0.9;
 
 #[derive(Event)]
 pub struct ReachedMaxSpeed {
@@ -14,6 +19,7 @@ pub struct ReachedMaxSpeed {
 
 pub fn steer_players(
     inputs: Res<PlayerInputs<GgrsConfig>>,
+    balance: Res<Balance>,
     mut players: Query<(&mut Transform, &Player, &mut DebugTransform)>,
 ) {
     for (mut transform, player, mut debug_transform) in &mut players {
@@ -31,16 +37,17 @@ pub fn steer_players(
             1.0
         };
         let move_ratio =
-            1.0 - (player.current_speed - MIN_SPEED) / (player.stats.max_speed - MIN_SPEED);
-        let move_ratio = (1.0 + move_ratio * 0.22474487).powi(2);
-        let rotation = DELTA_STEERING * steer_direction * move_ratio * shooting_friction;
-        transform.rotate_z(rotation);
+            1.0 - (player.current_speed - balance.min_speed()) / player.speed_range(balance);
+        let move_ratio = math::powi(1.0 + move_ratio * 0.22474487, 2);
+        let rotation = balance.delta_steering() * steer_direction * move_ratio * shooting_friction;
+        math::rotate_z(&mut transform, rotation);
         debug_transform.update(&transform);
     }
 }
 
 pub fn accelerate_players(
     inputs: Res<PlayerInputs<GgrsConfig>>,
+    balance: Res<Balance>,
     mut players: Query<(&Transform, &mut Player)>,
     mut ev_reached_max_speed: EventWriter<ReachedMaxSpeed>,
 ) {
@@ -64,22 +71,36 @@ pub fn accelerate_players(
         player.current_speed += acceleration;
         player.current_speed = player
             .current_speed
-            .clamp(MIN_SPEED, player.stats.max_speed);
+            .clamp(balance.min_speed(), player.stats.max_speed);
 
         // We just reached max speed this frame, send event
         if sub_sonic && player.current_speed == player.stats.max_speed {
             ev_reached_max_speed.send(ReachedMaxSpeed {
                 position: transform.translation,
-                direction: transform.rotation.mul_vec3(Vec3::X),
+                direction: math::forward(transform),
             });
         }
     }
 }
 
-pub fn move_players(mut players: Query<(&mut Transform, &Player, &mut DebugTransform)>) {
-    for (mut transform, player, mut debug_transform) in &mut players {
-        let direction = transform.local_x();
-        transform.translation += direction * player.current_speed;
+pub fn move_players(
+    mut players: Query<(&mut Transform, &mut Player, &mut DebugTransform)>,
+    zones: Query<&EnvironmentZone>,
+    round_frame: Res<RoundFrame>,
+) {
+    for (mut transform, mut player, mut debug_transform) in &mut players {
+        let forces = ZoneForces::at(&zones, transform.translation.truncate(), &round_frame);
+        math::rotate_z(&mut transform, forces.turbulence);
+
+        let direction = math::forward(&transform);
+        transform.translation += direction * player.current_speed
+            + player.knockback.extend(0.0)
+            + forces.wind.extend(0.0);
         debug_transform.update(&transform);
+
+        player.knockback *= KNOCKBACK_DECAY;
+        if player.knockback.length_squared() < 0.01 {
+            player.knockback = Vec2::ZERO;
+        }
     }
 }
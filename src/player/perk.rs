@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use bevy_ggrs::*;
+use rand::seq::SliceRandom;
+use rand_xoshiro::rand_core::SeedableRng;
+
+use crate::input;
+use crate::misc::GameRng;
+use crate::network::ggrs_config::{GGRS_FPS, PLAYER_COUNT};
+use crate::network::GgrsConfig;
+use crate::player::{PersistentPlayerStats, PlayerStats};
+use crate::world::{MatchRules, PerkDraft, RoundStats, Score, Seed, MAX_SCORE};
+
+pub const PERK_OFFERS: usize = 3;
+// After this the first offered perk gets picked automatically
+const DRAFT_TIMEOUT: u32 = 15 * GGRS_FPS as u32;
+// Perks shouldn't be drawn from the same numbers as the map of the next round
+const PERK_SEED_MASK: u64 = 0x9E37_79B9_7F4A_7C15;
+
+const ALL_PERKS: [Perk; 7] = [
+    Perk::FasterRockets,
+    Perk::QuickerDodge,
+    Perk::CoolerGuns,
+    Perk::BiggerBullets,
+    Perk::ExtraFlare,
+    Perk::ThickerArmor,
+    Perk::StrongerEngine,
+];
+
+#[derive(Reflect, Clone, Copy, PartialEq, Debug, Hash)]
+pub enum Perk {
+    FasterRockets,
+    QuickerDodge,
+    CoolerGuns,
+    BiggerBullets,
+    ExtraFlare,
+    ThickerArmor,
+    StrongerEngine,
+}
+
+impl Perk {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::FasterRockets => "FASTER ROCKET RELOAD",
+            Self::QuickerDodge => "QUICKER DODGE",
+            Self::CoolerGuns => "SLOWER HEAT BUILD-UP",
+            Self::BiggerBullets => "BIGGER BULLETS",
+            Self::ExtraFlare => "EXTRA FLARE",
+            Self::ThickerArmor => "THICKER ARMOR",
+            Self::StrongerEngine => "STRONGER ENGINE",
+        }
+    }
+
+    fn apply(&self, stats: &mut PlayerStats) {
+        match self {
+            Self::FasterRockets => stats.rocket_reload_time *= 0.8,
+            Self::QuickerDodge => stats.dodge_cooldown *= 0.75,
+            Self::CoolerGuns => stats.bullet_heat = stats.bullet_heat * 4 / 5,
+            Self::BiggerBullets => stats.bullet_size *= 1.5,
+            Self::ExtraFlare => stats.flare_charges += 1,
+            Self::ThickerArmor => stats.max_health += stats.max_health / 5,
+            Self::StrongerEngine => stats.max_speed *= 1.1,
+        }
+    }
+}
+
+/// The perks of both players in the current match.
+/// This is part of the rollback state, so both peers agree on the picks
+/// (they are made with the rollback inputs).
+#[derive(Resource, Reflect, Default, Debug, Hash)]
+#[reflect(Resource, Hash)]
+pub struct Perks {
+    /// Perks the player can currently pick from, empty if they aren't drafting
+    pub offers: [Vec<Perk>; PLAYER_COUNT],
+    pub picked: [Vec<Perk>; PLAYER_COUNT],
+    frames_left: u32,
+}
+
+impl Perks {
+    pub fn drafting(&self) -> bool {
+        self.offers.iter().any(|offers| !offers.is_empty())
+    }
+
+    /// The persistent stats of the player with all their picked perks stacked on top.
+    pub fn stats(&self, handle: usize, persistent_stats: &PersistentPlayerStats) -> PlayerStats {
+        let mut stats = persistent_stats.stats[handle].clone();
+        for perk in &self.picked[handle] {
+            perk.apply(&mut stats);
+        }
+        stats
+    }
+}
+
+pub fn start_perk_draft(
+    match_rules: Res<MatchRules>,
+    seed: Res<Seed>,
+    round_stats: Res<RoundStats>,
+    score: Res<Score>,
+    mut perks: ResMut<Perks>,
+) {
+    if score.p1 == MAX_SCORE || score.p2 == MAX_SCORE {
+        return;
+    }
+
+    let drafting = match (match_rules.perk_draft, score.last_winner) {
+        (PerkDraft::Off, _) => return,
+        (PerkDraft::Loser, Some(winner)) => [winner != 0, winner != 1],
+        // Nobody lost if nobody won, so let both players pick
+        (PerkDraft::Loser, None) | (PerkDraft::Both, _) => [true; PLAYER_COUNT],
+    };
+
+    let mut rng = GameRng::seed_from_u64((seed.seed + round_stats.rounds_played) ^ PERK_SEED_MASK);
+    for (offers, drafting) in perks.offers.iter_mut().zip(drafting) {
+        if drafting {
+            *offers = ALL_PERKS
+                .choose_multiple(&mut rng, PERK_OFFERS)
+                .copied()
+                .collect();
+        }
+    }
+    perks.frames_left = DRAFT_TIMEOUT;
+}
+
+pub fn pick_perks(inputs: Res<PlayerInputs<GgrsConfig>>, mut perks: ResMut<Perks>) {
+    if !perks.drafting() {
+        return;
+    }
+
+    perks.frames_left = perks.frames_left.saturating_sub(1);
+    let timed_out = perks.frames_left == 0;
+    let perks = &mut *perks;
+    for (handle, (offers, picked)) in perks
+        .offers
+        .iter_mut()
+        .zip(perks.picked.iter_mut())
+        .enumerate()
+    {
+        if offers.is_empty() {
+            continue;
+        }
+
+        let choice = match input::perk_choice(inputs[handle].0) {
+            Some(choice) => choice,
+            None if timed_out => 0,
+            None => continue,
+        };
+
+        picked.push(offers[choice]);
+        offers.clear();
+    }
+}
+
+pub fn reset_perks(mut perks: ResMut<Perks>) {
+    *perks = Perks::default();
+}
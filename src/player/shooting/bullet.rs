@@ -8,17 +8,16 @@ use bevy_ggrs::*;
 use crate::audio::RollbackSound;
 use crate::debug::DebugTransform;
 use crate::input;
-use crate::misc::utils::quat_from_vec3;
+use crate::misc::utils::{hash_timer, quat_from_vec3};
+use crate::misc::{math, Balance};
 use crate::network::ggrs_config::GGRS_FPS;
 use crate::network::GgrsConfig;
 use crate::player::Player;
-use crate::world::CollisionEntity;
+use crate::world::map::zone::{EnvironmentZone, ZoneForces};
+use crate::world::{CollisionEntity, RoundFrame};
 use crate::GameAssets;
 
-use super::reloading::OVERHEAT;
-
 pub const BULLET_RADIUS: f32 = 3.0;
-const BULLET_MOVE_SPEED: f32 = 600.0 / GGRS_FPS as f32;
 
 const LEFT_WING_BULLET_SPAWN: Vec3 = Vec3::new(20.0, 10.0, 0.0);
 const RIGHT_WING_BULLET_SPAWN: Vec3 = Vec3::new(20.0, -10.0, 0.0);
@@ -29,14 +28,16 @@ pub struct Bullet {
     current_speed: f32,
     pub damage: u32,
     pub handle: usize,
+    pub radius: f32,
 }
 
 impl Bullet {
-    fn new(player_speed: f32, damage: u32, handle: usize) -> Bullet {
+    fn new(speed: f32, damage: u32, handle: usize, radius: f32) -> Bullet {
         Bullet {
-            current_speed: BULLET_MOVE_SPEED + player_speed,
+            current_speed: speed,
             damage,
             handle,
+            radius,
         }
     }
 }
@@ -44,6 +45,9 @@ impl Bullet {
 impl Hash for Bullet {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.current_speed.to_bits().hash(state);
+        self.damage.hash(state);
+        self.handle.hash(state);
+        self.radius.to_bits().hash(state);
     }
 }
 
@@ -63,7 +67,7 @@ impl BulletTimer {
 
 impl Hash for BulletTimer {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.timer.duration().as_secs_f32().to_bits().hash(state);
+        hash_timer(&self.timer, state);
     }
 }
 
@@ -83,7 +87,7 @@ impl Default for BulletAnimationTimer {
 
 impl Hash for BulletAnimationTimer {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.timer.duration().as_secs_f32().to_bits().hash(state);
+        hash_timer(&self.timer, state);
     }
 }
 
@@ -99,26 +103,43 @@ pub struct BulletFired {
     pub handle: usize,
 }
 
+/// The sprite of a bullet, also used when a bullet gets restored from a snapshot.
+pub fn bullet_visuals(
+    assets: &GameAssets,
+    transform: Transform,
+) -> (BulletAnimationTimer, SpriteSheetBundle) {
+    (
+        BulletAnimationTimer::default(),
+        SpriteSheetBundle {
+            transform,
+            texture_atlas: assets.bullet.clone(),
+            ..default()
+        },
+    )
+}
+
 fn spawn_bullet(
     commands: &mut Commands,
     assets: &Res<GameAssets>,
     frame: &Res<FrameCount>,
+    balance: &Balance,
     player: &Player,
     player_transform: &Transform,
     side_handle: usize,
     ev_bullet_fired: &mut EventWriter<BulletFired>,
 ) {
-    let dir = player_transform.rotation.mul_vec3(Vec3::X);
+    let dir = math::forward(player_transform);
     let (spawn_offset, direction) = if side_handle == 0 {
         (LEFT_WING_BULLET_SPAWN, Vec3::new(-dir.y, dir.x, 0.0))
     } else {
         (RIGHT_WING_BULLET_SPAWN, Vec3::new(dir.y, -dir.x, 0.0))
     };
+    let radius = BULLET_RADIUS * player.stats.bullet_size;
     let transform = Transform::from_translation(
-        player_transform.translation + player_transform.rotation.mul_vec3(spawn_offset),
+        player_transform.translation + math::rotate(player_transform.rotation, spawn_offset),
     )
-    .with_rotation(quat_from_vec3(player_transform.local_x()))
-    .with_scale(Vec3::new(BULLET_RADIUS, BULLET_RADIUS, 1.0));
+    .with_rotation(quat_from_vec3(math::forward(player_transform)))
+    .with_scale(Vec3::new(radius, radius, 1.0));
 
     ev_bullet_fired.send(BulletFired {
         position: transform.translation,
@@ -129,22 +150,18 @@ fn spawn_bullet(
     let bullet_entity = commands
         .spawn((
             Bullet::new(
-                player.current_speed,
-                (player.stats.bullet_damage as f32 * player.speed_ratio()) as u32,
+                balance.bullet_speed() + player.current_speed,
+                (player.stats.bullet_damage as f32 * player.speed_ratio(balance)) as u32,
                 player.handle,
+                radius,
             ),
-            BulletAnimationTimer::default(),
             CollisionEntity::default(),
             DebugTransform::new(&transform),
-            SpriteSheetBundle {
-                transform,
-                texture_atlas: assets.bullet.clone(),
-                ..default()
-            },
+            bullet_visuals(assets, transform),
         ))
         .add_rollback()
         .id();
-    let playback_rate = 1.0 + (player.heat as f64 / OVERHEAT as f64).powi(3) * 0.5;
+    let playback_rate = 1.0 + (player.heat as f64 / balance.overheat() as f64).powi(3) * 0.5;
     commands
         .spawn(RollbackSound {
             clip: assets.bullet_shot.clone(),
@@ -161,6 +178,7 @@ pub fn fire_bullets(
     inputs: Res<PlayerInputs<GgrsConfig>>,
     assets: Res<GameAssets>,
     frame: Res<FrameCount>,
+    balance: Res<Balance>,
     mut players: Query<(&Transform, &mut Player, &mut BulletTimer)>,
     mut ev_bullet_fired: EventWriter<BulletFired>,
 ) {
@@ -177,6 +195,7 @@ pub fn fire_bullets(
             &mut commands,
             &assets,
             &frame,
+            &balance,
             &player,
             player_transform,
             0,
@@ -186,6 +205,7 @@ pub fn fire_bullets(
             &mut commands,
             &assets,
             &frame,
+            &balance,
             &player,
             player_transform,
             1,
@@ -197,10 +217,16 @@ pub fn fire_bullets(
     }
 }
 
-pub fn move_bullets(mut bullets: Query<(&mut Transform, &Bullet, &mut DebugTransform)>) {
+pub fn move_bullets(
+    mut bullets: Query<(&mut Transform, &Bullet, &mut DebugTransform)>,
+    zones: Query<&EnvironmentZone>,
+    round_frame: Res<RoundFrame>,
+) {
     for (mut transform, bullet, mut debug_transform) in &mut bullets {
-        let direction = transform.local_x();
-        transform.translation += direction * bullet.current_speed;
+        let forces = ZoneForces::at(&zones, transform.translation.truncate(), &round_frame);
+        let direction = math::forward(&transform);
+        transform.translation +=
+            direction * bullet.current_speed * forces.speed_factor + forces.wind.extend(0.0);
         debug_transform.update(&transform);
     }
 }
@@ -0,0 +1,188 @@
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use bevy::core::FrameCount;
+use bevy::prelude::*;
+use bevy_ggrs::*;
+use bevy_hanabi::EffectAsset;
+
+use crate::audio::RollbackSound;
+use crate::debug::DebugTransform;
+use crate::input;
+use crate::misc::{math, utils::hash_timer};
+use crate::network::ggrs_config::GGRS_FPS;
+use crate::network::GgrsConfig;
+use crate::player::effect::trail::spawn_trail_effect;
+use crate::player::Player;
+use crate::GameAssets;
+
+const FLARE_COOLDOWN: f32 = 0.75;
+const FLARE_BURN_TIME: f32 = 2.5;
+const FLARE_SIZE: Vec2 = Vec2::new(8.0, 8.0);
+const FLARE_COLOR: Color = Color::rgb(1.0, 0.85, 0.4);
+// Flares are ejected backwards and lose their speed quickly
+const FLARE_EJECT_SPEED: f32 = 180.0 / GGRS_FPS as f32;
+const FLARE_DRAG: f32 = 0.95;
+const FLARE_SPAWN_OFFSET: Vec3 = Vec3::new(-30.0, 0.0, -1.0);
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Hash)]
+pub struct FlareTimer {
+    pub timer: Timer,
+}
+
+impl FlareTimer {
+    pub fn new() -> FlareTimer {
+        let mut timer = Timer::from_seconds(FLARE_COOLDOWN, TimerMode::Once);
+        timer.tick(timer.duration());
+        FlareTimer { timer }
+    }
+}
+
+impl Hash for FlareTimer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_timer(&self.timer, state);
+    }
+}
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Hash)]
+pub struct Flare {
+    pub handle: usize,
+    velocity: Vec2,
+    timer: Timer,
+}
+
+impl Flare {
+    fn new(handle: usize, velocity: Vec2) -> Flare {
+        Flare {
+            handle,
+            velocity,
+            timer: Timer::from_seconds(FLARE_BURN_TIME, TimerMode::Once),
+        }
+    }
+
+    /// Burnt out flares don't lure rockets anymore.
+    pub fn burning(&self) -> bool {
+        !self.timer.finished()
+    }
+}
+
+impl Hash for Flare {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.handle.hash(state);
+        self.velocity.x.to_bits().hash(state);
+        self.velocity.y.to_bits().hash(state);
+        hash_timer(&self.timer, state);
+    }
+}
+
+/// The sprite of a flare, also used when a flare gets restored from a snapshot.
+pub fn flare_visuals(assets: &GameAssets, transform: Transform) -> SpriteBundle {
+    SpriteBundle {
+        transform,
+        texture: assets.white_pixel.clone(),
+        sprite: Sprite {
+            color: FLARE_COLOR,
+            custom_size: Some(FLARE_SIZE),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn spawn_flare(
+    commands: &mut Commands,
+    assets: &Res<GameAssets>,
+    frame: &Res<FrameCount>,
+    effects: &mut ResMut<Assets<EffectAsset>>,
+    player: &Player,
+    player_transform: &Transform,
+) {
+    let transform = Transform::from_translation(
+        player_transform.translation + math::rotate(player_transform.rotation, FLARE_SPAWN_OFFSET),
+    );
+    let forward = math::forward(player_transform).truncate();
+    let velocity = -forward * FLARE_EJECT_SPEED + forward * player.current_speed;
+    let flare_entity = commands
+        .spawn((
+            Flare::new(player.handle, velocity),
+            DebugTransform::new(&transform),
+            flare_visuals(assets, transform),
+        ))
+        .add_rollback()
+        .id();
+    commands
+        .spawn(RollbackSound {
+            clip: assets.rocket_spawn_sound.clone(),
+            start_frame: frame.0 as usize,
+            sub_key: (flare_entity.index() + frame.0) as usize,
+            volume: 0.4,
+            playback_rate: 1.6,
+        })
+        .add_rollback();
+    let trail_effect = spawn_trail_effect(commands, effects, Vec3::ZERO);
+    commands.entity(flare_entity).push_children(&[trail_effect]);
+}
+
+pub fn reload_flares(mut players: Query<&mut FlareTimer, With<Player>>) {
+    for mut flare_timer in &mut players {
+        flare_timer
+            .timer
+            .tick(Duration::from_secs_f64(1.0 / GGRS_FPS as f64));
+    }
+}
+
+pub fn fire_flares(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    frame: Res<FrameCount>,
+    inputs: Res<PlayerInputs<GgrsConfig>>,
+    mut players: Query<(&Transform, &mut Player, &mut FlareTimer)>,
+    mut effects: ResMut<Assets<EffectAsset>>,
+) {
+    for (player_transform, mut player, mut flare_timer) in &mut players {
+        let (input, _) = inputs[player.handle];
+        if !input::flare(input) || !flare_timer.timer.finished() || player.flares == 0 {
+            continue;
+        }
+
+        spawn_flare(
+            &mut commands,
+            &assets,
+            &frame,
+            &mut effects,
+            &player,
+            player_transform,
+        );
+
+        player.flares -= 1;
+        flare_timer.timer.reset();
+    }
+}
+
+pub fn move_flares(
+    mut flares: Query<(&mut Transform, &mut Flare, &mut Sprite, &mut DebugTransform)>,
+) {
+    for (mut transform, mut flare, mut sprite, mut debug_transform) in &mut flares {
+        flare
+            .timer
+            .tick(Duration::from_secs_f32(1.0 / GGRS_FPS as f32));
+
+        transform.translation += flare.velocity.extend(0.0);
+        flare.velocity *= FLARE_DRAG;
+
+        // Fade out the flare as it burns down
+        sprite.color.set_a(1.0 - flare.timer.percent());
+        debug_transform.update(&transform);
+    }
+}
+
+pub fn despawn_flares(mut commands: Commands, flares: Query<(Entity, &Flare)>) {
+    for (entity, flare) in &flares {
+        if !flare.burning() {
+            // Don't despawn recursively, the trail should fade out on its own
+            commands.entity(entity).despawn();
+        }
+    }
+}
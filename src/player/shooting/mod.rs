@@ -1,4 +1,5 @@
 pub mod bullet;
+pub mod flare;
 pub mod reloading;
 pub mod rocket;
 pub mod rocket_explosion;
@@ -8,7 +9,7 @@ mod bullet_casing;
 use bevy::prelude::*;
 use bevy_ggrs::GgrsSchedule;
 
-use crate::player::InGameSet;
+use crate::player::{health, InGameSet};
 use crate::{GameState, RollbackState};
 
 pub struct ShootingPlugin;
@@ -43,6 +44,9 @@ impl Plugin for ShootingPlugin {
                 bullet::fire_bullets,
                 bullet::move_bullets,
                 rocket::fire_rockets,
+                flare::reload_flares,
+                flare::fire_flares,
+                flare::move_flares,
                 rocket::toggle_visibility_dummy_rockets,
                 rocket::update_rocket_targets,
                 rocket::move_rockets,
@@ -54,6 +58,13 @@ impl Plugin for ShootingPlugin {
                 .in_set(InGameSet::Shooting)
                 .distributive_run_if(in_state(RollbackState::InRound)),
         )
+        .add_systems(
+            GgrsSchedule,
+            rocket_explosion::detonate_rocket_explosions
+                .before(health::damage_players)
+                .in_set(InGameSet::Health)
+                .distributive_run_if(in_state(RollbackState::InRound)),
+        )
         .add_event::<bullet::BulletCollided>()
         .add_event::<bullet::BulletFired>()
         .add_systems(
@@ -62,6 +73,7 @@ impl Plugin for ShootingPlugin {
                 bullet::destroy_bullets,
                 rocket::disable_rockets,
                 rocket::destroy_rockets,
+                flare::despawn_flares,
                 rocket_explosion::despawn_rocket_explosions
                     .after(rocket_explosion::animate_rocket_explosions),
             )
@@ -4,6 +4,7 @@ use bevy_ggrs::*;
 
 use crate::audio::RollbackSound;
 use crate::debug::DebugTransform;
+use crate::misc::Balance;
 use crate::network::ggrs_config::GGRS_FPS;
 use crate::player::Player;
 use crate::GameAssets;
@@ -12,7 +13,6 @@ use super::bullet::BulletTimer;
 use super::rocket::RocketTimer;
 use crate::player::spawning::player_spawn_transform;
 
-pub const OVERHEAT: u32 = 1000;
 const HEAT_COOLDOWN_DELTA: u32 = 12;
 const OVERHEAT_COOLDOWN_DELTA: u32 = 5;
 const RELOAD_BAR_COLOR: Color = Color::rgba(0.9, 0.9, 0.9, 0.6);
@@ -63,10 +63,11 @@ pub fn cooldown_heat(
     mut commands: Commands,
     assets: Res<GameAssets>,
     frame: Res<FrameCount>,
+    balance: Res<Balance>,
     mut players: Query<(Entity, &mut Player, &BulletTimer)>,
 ) {
     for (entity, mut player, bullet_timer) in &mut players {
-        if player.heat >= OVERHEAT {
+        if player.heat >= balance.overheat() {
             player.overheated = true;
             commands
                 .spawn(RollbackSound {
@@ -170,6 +171,7 @@ pub fn tick_reload_bars(
         (Without<Player>, Without<ReloadBar>),
     >,
     players: Query<&Player, Without<ReloadBar>>,
+    balance: Res<Balance>,
 ) {
     for (reload_bar, children, mut visibility) in &mut reload_bars {
         *visibility = Visibility::Hidden;
@@ -183,7 +185,7 @@ pub fn tick_reload_bars(
                 .get_mut(children[0])
                 .expect("child of reloadbar (the ticker) is not accessable by it's parent");
 
-            let x_fill = (100 * player.heat / OVERHEAT).clamp(0, 100);
+            let x_fill = (100 * player.heat / balance.overheat()).clamp(0, 100);
             let x_fill = (x_fill as f32 / 100.0) - 0.5;
 
             fill.0.translation = Vec3::new(x_fill, fill.0.translation.y, fill.0.translation.z);
@@ -1,4 +1,3 @@
-use std::f32::consts::PI;
 use std::hash::{Hash, Hasher};
 use std::time::Duration;
 
@@ -11,24 +10,26 @@ use crate::audio::RollbackSound;
 use crate::camera::CameraShake;
 use crate::debug::DebugTransform;
 use crate::input::{self, GamepadRumble};
-use crate::misc::utils::quat_from_vec3;
+use crate::misc::utils::{hash_timer, quat_from_vec3};
+use crate::misc::{math, Balance};
 use crate::network::ggrs_config::GGRS_FPS;
 use crate::network::GgrsConfig;
-use crate::player::LocalPlayerHandle;
+use crate::world::map::destructible::DestructibleWall;
 use crate::world::map::obstacle::{ray_obstacle_collision, Obstacle};
-use crate::world::CollisionEntity;
+use crate::world::map::zone::{ray_cloud_collision, EnvironmentZone, ZoneForces};
+use crate::world::{CollisionEntity, RoundFrame};
 use crate::GameAssets;
 
 use super::super::effect::trail::spawn_trail_effect;
-use super::super::{Player, PLAYER_RADIUS};
+use super::super::Player;
+use super::flare::Flare;
 use super::rocket_explosion::spawn_rocket_explosion;
 
 const ROCKET_RADIUS: f32 = 1.5;
-const ROCKET_MOVE_SPEED: f32 = 700.0 / 60.0;
 const ROCKET_START_TIME: f32 = 0.5;
-const ROCKET_PUSH_STRENGTH: f32 = 20.0;
-const VISIBILITY_ANGLE: f32 = PI / 2.0;
-const DELTA_STEERING: f32 = 2.5 / GGRS_FPS as f32;
+// Rockets within this distance of an enemy flare will chase it instead
+const FLARE_LURE_RADIUS: f32 = 350.0;
+const FLARE_DETONATION_RADIUS: f32 = 15.0;
 
 const LEFT_WING_ROCKET_OFFSET: Vec3 = Vec3::new(8.0, 22.0, -1.0);
 const RIGHT_WING_ROCKET_OFFSET: Vec3 = Vec3::new(8.0, -22.0, -1.0);
@@ -43,6 +44,8 @@ pub struct Rocket {
     current_speed: f32,
     // The target we are aiming at (if it is in sight)
     target: Option<Vec3>,
+    // Whether the rocket got lured by a flare, it won't go back to chasing planes
+    decoyed: bool,
     pub start_timer: Timer,
     pub handle: usize,
 }
@@ -54,19 +57,29 @@ impl Rocket {
             current_speed: player_speed,
             start_timer: Timer::from_seconds(ROCKET_START_TIME, TimerMode::Once),
             target: None,
+            decoyed: false,
             handle,
         }
     }
+
+    /// Whether the rocket is homing in on the enemy plane (and not on a flare).
+    pub fn locked_on(&self) -> bool {
+        self.target.is_some() && !self.decoyed
+    }
 }
 
 impl Hash for Rocket {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        self.left_side.hash(state);
         self.current_speed.to_bits().hash(state);
-        self.start_timer
-            .duration()
-            .as_secs_f32()
-            .to_bits()
-            .hash(state);
+        if let Some(target) = self.target {
+            target.x.to_bits().hash(state);
+            target.y.to_bits().hash(state);
+            target.z.to_bits().hash(state);
+        }
+        self.decoyed.hash(state);
+        hash_timer(&self.start_timer, state);
+        self.handle.hash(state);
     }
 }
 
@@ -86,7 +99,21 @@ impl RocketTimer {
 
 impl Hash for RocketTimer {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.timer.duration().as_secs_f32().to_bits().hash(state);
+        hash_timer(&self.timer, state);
+    }
+}
+
+/// The sprite of a rocket, also used when a rocket gets restored from a snapshot.
+pub fn rocket_visuals(assets: &GameAssets, handle: usize, transform: Transform) -> SpriteBundle {
+    let texture = if handle == 0 {
+        assets.rocket1.clone()
+    } else {
+        assets.rocket2.clone()
+    };
+    SpriteBundle {
+        transform,
+        texture,
+        ..default()
     }
 }
 
@@ -101,24 +128,15 @@ fn spawn_rocket(
     left_side: bool,
 ) {
     let transform = Transform::from_translation(
-        player_transform.translation + player_transform.rotation.mul_vec3(spawn_offset),
+        player_transform.translation + math::rotate(player_transform.rotation, spawn_offset),
     )
-    .with_rotation(quat_from_vec3(player_transform.local_x()));
-    let texture = if player.handle == 0 {
-        assets.rocket1.clone()
-    } else {
-        assets.rocket2.clone()
-    };
+    .with_rotation(quat_from_vec3(math::forward(player_transform)));
     let rocket_entity = commands
         .spawn((
             Rocket::new(left_side, player.current_speed, player.handle),
             CollisionEntity::default(),
             DebugTransform::new(&transform),
-            SpriteBundle {
-                transform,
-                texture,
-                ..default()
-            },
+            rocket_visuals(assets, player.handle, transform),
         ))
         .add_rollback()
         .id();
@@ -181,39 +199,45 @@ pub fn move_rockets(
     assets: Res<GameAssets>,
     frame: Res<FrameCount>,
     mut rockets: Query<(&mut Transform, &mut Rocket, &mut DebugTransform)>,
+    zones: Query<&EnvironmentZone>,
+    round_frame: Res<RoundFrame>,
+    balance: Res<Balance>,
 ) {
     for (mut transform, mut rocket, mut debug_transform) in &mut rockets {
         rocket
             .start_timer
             .tick(Duration::from_secs_f32(1.0 / GGRS_FPS as f32));
 
+        let forces = ZoneForces::at(&zones, transform.translation.truncate(), &round_frame);
         let speed = if !rocket.start_timer.finished() {
             rocket.current_speed
         } else {
-            rocket.current_speed + ROCKET_MOVE_SPEED
+            rocket.current_speed + balance.rocket_speed()
         };
-        let direction = transform.local_x();
+        let speed = speed * forces.speed_factor;
+        let direction = math::forward(&transform);
         transform.translation += direction * speed;
 
         if rocket.start_timer.finished() && rocket.target.is_some() {
-            let dir = transform.rotation.mul_vec3(Vec3::X);
+            let dir = math::forward(&transform);
             let target_dir = rocket.target.unwrap() - transform.translation;
-            let angle = dir.truncate().angle_between(target_dir.truncate());
+            let angle = math::angle_between(dir.truncate(), target_dir.truncate());
 
-            if angle.abs() < DELTA_STEERING {
+            if angle.abs() < balance.rocket_delta_steering() {
                 transform.rotation = quat_from_vec3(target_dir);
             } else {
                 let sign = angle / angle.abs();
-                transform.rotate_z(sign * DELTA_STEERING);
+                math::rotate_z(&mut transform, sign * balance.rocket_delta_steering());
             }
         } else {
-            let dir = transform.rotation.mul_vec3(Vec3::X);
+            let dir = math::forward(&transform);
             let sign = if rocket.left_side { 1.0 } else { -1.0 };
             let dir = Vec3::new(-dir.y, dir.x, 0.0) * sign;
-            transform.translation += dir
-                * (rocket.start_timer.duration().as_secs_f32() - rocket.start_timer.elapsed_secs())
-                    .powi(2)
-                * ROCKET_PUSH_STRENGTH;
+            transform.translation +=
+                dir * math::powi(
+                    rocket.start_timer.duration().as_secs_f32() - rocket.start_timer.elapsed_secs(),
+                    2,
+                ) * balance.rocket_push_strength();
         }
 
         if rocket.start_timer.just_finished() {
@@ -232,15 +256,28 @@ pub fn move_rockets(
     }
 }
 
+/// Disable rockets that hit an enemy plane or reached the flare they were lured by.
+/// The damage itself is dealt by the resulting explosion.
 pub fn disable_rockets(
-    mut players: Query<(&Transform, &mut Player)>,
+    players: Query<(&Transform, &Player)>,
     mut rockets: Query<(&mut CollisionEntity, &Rocket, &Transform)>,
-    mut camera_shake: ResMut<CameraShake>,
-    mut gamepad_rumble: ResMut<GamepadRumble>,
-    local_handle: Res<LocalPlayerHandle>,
+    balance: Res<Balance>,
 ) {
     for (mut collision_entity, rocket, rocket_transform) in &mut rockets {
-        for (player_transform, mut player) in &mut players {
+        if rocket.decoyed {
+            if let Some(target) = rocket.target {
+                if target
+                    .truncate()
+                    .distance_squared(rocket_transform.translation.truncate())
+                    < FLARE_DETONATION_RADIUS * FLARE_DETONATION_RADIUS
+                {
+                    collision_entity.disabled = true;
+                }
+            }
+            continue;
+        }
+
+        for (player_transform, player) in &players {
             if player.handle == rocket.handle {
                 continue;
             }
@@ -255,16 +292,7 @@ pub fn disable_rockets(
                 player_transform.translation.truncate(),
                 rocket_transform.translation.truncate(),
             );
-            if distance < PLAYER_RADIUS * PLAYER_RADIUS + ROCKET_RADIUS * ROCKET_RADIUS {
-                if player.health <= player.stats.max_health / 2 {
-                    player.health = 0;
-                } else {
-                    if player.handle == local_handle.0 {
-                        camera_shake.add_trauma(0.35);
-                        gamepad_rumble.add_rumble(0.5, 0.5);
-                    }
-                    player.health -= player.stats.max_health / 2;
-                }
+            if distance < math::powi(balance.player_radius(), 2) + ROCKET_RADIUS * ROCKET_RADIUS {
                 collision_entity.disabled = true;
             }
         }
@@ -303,11 +331,13 @@ fn spawn_player_wing_rocket(
     texture: Handle<Image>,
     offset: Vec3,
 ) {
+    let transform = Transform::from_translation(offset);
     let dummy_rocket = commands
         .spawn((
             DummyRocket,
+            DebugTransform::new(&transform),
             SpriteBundle {
-                transform: Transform::from_translation(offset),
+                transform,
                 texture,
                 ..default()
             },
@@ -371,12 +401,92 @@ pub fn toggle_visibility_dummy_rockets(
     }
 }
 
+fn in_sight(
+    rocket_transform: &Transform,
+    target: Vec3,
+    obstacles: &Query<(&Obstacle, Option<&DestructibleWall>)>,
+    balance: &Balance,
+) -> bool {
+    if math::angle_between(
+        math::forward(rocket_transform).truncate(),
+        target.truncate() - rocket_transform.translation.truncate(),
+    )
+    .abs()
+        >= balance.rocket_visibility_angle()
+    {
+        return false;
+    }
+
+    !ray_obstacle_collision(
+        rocket_transform.translation.truncate(),
+        target.truncate(),
+        obstacles,
+    )
+}
+
+/// Find the closest burning enemy flare that is in sight and close enough to lure the rocket.
+fn closest_flare(
+    rocket: &Rocket,
+    rocket_transform: &Transform,
+    flares: &Query<(&Flare, &Transform)>,
+    obstacles: &Query<(&Obstacle, Option<&DestructibleWall>)>,
+    balance: &Balance,
+) -> Option<Vec3> {
+    let mut closest: Option<(f32, Vec3)> = None;
+    for (flare, flare_transform) in flares {
+        if flare.handle == rocket.handle || !flare.burning() {
+            continue;
+        }
+
+        let distance = rocket_transform
+            .translation
+            .truncate()
+            .distance_squared(flare_transform.translation.truncate());
+        if distance > FLARE_LURE_RADIUS * FLARE_LURE_RADIUS {
+            continue;
+        }
+        if let Some((closest_distance, _)) = closest {
+            if closest_distance <= distance {
+                continue;
+            }
+        }
+        if !in_sight(
+            rocket_transform,
+            flare_transform.translation,
+            obstacles,
+            balance,
+        ) {
+            continue;
+        }
+
+        closest = Some((distance, flare_transform.translation));
+    }
+    closest.map(|(_, position)| position)
+}
+
 pub fn update_rocket_targets(
     mut rockets: Query<(&mut Rocket, &Transform)>,
     players: Query<(&Player, &Transform)>,
-    obstacles: Query<&Obstacle>,
+    flares: Query<(&Flare, &Transform)>,
+    obstacles: Query<(&Obstacle, Option<&DestructibleWall>)>,
+    zones: Query<&EnvironmentZone>,
+    balance: Res<Balance>,
 ) {
     for (mut rocket, rocket_transform) in &mut rockets {
+        if let Some(flare_position) =
+            closest_flare(&rocket, rocket_transform, &flares, &obstacles, &balance)
+        {
+            rocket.target = Some(flare_position);
+            rocket.decoyed = true;
+            continue;
+        }
+
+        // The flare burnt out or got out of sight, the rocket flies straight from now on
+        if rocket.decoyed {
+            rocket.target = None;
+            continue;
+        }
+
         for (player, player_transform) in &players {
             if player.handle == rocket.handle {
                 continue;
@@ -384,24 +494,19 @@ pub fn update_rocket_targets(
 
             rocket.target = None;
 
-            if rocket_transform
-                .rotation
-                .mul_vec3(Vec3::X)
-                .truncate()
-                .angle_between(
-                    player_transform.translation.truncate()
-                        - rocket_transform.translation.truncate(),
-                )
-                .abs()
-                >= VISIBILITY_ANGLE
-            {
+            if !in_sight(
+                rocket_transform,
+                player_transform.translation,
+                &obstacles,
+                &balance,
+            ) {
                 continue;
             }
-
-            if ray_obstacle_collision(
+            // Planes can hide in clouds
+            if ray_cloud_collision(
+                &zones,
                 rocket_transform.translation.truncate(),
                 player_transform.translation.truncate(),
-                &obstacles,
             ) {
                 continue;
             }
@@ -6,15 +6,37 @@ use bevy::prelude::*;
 use bevy_ggrs::*;
 
 use crate::audio::RollbackSound;
+use crate::camera::CameraShake;
 use crate::debug::DebugTransform;
+use crate::input::GamepadRumble;
+use crate::misc::{math, utils::hash_timer};
 use crate::network::ggrs_config::GGRS_FPS;
+use crate::player::health::PlayerTookDamage;
+use crate::player::{LocalPlayerHandle, Player};
+use crate::world::map::destructible::{DestructibleWall, WallDestroyed};
+use crate::world::map::obstacle::Obstacle;
 use crate::GameAssets;
 
+const EXPLOSION_RADIUS: f32 = 80.0;
+const EXPLOSION_WALL_DAMAGE: u32 = 1500;
+// Planes closer than this to the explosion take the full damage (a direct hit)
+const EXPLOSION_FULL_DAMAGE_RADIUS: f32 = 50.0;
+// The damage and knockback fall off linearly until this distance
+const EXPLOSION_SPLASH_RADIUS: f32 = 160.0;
+// Fraction of max health that a direct hit deals
+const EXPLOSION_DAMAGE_RATIO: f32 = 0.5;
+// Planes only take a fraction of the damage of their own rockets
+const SELF_DAMAGE_RATIO: f32 = 0.25;
+const EXPLOSION_KNOCKBACK_STRENGTH: f32 = 9.0;
+
 #[derive(Component, Default, Reflect, Hash)]
+#[reflect(Hash)]
 pub struct RocketExplosion {
     handle: usize,
     frame: usize,
     disabled: bool,
+    // Whether the explosion already dealt its damage
+    detonated: bool,
 }
 
 impl RocketExplosion {
@@ -23,6 +45,7 @@ impl RocketExplosion {
             handle,
             frame: 0,
             disabled: false,
+            detonated: false,
         }
     }
 }
@@ -43,7 +66,16 @@ impl Default for ExplosionAnimationTimer {
 
 impl Hash for ExplosionAnimationTimer {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.timer.duration().as_secs_f32().to_bits().hash(state);
+        hash_timer(&self.timer, state);
+    }
+}
+
+/// The sprite of an explosion, also used when an explosion gets restored from a snapshot.
+pub fn rocket_explosion_visuals(assets: &GameAssets, transform: Transform) -> SpriteSheetBundle {
+    SpriteSheetBundle {
+        transform,
+        texture_atlas: assets.explosion.clone(),
+        ..default()
     }
 }
 
@@ -60,11 +92,7 @@ pub fn spawn_rocket_explosion(
             RocketExplosion::new(handle),
             ExplosionAnimationTimer::default(),
             DebugTransform::new(&transform),
-            SpriteSheetBundle {
-                transform,
-                texture_atlas: assets.explosion.clone(),
-                ..default()
-            },
+            rocket_explosion_visuals(assets, transform),
         ))
         .add_rollback()
         .id();
@@ -78,6 +106,79 @@ pub fn spawn_rocket_explosion(
         .add_rollback();
 }
 
+/// Linear falloff from 1.0 (direct hit) to 0.0 (outside of the splash radius).
+fn splash_falloff(distance: f32) -> f32 {
+    1.0 - ((distance - EXPLOSION_FULL_DAMAGE_RADIUS)
+        / (EXPLOSION_SPLASH_RADIUS - EXPLOSION_FULL_DAMAGE_RADIUS))
+        .clamp(0.0, 1.0)
+}
+
+fn splash_damage(explosion: &RocketExplosion, player: &Player, falloff: f32) -> u32 {
+    let ratio = if explosion.handle == player.handle {
+        SELF_DAMAGE_RATIO
+    } else {
+        1.0
+    };
+    (player.stats.max_health as f32 * EXPLOSION_DAMAGE_RATIO * falloff * ratio) as u32
+}
+
+pub fn detonate_rocket_explosions(
+    mut explosions: Query<(&Transform, &mut RocketExplosion)>,
+    mut players: Query<(&Transform, &mut Player)>,
+    mut walls: Query<(&Obstacle, &mut DestructibleWall)>,
+    mut camera_shake: ResMut<CameraShake>,
+    mut gamepad_rumble: ResMut<GamepadRumble>,
+    local_handle: Res<LocalPlayerHandle>,
+    mut ev_player_took_damage: EventWriter<PlayerTookDamage>,
+    mut ev_wall_destroyed: EventWriter<WallDestroyed>,
+) {
+    for (explosion_transform, mut explosion) in &mut explosions {
+        if explosion.detonated {
+            continue;
+        }
+        explosion.detonated = true;
+
+        let position = explosion_transform.translation.truncate();
+        for (player_transform, mut player) in &mut players {
+            let offset = player_transform.translation.truncate() - position;
+            let falloff = splash_falloff(offset.length());
+            if falloff == 0.0 {
+                continue;
+            }
+
+            player.knockback += offset.normalize_or_zero() * EXPLOSION_KNOCKBACK_STRENGTH * falloff;
+
+            if player.dodging || player.health == 0 {
+                continue;
+            }
+
+            let damage = splash_damage(&explosion, &player, falloff);
+            if damage == 0 {
+                continue;
+            }
+
+            player.health = player.health.saturating_sub(damage);
+            ev_player_took_damage.send(PlayerTookDamage::new(*player_transform, player.handle));
+            if player.handle == local_handle.0 {
+                camera_shake.add_trauma(0.35 * falloff);
+                gamepad_rumble.add_rumble(0.5 * falloff, 0.5);
+            }
+        }
+
+        for (obstacle, mut wall) in &mut walls {
+            if obstacle.closest_point(position).distance_squared(position)
+                > math::powi(EXPLOSION_RADIUS, 2)
+            {
+                continue;
+            }
+
+            if wall.take_damage(EXPLOSION_WALL_DAMAGE) {
+                ev_wall_destroyed.send(WallDestroyed::new(obstacle));
+            }
+        }
+    }
+}
+
 pub fn animate_rocket_explosions(
     mut query: Query<(
         &mut RocketExplosion,
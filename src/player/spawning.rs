@@ -6,7 +6,9 @@ use bevy_hanabi::EffectAsset;
 use super::dodge::spawn_plane_whites;
 use super::dodge::DodgeTimer;
 use super::effect::trail::spawn_player_trails;
+use super::perk::Perks;
 use super::shooting::bullet::BulletTimer;
+use super::shooting::flare::FlareTimer;
 use super::shooting::rocket::spawn_player_wing_rockets;
 use super::shooting::rocket::RocketTimer;
 use super::LocalPlayerHandle;
@@ -18,7 +20,9 @@ use crate::audio::RollbackSound;
 use crate::camera::CameraShake;
 use crate::debug::DebugTransform;
 use crate::input::GamepadRumble;
-use crate::world::CollisionEntity;
+use crate::misc::Balance;
+use crate::network::ggrs_config::PLAYER_COUNT;
+use crate::world::{CollisionEntity, MatchRules};
 use crate::GameAssets;
 use crate::RollbackState;
 
@@ -46,14 +50,16 @@ fn spawn_player(
     texture: Handle<Image>,
     handle: usize,
     stats: PlayerStats,
+    balance: &Balance,
 ) -> Entity {
     let transform = player_spawn_transform(handle);
     commands
         .spawn((
-            Player::new(handle, stats.clone()),
+            Player::new(handle, stats.clone(), balance),
             BulletTimer::new(stats.bullet_reload_time),
             RocketTimer::new(stats.rocket_reload_time),
             DodgeTimer::new(stats.dodge_cooldown),
+            FlareTimer::new(),
             CollisionEntity::default(),
             DebugTransform::new(&transform),
             SpriteBundle {
@@ -66,25 +72,51 @@ fn spawn_player(
         .id()
 }
 
+/// Spawn the plane of the given player together with everything attached to it.
+pub fn spawn_plane(
+    commands: &mut Commands,
+    assets: &Res<GameAssets>,
+    effects: &mut ResMut<Assets<EffectAsset>>,
+    balance: &Balance,
+    handle: usize,
+    stats: PlayerStats,
+) {
+    let texture = if handle == 0 {
+        assets.player_1.clone()
+    } else {
+        assets.player_2.clone()
+    };
+
+    let player = spawn_player(commands, texture, handle, stats, balance);
+    spawn_player_wing_rockets(commands, assets, player, handle);
+    spawn_plane_whites(commands, assets, balance, player, handle);
+    spawn_player_trails(commands, effects, player);
+}
+
 pub fn spawn_players(
     mut commands: Commands,
     assets: Res<GameAssets>,
     mut effects: ResMut<Assets<EffectAsset>>,
     stats: Res<PersistentPlayerStats>,
+    perks: Res<Perks>,
+    balance: Res<Balance>,
 ) {
-    let textures = [assets.player_1.clone(), assets.player_2.clone()];
-
-    for (handle, texture) in textures.into_iter().enumerate() {
-        let player = spawn_player(&mut commands, texture, handle, stats.stats[handle].clone());
-        spawn_player_wing_rockets(&mut commands, &assets, player, handle);
-        spawn_plane_whites(&mut commands, &assets, player, handle);
-        spawn_player_trails(&mut commands, &mut effects, player);
+    for handle in 0..PLAYER_COUNT {
+        spawn_plane(
+            &mut commands,
+            &assets,
+            &mut effects,
+            &balance,
+            handle,
+            perks.stats(handle, &stats),
+        );
     }
 }
 
 pub fn despawn_players(
     mut commands: Commands,
     players: Query<(Entity, &Player, &CollisionEntity)>,
+    match_rules: Res<MatchRules>,
     mut next_state: ResMut<NextState<RollbackState>>,
 ) {
     for (player_entity, player, collision_entity) in &players {
@@ -93,6 +125,11 @@ pub fn despawn_players(
         }
     }
 
+    // Planes respawn in objective modes, the round ends once the objective is completed
+    if match_rules.mode.objective() {
+        return;
+    }
+
     if players.iter().count() <= 1 {
         next_state.set(RollbackState::RoundEnd);
     }
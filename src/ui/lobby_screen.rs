@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use super::just_pressed;
+use crate::misc::Settings;
+use crate::network::lobby::{lock_in, Lobby};
+use crate::network::socket::AceSocket;
+use crate::world::map::playlist::{MAP_COUNT, MAP_NAMES};
+use crate::{GameAssets, GameState};
+
+#[derive(Component)]
+struct LobbyScreen;
+
+#[derive(Component)]
+struct LobbyText;
+
+/// The map that is currently selected for banning/picking.
+#[derive(Resource, Default)]
+struct MapCursor(usize);
+
+fn map_status(settings: &Settings, map: usize) -> &'static str {
+    if settings.rules.banned_maps.contains(&map) {
+        "BANNED"
+    } else if settings.rules.picked_maps.contains(&map) {
+        "PICKED"
+    } else {
+        "-"
+    }
+}
+
+fn lobby_text(settings: &Settings, lobby: &Lobby, cursor: &MapCursor) -> String {
+    let mut lines = vec![format!(
+        "[V] PLAYLIST VOTE: {}",
+        settings
+            .rules
+            .playlist
+            .name()
+            .replace('_', " ")
+            .to_uppercase()
+    )];
+    for (map, name) in MAP_NAMES.iter().enumerate() {
+        let marker = if map == cursor.0 { ">" } else { " " };
+        lines.push(format!(
+            "{} {}: {}",
+            marker,
+            name,
+            map_status(settings, map)
+        ));
+    }
+    lines.push(String::new());
+    lines.push(if lobby.locked_in {
+        "WAITING FOR YOUR OPPONENT".to_string()
+    } else {
+        "[N] NEXT MAP  [SPACE] BAN / PICK  [P] READY".to_string()
+    });
+    lines.push(if lobby.remote_rules.is_some() {
+        "YOUR OPPONENT IS READY".to_string()
+    } else {
+        "YOUR OPPONENT IS CHOOSING".to_string()
+    });
+    lines.join("\n")
+}
+
+fn spawn_lobby_screen(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    settings: Res<Settings>,
+    lobby: Res<Lobby>,
+    mut cursor: ResMut<MapCursor>,
+) {
+    *cursor = MapCursor::default();
+
+    let root_node = commands
+        .spawn((
+            LobbyScreen,
+            NodeBundle {
+                style: Style {
+                    height: Val::Vh(100.0),
+                    width: Val::Vw(100.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Vh(5.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                z_index: ZIndex::Local(101),
+                ..default()
+            },
+        ))
+        .id();
+    let title_text = commands
+        .spawn(TextBundle::from_section(
+            "BAN AND PICK MAPS",
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 50.0,
+                color: Color::WHITE,
+            },
+        ))
+        .id();
+    let lobby_text = commands
+        .spawn((
+            LobbyText,
+            TextBundle::from_section(
+                lobby_text(&settings, &lobby, &cursor),
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_text_alignment(TextAlignment::Center),
+        ))
+        .id();
+    commands
+        .entity(root_node)
+        .push_children(&[title_text, lobby_text]);
+}
+
+fn despawn_lobby_screen(mut commands: Commands, screens: Query<Entity, With<LobbyScreen>>) {
+    for screen in &screens {
+        commands.entity(screen).despawn_recursive();
+    }
+}
+
+/// The controls don't overlap with the fullscreen toggle (B, DPadUp) or quit (Q, East).
+fn change_choices(
+    keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    button_inputs: Res<Input<GamepadButton>>,
+    mut settings: ResMut<Settings>,
+    mut lobby: ResMut<Lobby>,
+    mut cursor: ResMut<MapCursor>,
+    socket: Option<ResMut<AceSocket>>,
+    mut texts: Query<&mut Text, With<LobbyText>>,
+) {
+    if !lobby.locked_in {
+        if just_pressed(
+            &keys,
+            &gamepads,
+            &button_inputs,
+            KeyCode::V,
+            GamepadButtonType::RightTrigger,
+        ) {
+            settings.rules.playlist = settings.rules.playlist.next();
+        }
+        if just_pressed(
+            &keys,
+            &gamepads,
+            &button_inputs,
+            KeyCode::N,
+            GamepadButtonType::DPadDown,
+        ) {
+            cursor.0 = (cursor.0 + 1) % MAP_COUNT;
+        }
+        if just_pressed(
+            &keys,
+            &gamepads,
+            &button_inputs,
+            KeyCode::Space,
+            GamepadButtonType::West,
+        ) {
+            settings.rules.toggle_map(cursor.0);
+        }
+        if just_pressed(
+            &keys,
+            &gamepads,
+            &button_inputs,
+            KeyCode::P,
+            GamepadButtonType::South,
+        ) {
+            if let Some(mut socket) = socket {
+                lock_in(&mut lobby, &mut socket, &settings);
+            }
+        }
+    }
+
+    if !settings.is_changed() && !lobby.is_changed() && !cursor.is_changed() {
+        return;
+    }
+    for mut text in &mut texts {
+        text.sections[0].value = lobby_text(&settings, &lobby, &cursor);
+    }
+}
+
+pub struct LobbyUiPlugin;
+
+impl Plugin for LobbyUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MapCursor>()
+            .add_systems(Update, change_choices.run_if(in_state(GameState::Lobby)))
+            .add_systems(OnEnter(GameState::Lobby), spawn_lobby_screen)
+            .add_systems(OnExit(GameState::Lobby), despawn_lobby_screen);
+    }
+}
@@ -1,10 +1,52 @@
 use bevy::prelude::*;
 
+use super::just_pressed;
+use crate::misc::Settings;
 use crate::{GameAssets, GameState};
 
 #[derive(Component)]
 struct MainMenuScreen;
 
+#[derive(Component)]
+struct SettingsText;
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "ON"
+    } else {
+        "OFF"
+    }
+}
+
+fn settings_text(settings: &Settings) -> String {
+    [
+        format!("[M] MISSILE WARNING: {}", on_off(settings.missile_warning)),
+        format!(
+            "[G] GAME MODE: {}",
+            settings.rules.mode.name().replace('_', " ").to_uppercase()
+        ),
+        format!(
+            "[T] PERK DRAFT: {}",
+            settings.rules.perk_draft.name().to_uppercase()
+        ),
+        format!("[X] CHEATS: {}", on_off(settings.rules.cheats)),
+        format!(
+            "[K] PLANE COLLISION: {}",
+            settings.rules.plane_collision.name().to_uppercase()
+        ),
+        format!(
+            "[L] BORDER: {}",
+            settings
+                .rules
+                .border
+                .name()
+                .replace('_', " ")
+                .to_uppercase()
+        ),
+    ]
+    .join("\n")
+}
+
 fn spawn_title_text(commands: &mut Commands, font: Handle<Font>) -> Entity {
     let text_style = TextStyle {
         font,
@@ -39,7 +81,19 @@ fn spawn_quit_text(commands: &mut Commands, font: Handle<Font>) -> Entity {
     commands.spawn(text_bundle).id()
 }
 
-fn spawn_text(commands: &mut Commands, font: Handle<Font>) {
+fn spawn_settings_text(commands: &mut Commands, font: Handle<Font>, settings: &Settings) -> Entity {
+    let text_style = TextStyle {
+        font,
+        font_size: 20.0,
+        color: Color::WHITE,
+    };
+    let text_bundle =
+        TextBundle::from_sections([TextSection::new(settings_text(settings), text_style)])
+            .with_text_alignment(TextAlignment::Center);
+    commands.spawn((SettingsText, text_bundle)).id()
+}
+
+fn spawn_text(commands: &mut Commands, font: Handle<Font>, settings: &Settings) {
     let text_root_node = commands
         .spawn((
             MainMenuScreen,
@@ -62,13 +116,21 @@ fn spawn_text(commands: &mut Commands, font: Handle<Font>) {
     let title_text = spawn_title_text(commands, font.clone());
     let play_text = spawn_play_text(commands, font.clone());
     let quit_text = spawn_quit_text(commands, font.clone());
-    commands
-        .entity(text_root_node)
-        .push_children(&[title_text, play_text, quit_text]);
+    let settings_text = spawn_settings_text(commands, font.clone(), settings);
+    commands.entity(text_root_node).push_children(&[
+        title_text,
+        play_text,
+        quit_text,
+        settings_text,
+    ]);
 }
 
-fn spawn_main_menu_screen(mut commands: Commands, assets: Res<GameAssets>) {
-    spawn_text(&mut commands, assets.font.clone());
+fn spawn_main_menu_screen(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    settings: Res<Settings>,
+) {
+    spawn_text(&mut commands, assets.font.clone(), &settings);
 }
 
 fn despawn_main_menu_screen(
@@ -99,12 +161,85 @@ fn play_game(
     }
 }
 
+fn change_settings(
+    keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    button_inputs: Res<Input<GamepadButton>>,
+    mut settings: ResMut<Settings>,
+    mut texts: Query<&mut Text, With<SettingsText>>,
+) {
+    if just_pressed(
+        &keys,
+        &gamepads,
+        &button_inputs,
+        KeyCode::M,
+        GamepadButtonType::Select,
+    ) {
+        settings.missile_warning = !settings.missile_warning;
+    }
+    if just_pressed(
+        &keys,
+        &gamepads,
+        &button_inputs,
+        KeyCode::G,
+        GamepadButtonType::LeftTrigger,
+    ) {
+        settings.rules.mode = settings.rules.mode.next();
+    }
+    if just_pressed(
+        &keys,
+        &gamepads,
+        &button_inputs,
+        KeyCode::T,
+        GamepadButtonType::LeftThumb,
+    ) {
+        settings.rules.perk_draft = settings.rules.perk_draft.next();
+    }
+    if just_pressed(
+        &keys,
+        &gamepads,
+        &button_inputs,
+        KeyCode::X,
+        GamepadButtonType::RightThumb,
+    ) {
+        settings.rules.cheats = !settings.rules.cheats;
+    }
+    if just_pressed(
+        &keys,
+        &gamepads,
+        &button_inputs,
+        KeyCode::K,
+        GamepadButtonType::DPadRight,
+    ) {
+        settings.rules.plane_collision = settings.rules.plane_collision.next();
+    }
+    if just_pressed(
+        &keys,
+        &gamepads,
+        &button_inputs,
+        KeyCode::L,
+        GamepadButtonType::DPadLeft,
+    ) {
+        settings.rules.border = settings.rules.border.next();
+    }
+
+    if !settings.is_changed() {
+        return;
+    }
+    for mut text in &mut texts {
+        text.sections[0].value = settings_text(&settings);
+    }
+}
+
 pub struct MainMenuUiPlugin;
 
 impl Plugin for MainMenuUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (play_game.run_if(in_state(GameState::MainMenu)),))
-            .add_systems(OnEnter(GameState::MainMenu), spawn_main_menu_screen)
-            .add_systems(OnExit(GameState::MainMenu), despawn_main_menu_screen);
+        app.add_systems(
+            Update,
+            (play_game, change_settings).run_if(in_state(GameState::MainMenu)),
+        )
+        .add_systems(OnEnter(GameState::MainMenu), spawn_main_menu_screen)
+        .add_systems(OnExit(GameState::MainMenu), despawn_main_menu_screen);
     }
 }
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 
+use crate::network::session::Ready;
 use crate::{GameAssets, GameState};
 
 #[derive(Component)]
@@ -8,6 +9,7 @@ struct MatchmakingScreen;
 struct MatchmakingText;
 
 const MESSAGE: &str = "WAITING FOR 1 OTHER PLAYER";
+const BALANCE_MISMATCH_MESSAGE: &str = "YOUR OPPONENT USES A DIFFERENT BALANCE";
 const LOADING_TICKER_TIME: f32 = 0.15;
 
 fn spawn_title_text(commands: &mut Commands, font: Handle<Font>) -> Entity {
@@ -76,6 +78,7 @@ fn animate_matchmaking_screen(
     mut ticks: Local<f32>,
     mut forward: Local<bool>,
     time: Res<Time>,
+    ready: Res<Ready>,
 ) {
     let mut text = if let Ok(t) = query.get_single_mut() {
         t
@@ -84,6 +87,11 @@ fn animate_matchmaking_screen(
         return;
     };
 
+    if ready.balance_mismatch {
+        text.sections[0].value = BALANCE_MISMATCH_MESSAGE.to_string();
+        return;
+    }
+
     *ticks += time.delta_seconds();
     if *ticks < LOADING_TICKER_TIME {
         return;
@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+
+use crate::audio::FadedLoopSound;
+use crate::camera::{edge_of_view, view_scale};
+use crate::misc::Settings;
+use crate::player::shooting::rocket::Rocket;
+use crate::player::{LocalPlayerHandle, Player};
+use crate::{GameAssets, GameState};
+
+const MAX_INDICATORS: usize = 4;
+const INDICATOR_SIZE: Vec2 = Vec2::new(36.0, 8.0);
+// Distance of the indicators to the edge of the screen
+const INDICATOR_MARGIN: f32 = 40.0;
+const INDICATOR_COLOR: Color = Color::rgb(0.95, 0.2, 0.2);
+// The warning gets more intense the closer the rocket is
+const WARNING_FAR_DISTANCE: f32 = 900.0;
+const WARNING_NEAR_DISTANCE: f32 = 100.0;
+const MIN_TONE_PLAYBACK_RATE: f64 = 1.0;
+const MAX_TONE_PLAYBACK_RATE: f64 = 2.5;
+const TONE_VOLUME: f64 = 0.25;
+
+#[derive(Component)]
+struct MissileWarningIndicator {
+    index: usize,
+}
+
+#[derive(Component)]
+struct MissileWarningTone;
+
+/// 0.0 when the rocket is far away, 1.0 when it's about to hit.
+fn closeness(distance: f32) -> f32 {
+    1.0 - ((distance - WARNING_NEAR_DISTANCE) / (WARNING_FAR_DISTANCE - WARNING_NEAR_DISTANCE))
+        .clamp(0.0, 1.0)
+}
+
+fn spawn_missile_warnings(mut commands: Commands, assets: Res<GameAssets>) {
+    for index in 0..MAX_INDICATORS {
+        commands.spawn((
+            MissileWarningIndicator { index },
+            SpriteBundle {
+                texture: assets.white_pixel.clone(),
+                sprite: Sprite {
+                    color: INDICATOR_COLOR,
+                    custom_size: Some(INDICATOR_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, 50.0),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ));
+    }
+
+    commands.spawn((
+        MissileWarningTone,
+        FadedLoopSound {
+            clip: assets.rocket_reload.clone(),
+            volume: TONE_VOLUME,
+            should_play: false,
+            ..default()
+        },
+    ));
+}
+
+fn despawn_missile_warnings(
+    mut commands: Commands,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    indicators: Query<Entity, With<MissileWarningIndicator>>,
+    mut tones: Query<(Entity, &mut FadedLoopSound), With<MissileWarningTone>>,
+) {
+    for entity in &indicators {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // The main menu purges all entities right away, so we have to stop the tone ourselves
+    for (entity, mut tone) in &mut tones {
+        if let Some(instance_handle) = tone.audio_instance.take() {
+            if let Some(instance) = audio_instances.get_mut(&instance_handle) {
+                instance.stop(AudioTween::default());
+            }
+        }
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_missile_warnings(
+    settings: Res<Settings>,
+    local_handle: Option<Res<LocalPlayerHandle>>,
+    camera: Query<(&Transform, &OrthographicProjection), With<Camera2d>>,
+    players: Query<(&Player, &Transform)>,
+    rockets: Query<(&Rocket, &Transform)>,
+    mut indicators: Query<
+        (
+            &MissileWarningIndicator,
+            &mut Transform,
+            &mut Sprite,
+            &mut Visibility,
+        ),
+        (Without<Player>, Without<Rocket>, Without<Camera2d>),
+    >,
+    mut tones: Query<&mut FadedLoopSound, With<MissileWarningTone>>,
+) {
+    let local_handle = match local_handle {
+        Some(handle) => handle.0,
+        None => return,
+    };
+    let player_position = players
+        .iter()
+        .find(|(player, _)| player.handle == local_handle)
+        .map(|(_, transform)| transform.translation.truncate());
+
+    let mut locked_rockets = Vec::new();
+    if let Some(player_position) = player_position {
+        if settings.missile_warning {
+            for (rocket, rocket_transform) in &rockets {
+                if rocket.handle == local_handle || !rocket.locked_on() {
+                    continue;
+                }
+                let rocket_position = rocket_transform.translation.truncate();
+                locked_rockets.push((rocket_position, player_position.distance(rocket_position)));
+            }
+        }
+    }
+    locked_rockets.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    let (camera_transform, projection) = camera.single();
+    let camera_position = camera_transform.translation.truncate();
+    let scale = view_scale(projection);
+    for (indicator, mut transform, mut sprite, mut visibility) in &mut indicators {
+        let (rocket_position, distance) = match locked_rockets.get(indicator.index) {
+            Some(locked_rocket) => *locked_rocket,
+            None => {
+                *visibility = Visibility::Hidden;
+                continue;
+            }
+        };
+
+        let position = edge_of_view(
+            camera_position,
+            projection.area,
+            rocket_position,
+            INDICATOR_MARGIN * scale,
+        );
+        let dir = rocket_position - camera_position;
+        transform.translation = position.extend(transform.translation.z);
+        transform.rotation = Quat::from_rotation_z(dir.y.atan2(dir.x));
+        transform.scale = Vec3::new(scale, scale, 1.0);
+        sprite.color.set_a(0.4 + 0.6 * closeness(distance));
+        *visibility = Visibility::Visible;
+    }
+
+    let mut tone = match tones.get_single_mut() {
+        Ok(tone) => tone,
+        Err(_) => return,
+    };
+    match locked_rockets.first() {
+        Some((_, distance)) => {
+            let playback_rate = MIN_TONE_PLAYBACK_RATE
+                + (MAX_TONE_PLAYBACK_RATE - MIN_TONE_PLAYBACK_RATE) * closeness(*distance) as f64;
+            // Only touch the sound if something changed, changes are forwarded to the audio instance
+            if !tone.should_play || (tone.playback_rate - playback_rate).abs() > 0.05 {
+                tone.should_play = true;
+                tone.playback_rate = playback_rate;
+            }
+        }
+        None => {
+            if tone.should_play {
+                tone.should_play = false;
+            }
+        }
+    }
+}
+
+pub struct MissileWarningPlugin;
+
+impl Plugin for MissileWarningPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::InRollbackGame), spawn_missile_warnings)
+            .add_systems(OnExit(GameState::InRollbackGame), despawn_missile_warnings)
+            .add_systems(
+                Update,
+                update_missile_warnings.run_if(in_state(GameState::InRollbackGame)),
+            );
+    }
+}
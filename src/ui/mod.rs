@@ -1,8 +1,13 @@
 pub mod round_start_screen;
 
 mod game_over_screen;
+mod lobby_screen;
 mod main_menu_screen;
 mod matchmaking_screen;
+mod missile_warning;
+mod objective_hud;
+mod offscreen_indicator;
+mod perk_draft_screen;
 mod round_over_screen;
 mod scoreboard;
 mod seed_screen;
@@ -10,6 +15,20 @@ mod session_stats_screen;
 
 use bevy::prelude::*;
 
+/// Whether the key or the button of any gamepad was just pressed.
+pub(crate) fn just_pressed(
+    keys: &Input<KeyCode>,
+    gamepads: &Gamepads,
+    button_inputs: &Input<GamepadButton>,
+    key: KeyCode,
+    button: GamepadButtonType,
+) -> bool {
+    keys.just_pressed(key)
+        || gamepads
+            .iter()
+            .any(|gamepad| button_inputs.just_pressed(GamepadButton::new(gamepad, button)))
+}
+
 pub struct AceUiPlugin;
 
 impl Plugin for AceUiPlugin {
@@ -17,6 +36,11 @@ impl Plugin for AceUiPlugin {
         app.add_plugins((
             main_menu_screen::MainMenuUiPlugin,
             matchmaking_screen::MatchmakingUiPlugin,
+            lobby_screen::LobbyUiPlugin,
+            missile_warning::MissileWarningPlugin,
+            objective_hud::ObjectiveHudPlugin,
+            offscreen_indicator::OffscreenIndicatorPlugin,
+            perk_draft_screen::PerkDraftUiPlugin,
             scoreboard::ScoreboardUiPlugin,
             session_stats_screen::SessionStatsPlugin,
             seed_screen::SeedUiPlugin,
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use bevy_ggrs::AddRollbackCommandExtension;
+
+use crate::network::ggrs_config::GGRS_FPS;
+use crate::player::{P1_COLOR, P2_COLOR};
+use crate::world::{GameMode, MatchRules, ObjectiveState, CAPTURES_TO_WIN, HILL_POINTS_TO_WIN};
+use crate::{GameAssets, GameState, RollbackState};
+
+#[derive(Component)]
+struct ObjectiveText;
+
+fn spawn_objective_hud(mut commands: Commands, assets: Res<GameAssets>) {
+    let text_style = TextStyle {
+        font: assets.font.clone(),
+        font_size: 25.0,
+        color: Color::WHITE,
+    };
+    let root_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Vw(100.0),
+                top: Val::Vh(15.0),
+                justify_content: JustifyContent::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            ..default()
+        })
+        .add_rollback()
+        .id();
+    let text = commands
+        .spawn((
+            ObjectiveText,
+            TextBundle::from_sections([
+                TextSection::new(
+                    "",
+                    TextStyle {
+                        color: P1_COLOR,
+                        ..text_style.clone()
+                    },
+                ),
+                TextSection::new("", text_style.clone()),
+                TextSection::new(
+                    "",
+                    TextStyle {
+                        color: P2_COLOR,
+                        ..text_style.clone()
+                    },
+                ),
+                TextSection::new("", text_style),
+            ])
+            .with_text_alignment(TextAlignment::Center),
+        ))
+        .add_rollback()
+        .id();
+    commands.entity(root_node).push_children(&[text]);
+}
+
+fn update_objective_hud(
+    match_rules: Res<MatchRules>,
+    objective_state: Res<ObjectiveState>,
+    mut texts: Query<&mut Text, With<ObjectiveText>>,
+) {
+    let points = objective_state.points;
+    let values = match match_rules.mode {
+        GameMode::Elimination => [String::new(), String::new(), String::new(), String::new()],
+        GameMode::KingOfTheHill => {
+            let seconds = |frames: u32| format!("{:.1}", frames as f32 / GGRS_FPS as f32);
+            [
+                seconds(points[0]),
+                " - ".to_string(),
+                seconds(points[1]),
+                format!("\nHOLD THE HILL FOR {}s", seconds(HILL_POINTS_TO_WIN)),
+            ]
+        }
+        GameMode::CaptureTheFlag => {
+            let carrying = match objective_state.flag_carriers {
+                [Some(_), _] | [_, Some(_)] => "\nFLAG TAKEN",
+                _ => "",
+            };
+            [
+                points[0].to_string(),
+                " - ".to_string(),
+                points[1].to_string(),
+                format!("\nCAPTURE {} FLAGS{}", CAPTURES_TO_WIN, carrying),
+            ]
+        }
+    };
+
+    for mut text in &mut texts {
+        for (section, value) in text.sections.iter_mut().zip(values.iter()) {
+            if section.value != *value {
+                section.value = value.clone();
+            }
+        }
+    }
+}
+
+pub struct ObjectiveHudPlugin;
+
+impl Plugin for ObjectiveHudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(RollbackState::Setup), spawn_objective_hud)
+            .add_systems(
+                Update,
+                update_objective_hud.run_if(in_state(GameState::InRollbackGame)),
+            );
+    }
+}
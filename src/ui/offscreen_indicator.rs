@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::camera::{edge_of_view, view_scale};
+use crate::player::{LocalPlayerHandle, Player, P1_COLOR, P2_COLOR};
+use crate::{GameAssets, GameState};
+
+const INDICATOR_SIZE: Vec2 = Vec2::new(16.0, 16.0);
+const INDICATOR_MARGIN: f32 = 25.0;
+
+/// Points towards the opponent when they are not on screen.
+#[derive(Component)]
+struct OffscreenIndicator {
+    handle: usize,
+}
+
+fn spawn_offscreen_indicators(mut commands: Commands, assets: Res<GameAssets>) {
+    for (handle, color) in [P1_COLOR, P2_COLOR].into_iter().enumerate() {
+        commands.spawn((
+            OffscreenIndicator { handle },
+            SpriteBundle {
+                texture: assets.white_pixel.clone(),
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(INDICATOR_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, 50.0),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+        ));
+    }
+}
+
+fn despawn_offscreen_indicators(
+    mut commands: Commands,
+    indicators: Query<Entity, With<OffscreenIndicator>>,
+) {
+    for entity in &indicators {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_offscreen_indicators(
+    local_handle: Option<Res<LocalPlayerHandle>>,
+    camera: Query<(&Transform, &OrthographicProjection), With<Camera2d>>,
+    players: Query<(&Player, &Transform), Without<OffscreenIndicator>>,
+    mut indicators: Query<
+        (&OffscreenIndicator, &mut Transform, &mut Visibility),
+        (Without<Player>, Without<Camera2d>),
+    >,
+) {
+    let local_handle = match local_handle {
+        Some(handle) => handle.0,
+        None => return,
+    };
+    let (camera_transform, projection) = camera.single();
+    let camera_position = camera_transform.translation.truncate();
+    let view = Rect::from_center_size(camera_position, projection.area.size());
+    let scale = view_scale(projection);
+
+    for (indicator, mut transform, mut visibility) in &mut indicators {
+        *visibility = Visibility::Hidden;
+        if indicator.handle == local_handle {
+            continue;
+        }
+
+        let Some((_, player_transform)) = players
+            .iter()
+            .find(|(player, _)| player.handle == indicator.handle)
+        else {
+            continue;
+        };
+        let player_position = player_transform.translation.truncate();
+        if view.contains(player_position) {
+            continue;
+        }
+
+        let position = edge_of_view(
+            camera_position,
+            projection.area,
+            player_position,
+            INDICATOR_MARGIN * scale,
+        );
+        let dir = player_position - camera_position;
+        transform.translation = position.extend(transform.translation.z);
+        // Rotated by 45 degrees so that one corner points at the opponent
+        transform.rotation =
+            Quat::from_rotation_z(dir.y.atan2(dir.x) + std::f32::consts::FRAC_PI_4);
+        transform.scale = Vec3::new(scale, scale, 1.0);
+        *visibility = Visibility::Visible;
+    }
+}
+
+pub struct OffscreenIndicatorPlugin;
+
+impl Plugin for OffscreenIndicatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::InRollbackGame),
+            spawn_offscreen_indicators,
+        )
+        .add_systems(
+            OnExit(GameState::InRollbackGame),
+            despawn_offscreen_indicators,
+        )
+        .add_systems(
+            Update,
+            update_offscreen_indicators.run_if(in_state(GameState::InRollbackGame)),
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use bevy_ggrs::AddRollbackCommandExtension;
+
+use crate::player::perk::Perks;
+use crate::player::LocalPlayerHandle;
+use crate::{GameAssets, GameState, RollbackState};
+
+#[derive(Component)]
+struct PerkDraftText;
+
+fn spawn_perk_draft_screen(mut commands: Commands, assets: Res<GameAssets>) {
+    let root_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Vw(100.0),
+                top: Val::Vh(65.0),
+                justify_content: JustifyContent::Center,
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            z_index: ZIndex::Global(101),
+            ..default()
+        })
+        .add_rollback()
+        .id();
+    let text = commands
+        .spawn((
+            PerkDraftText,
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 30.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_text_alignment(TextAlignment::Center),
+        ))
+        .add_rollback()
+        .id();
+    commands.entity(root_node).push_children(&[text]);
+}
+
+fn perk_draft_text(perks: &Perks, local_handle: usize) -> String {
+    if !perks.drafting() {
+        return String::new();
+    }
+
+    let offers = &perks.offers[local_handle];
+    if offers.is_empty() {
+        return "WAITING FOR YOUR OPPONENT TO PICK A PERK".to_string();
+    }
+
+    let mut lines = vec!["PICK A PERK".to_string()];
+    for (i, perk) in offers.iter().enumerate() {
+        lines.push(format!("[{}] {}", i + 1, perk.name()));
+    }
+    lines.join("\n")
+}
+
+fn update_perk_draft_screen(
+    perks: Res<Perks>,
+    local_handle: Res<LocalPlayerHandle>,
+    mut texts: Query<&mut Text, With<PerkDraftText>>,
+) {
+    let value = perk_draft_text(&perks, local_handle.0);
+    for mut text in &mut texts {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+pub struct PerkDraftUiPlugin;
+
+impl Plugin for PerkDraftUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(RollbackState::Setup), spawn_perk_draft_screen)
+            .add_systems(
+                Update,
+                update_perk_draft_screen.run_if(in_state(GameState::InRollbackGame)),
+            );
+    }
+}
@@ -1,3 +1,4 @@
+use std::hash::{Hash, Hasher};
 use std::time::Duration;
 
 use bevy::core::FrameCount;
@@ -5,13 +6,14 @@ use bevy::prelude::*;
 use bevy_ggrs::{AddRollbackCommandExtension, GgrsSchedule};
 
 use crate::audio::RollbackSound;
+use crate::misc::utils::hash_timer;
 use crate::player::spawning::despawn_players;
 use crate::player::{LocalPlayerHandle, P1_COLOR, P2_COLOR};
 use crate::world::{check_rematch, round_end_timeout};
 use crate::{GameAssets, RollbackState};
 
 #[derive(Resource, Reflect, Deref, DerefMut)]
-#[reflect(Resource)]
+#[reflect(Resource, Hash)]
 pub struct RoundStartTimer(Timer);
 
 impl Default for RoundStartTimer {
@@ -20,8 +22,14 @@ impl Default for RoundStartTimer {
     }
 }
 
+impl Hash for RoundStartTimer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_timer(&self.0, state);
+    }
+}
+
 #[derive(Resource, Reflect, Deref, DerefMut)]
-#[reflect(Resource)]
+#[reflect(Resource, Hash)]
 pub struct HideScreenTimer(Timer);
 
 impl Default for HideScreenTimer {
@@ -30,6 +38,12 @@ impl Default for HideScreenTimer {
     }
 }
 
+impl Hash for HideScreenTimer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_timer(&self.0, state);
+    }
+}
+
 #[derive(Component)]
 struct RoundStartScreen;
 #[derive(Component)]
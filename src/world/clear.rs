@@ -2,9 +2,12 @@ use bevy::prelude::*;
 use bevy_ggrs::Session;
 
 use super::map;
-use super::{RoundEndTimer, RoundStats, Score, Seeds};
+use super::objective::ObjectiveEntity;
+use super::{RoundEndTimer, RoundFrame, RoundStats, Score, Seeds};
 use crate::audio::{BgmStage, PlaybackStates};
 use crate::misc::DeadSprite;
+use crate::network::lobby::Lobby;
+use crate::network::local::LoopbackPeer;
 use crate::network::session::Ready;
 use crate::network::socket::AceSocket;
 use crate::network::GgrsConfig;
@@ -17,10 +20,13 @@ fn clear_world(
     players: Query<Entity, With<player::Player>>,
     bullets: Query<Entity, With<player::shooting::bullet::Bullet>>,
     rockets: Query<Entity, With<player::shooting::rocket::Rocket>>,
+    flares: Query<Entity, With<player::shooting::flare::Flare>>,
     rocket_explosions: Query<Entity, With<player::shooting::rocket_explosion::RocketExplosion>>,
     health_bars: Query<Entity, With<player::health::HealthBar>>,
     reload_bars: Query<Entity, With<player::shooting::reloading::ReloadBar>>,
     obstacles: Query<Entity, With<map::obstacle::Obstacle>>,
+    zones: Query<Entity, With<map::zone::EnvironmentZone>>,
+    objective_entities: Query<Entity, With<ObjectiveEntity>>,
     dead_sprites: Query<Entity, With<DeadSprite>>,
 ) {
     for player in &players {
@@ -35,6 +41,10 @@ fn clear_world(
         commands.entity(rocket).despawn_recursive();
     }
 
+    for flare in &flares {
+        commands.entity(flare).despawn_recursive();
+    }
+
     for rocket_explosion in &rocket_explosions {
         commands.entity(rocket_explosion).despawn_recursive();
     }
@@ -51,6 +61,14 @@ fn clear_world(
         commands.entity(obstacle).despawn_recursive();
     }
 
+    for zone in &zones {
+        commands.entity(zone).despawn_recursive();
+    }
+
+    for objective_entity in &objective_entities {
+        commands.entity(objective_entity).despawn_recursive();
+    }
+
     for dead_sprite in &dead_sprites {
         commands.entity(dead_sprite).despawn_recursive();
     }
@@ -74,19 +92,23 @@ fn reset_resources(
     mut seeds: ResMut<Seeds>,
     mut score: ResMut<Score>,
     mut round_end_timer: ResMut<RoundEndTimer>,
+    mut round_frame: ResMut<RoundFrame>,
     mut round_start_timer: ResMut<RoundStartTimer>,
     mut hide_screen_timer: ResMut<HideScreenTimer>,
     mut playback_states: ResMut<PlaybackStates>,
     mut ready: ResMut<Ready>,
+    mut lobby: ResMut<Lobby>,
 ) {
     *round_stats = RoundStats::default();
     *seeds = Seeds::default();
     *score = Score::default();
     *round_end_timer = RoundEndTimer::default();
+    *round_frame = RoundFrame::default();
     *round_start_timer = RoundStartTimer::default();
     *hide_screen_timer = HideScreenTimer::default();
     *playback_states = PlaybackStates::default();
     *ready = Ready::default();
+    *lobby = Lobby::default();
 }
 
 fn purge_network_resources(world: &mut World) {
@@ -96,6 +118,9 @@ fn purge_network_resources(world: &mut World) {
     if world.contains_resource::<Session<GgrsConfig>>() {
         world.remove_resource::<Session<GgrsConfig>>();
     }
+    if world.contains_resource::<LoopbackPeer>() {
+        world.remove_resource::<LoopbackPeer>();
+    }
     if world.contains_resource::<LocalPlayerHandle>() {
         world.remove_resource::<LocalPlayerHandle>();
     }
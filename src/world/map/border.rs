@@ -0,0 +1,147 @@
+use std::hash::{Hash, Hasher};
+
+use bevy::prelude::*;
+
+use crate::debug::DebugTransform;
+use crate::misc::{math, utils::quat_from_vec3};
+use crate::player::health::PlayerTookDamage;
+use crate::player::Player;
+use crate::world::{BorderBehavior, CollisionEntity, MatchRules, RoundFrame};
+
+// Size of the default arena, exactly one screen
+const DEFAULT_ARENA_HALF_SIZE: Vec2 = Vec2::new(800.0, 448.0);
+
+// How far planes can fly into the soft wall before they are stopped completely
+const SOFT_WALL_DEPTH: f32 = 150.0;
+// Knockback added per frame at full depth, it builds up because knockback decays slowly
+const SOFT_WALL_PUSH_STRENGTH: f32 = 0.7;
+const SOFT_WALL_DAMAGE: u32 = 100;
+// Planes in the soft wall take damage every this many frames
+const SOFT_WALL_DAMAGE_INTERVAL: u32 = 20;
+
+/// The playable area of the current map, it's centered around the origin.
+/// Each map can set its own size when it gets spawned.
+#[derive(Resource, Reflect, Debug, Clone, Copy)]
+#[reflect(Resource, Hash)]
+pub struct Arena {
+    pub half_size: Vec2,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self {
+            half_size: DEFAULT_ARENA_HALF_SIZE,
+        }
+    }
+}
+
+impl Hash for Arena {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.half_size.x.to_bits().hash(state);
+        self.half_size.y.to_bits().hash(state);
+    }
+}
+
+impl Arena {
+    pub fn new(half_size: Vec2) -> Self {
+        Self { half_size }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.half_size * 2.0
+    }
+
+    pub fn outside(&self, target_position: Vec3) -> bool {
+        target_position.x.abs() > self.half_size.x || target_position.y.abs() > self.half_size.y
+    }
+
+    fn clamp(&self, position: Vec2, margin: f32) -> Vec2 {
+        let half_size = self.half_size + Vec2::splat(margin);
+        position.clamp(-half_size, half_size)
+    }
+
+    fn wrap(&self, position: Vec3) -> Vec3 {
+        let size = self.size();
+        Vec3::new(
+            (position.x + self.half_size.x).rem_euclid(size.x) - self.half_size.x,
+            (position.y + self.half_size.y).rem_euclid(size.y) - self.half_size.y,
+            position.z,
+        )
+    }
+}
+
+/// Reflect the heading of anything that flew over the border and put it back inside.
+fn bounce(arena: &Arena, transform: &mut Transform) {
+    let position = transform.translation.truncate();
+    let clamped_position = arena.clamp(position, 0.0);
+    let mut direction = math::forward(transform);
+    if clamped_position.x != position.x {
+        direction.x = -direction.x;
+    }
+    if clamped_position.y != position.y {
+        direction.y = -direction.y;
+    }
+
+    transform.translation = clamped_position.extend(transform.translation.z);
+    transform.rotation = quat_from_vec3(direction);
+}
+
+/// Push planes in the soft wall back into the arena, returns true if they are in the wall.
+fn push_back(arena: &Arena, transform: &mut Transform, player: &mut Player) -> bool {
+    let position = transform.translation.truncate();
+    let penetration = position - arena.clamp(position, 0.0);
+    if penetration == Vec2::ZERO {
+        return false;
+    }
+
+    player.knockback -= penetration / SOFT_WALL_DEPTH * SOFT_WALL_PUSH_STRENGTH;
+    transform.translation = arena
+        .clamp(position, SOFT_WALL_DEPTH)
+        .extend(transform.translation.z);
+    true
+}
+
+/// Keep planes, bullets and rockets in the arena according to the match rules.
+/// Disabling everything that leaves the arena (instant death) is done in `disable_collision_entities`.
+pub fn apply_border_rules(
+    match_rules: Res<MatchRules>,
+    arena: Res<Arena>,
+    round_frame: Res<RoundFrame>,
+    mut collision_entities: Query<
+        (&mut Transform, &mut DebugTransform, Option<&mut Player>),
+        With<CollisionEntity>,
+    >,
+    mut ev_player_took_damage: EventWriter<PlayerTookDamage>,
+) {
+    for (mut transform, mut debug_transform, player) in &mut collision_entities {
+        match match_rules.border {
+            BorderBehavior::Death => continue,
+            BorderBehavior::Wrap => {
+                if !arena.outside(transform.translation) {
+                    continue;
+                }
+                transform.translation = arena.wrap(transform.translation);
+            }
+            BorderBehavior::Bounce => {
+                if !arena.outside(transform.translation) {
+                    continue;
+                }
+                bounce(&arena, &mut transform);
+            }
+            BorderBehavior::SoftWall => {
+                // Bullets and rockets get absorbed by the wall
+                let Some(mut player) = player else {
+                    continue;
+                };
+                if !push_back(&arena, &mut transform, &mut player) {
+                    continue;
+                }
+                if !player.dodging && round_frame.frame % SOFT_WALL_DAMAGE_INTERVAL == 0 {
+                    player.health = player.health.saturating_sub(SOFT_WALL_DAMAGE);
+                    ev_player_took_damage.send(PlayerTookDamage::new(*transform, player.handle));
+                }
+            }
+        }
+        debug_transform.update(&transform);
+    }
+}
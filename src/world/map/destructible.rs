@@ -0,0 +1,259 @@
+use bevy::core::FrameCount;
+use bevy::prelude::*;
+use bevy_ggrs::AddRollbackCommandExtension;
+use bevy_hanabi::prelude::*;
+
+use super::obstacle::{collision, Obstacle};
+use crate::audio::RollbackSound;
+use crate::camera::CameraShake;
+use crate::debug::DebugTransform;
+use crate::misc::math;
+use crate::player::shooting::bullet::Bullet;
+use crate::world::CollisionEntity;
+use crate::GameAssets;
+
+const DESTRUCTIBLE_WALL_HEALTH: u32 = 3000;
+const CRACK_STAGES: u32 = 3;
+const CRACK_WIDTH: f32 = 3.0;
+const CRACK_COLOR: Color = Color::rgba(0.08, 0.08, 0.08, 0.85);
+// Offset of each crack relative to the half size of the wall and its rotation.
+// Every two cracks make up one stage.
+const CRACK_LAYOUT: [(Vec2, f32); 6] = [
+    (Vec2::new(-0.35, 0.3), 0.6),
+    (Vec2::new(0.4, -0.25), -0.9),
+    (Vec2::new(0.1, 0.45), -0.3),
+    (Vec2::new(-0.3, -0.4), 1.2),
+    (Vec2::new(0.45, 0.35), 2.1),
+    (Vec2::new(-0.05, -0.05), -1.6),
+];
+
+#[derive(Component, Reflect, Default, Hash)]
+#[reflect(Hash)]
+pub struct DestructibleWall {
+    pub health: u32,
+    pub max_health: u32,
+}
+
+impl DestructibleWall {
+    fn new(max_health: u32) -> Self {
+        Self {
+            health: max_health,
+            max_health,
+        }
+    }
+
+    pub fn destroyed(&self) -> bool {
+        self.health == 0
+    }
+
+    /// Apply the damage to the wall, returns true if the wall got destroyed by it.
+    pub fn take_damage(&mut self, damage: u32) -> bool {
+        if self.destroyed() {
+            return false;
+        }
+
+        self.health = self.health.saturating_sub(damage);
+        self.destroyed()
+    }
+
+    fn crack_stage(&self) -> u32 {
+        if self.max_health == 0 {
+            return 0;
+        }
+        (self.max_health - self.health) * (CRACK_STAGES + 1) / self.max_health
+    }
+}
+
+#[derive(Component)]
+pub struct WallCrack {
+    stage: u32,
+}
+
+#[derive(Component)]
+pub struct WallDebrisEffectSpawner;
+
+#[derive(Event)]
+pub struct WallDestroyed {
+    pub position: Vec3,
+}
+
+impl WallDestroyed {
+    pub fn new(obstacle: &Obstacle) -> Self {
+        Self {
+            position: obstacle.center().extend(0.0),
+        }
+    }
+}
+
+pub fn make_destructible(commands: &mut Commands, wall: Entity, extents: [Vec2; 2]) {
+    let center = (extents[0] + extents[1]) / 2.0;
+    let half_size = (extents[1] - extents[0]) / 2.0;
+    let length = half_size.min_element();
+
+    let mut cracks = Vec::new();
+    for (i, (offset, angle)) in CRACK_LAYOUT.into_iter().enumerate() {
+        let transform = Transform::from_translation((center + offset * half_size).extend(1.0))
+            .with_rotation(math::rotation_z(angle));
+        let crack = commands
+            .spawn((
+                WallCrack {
+                    stage: i as u32 / 2 + 1,
+                },
+                DebugTransform::new(&transform),
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: CRACK_COLOR,
+                        custom_size: Some(Vec2::new(length, CRACK_WIDTH)),
+                        ..default()
+                    },
+                    transform,
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+            ))
+            .add_rollback()
+            .id();
+        cracks.push(crack);
+    }
+
+    commands
+        .entity(wall)
+        .insert(DestructibleWall::new(DESTRUCTIBLE_WALL_HEALTH))
+        .push_children(&cracks);
+}
+
+pub fn damage_walls_with_bullets(
+    mut walls: Query<(&Obstacle, &mut DestructibleWall)>,
+    mut bullets: Query<(&mut CollisionEntity, &Transform, &Bullet)>,
+    mut ev_wall_destroyed: EventWriter<WallDestroyed>,
+) {
+    for (obstacle, mut wall) in &mut walls {
+        if wall.destroyed() {
+            continue;
+        }
+
+        for (mut collision_entity, bullet_transform, bullet) in &mut bullets {
+            if collision_entity.disabled {
+                continue;
+            }
+            if !collision(obstacle, bullet_transform.translation) {
+                continue;
+            }
+
+            collision_entity.disabled = true;
+            if wall.take_damage(bullet.damage) {
+                ev_wall_destroyed.send(WallDestroyed::new(obstacle));
+                break;
+            }
+        }
+    }
+}
+
+pub fn update_destructible_walls(
+    mut walls: Query<(&DestructibleWall, &Children, &mut Visibility), Without<WallCrack>>,
+    mut cracks: Query<(&WallCrack, &mut Visibility), Without<DestructibleWall>>,
+) {
+    for (wall, children, mut visibility) in &mut walls {
+        *visibility = if wall.destroyed() {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+
+        let stage = wall.crack_stage();
+        for &child in children {
+            if let Ok((crack, mut crack_visibility)) = cracks.get_mut(child) {
+                *crack_visibility = if crack.stage <= stage {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+    }
+}
+
+pub fn spawn_wall_debris_effect_spawner(
+    mut commands: Commands,
+    mut effects: ResMut<Assets<EffectAsset>>,
+) {
+    let mut color_gradient = Gradient::new();
+    color_gradient.add_key(0.0, Vec4::new(0.6, 0.6, 0.6, 1.0));
+    color_gradient.add_key(1.0, Vec4::new(0.3, 0.3, 0.3, 0.0));
+    let mut scale_gradient = Gradient::new();
+    scale_gradient.add_key(0.0, Vec2::ONE * 9.0);
+    scale_gradient.add_key(0.5, Vec2::ONE * 6.0);
+    scale_gradient.add_key(1.0, Vec2::new(0.0, 0.0));
+
+    let spawner = Spawner::once(60.0.into(), false);
+
+    let writer = ExprWriter::new();
+
+    let age = writer.lit(0.).uniform(writer.lit(0.2)).expr();
+    let init_age = SetAttributeModifier::new(Attribute::AGE, age);
+
+    let lifetime = writer.lit(0.6).uniform(writer.lit(1.0)).expr();
+    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, lifetime);
+
+    // Slow the debris down quickly so that it settles around the wall
+    let drag = writer.lit(2.5).expr();
+    let update_drag = LinearDragModifier::new(drag);
+
+    let init_pos = SetPositionSphereModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        radius: writer.lit(30.).expr(),
+        dimension: ShapeDimension::Volume,
+    };
+
+    let init_vel = SetVelocitySphereModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        speed: (writer.rand(ScalarType::Float) * writer.lit(120.) + writer.lit(60.)).expr(),
+    };
+
+    let effect = effects.add(
+        EffectAsset::new(1200, spawner, writer.finish())
+            .init(init_pos)
+            .init(init_vel)
+            .init(init_age)
+            .init(init_lifetime)
+            .update(update_drag)
+            .render(SizeOverLifetimeModifier {
+                gradient: scale_gradient,
+                screen_space_size: false,
+            })
+            .render(ColorOverLifetimeModifier {
+                gradient: color_gradient,
+            }),
+    );
+
+    commands.spawn((
+        WallDebrisEffectSpawner,
+        ParticleEffectBundle::new(effect).with_spawner(spawner),
+    ));
+}
+
+pub fn spawn_wall_destroyed_effects(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    frame: Res<FrameCount>,
+    mut camera_shake: ResMut<CameraShake>,
+    mut spawner: Query<(&mut EffectSpawner, &mut Transform), With<WallDebrisEffectSpawner>>,
+    mut ev_wall_destroyed: EventReader<WallDestroyed>,
+) {
+    let (mut spawner, mut transform) = spawner.single_mut();
+
+    for (i, ev) in ev_wall_destroyed.iter().enumerate() {
+        transform.translation = ev.position;
+        spawner.reset();
+        camera_shake.add_trauma(0.3);
+        commands
+            .spawn(RollbackSound {
+                clip: assets.explosion_sound.clone(),
+                start_frame: frame.0 as usize,
+                sub_key: frame.0 as usize + i,
+                volume: 0.6,
+                playback_rate: 0.6,
+            })
+            .add_rollback();
+    }
+}
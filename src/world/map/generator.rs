@@ -0,0 +1,295 @@
+use std::f32::consts::TAU;
+
+use rand::Rng;
+
+use bevy::prelude::*;
+
+use super::border::Arena;
+use super::wall::*;
+use super::zone::{spawn_mirrored_zones, ZoneEffect};
+use crate::misc::{math, Balance, GameRng};
+use crate::player::spawning::P1_TRANSFORM;
+use crate::GameAssets;
+
+const MIN_WALL_PAIRS: usize = 2;
+const MAX_WALL_PAIRS: usize = 5;
+const MAX_ATTEMPTS: usize = 100;
+// Space in front of each spawn that has to be free of walls
+const SPAWN_CORRIDOR_LENGTH: f32 = 600.0;
+const SPAWN_CORRIDOR_HALF_WIDTH: f32 = 120.0;
+const DESTRUCTIBLE_CHANCE: f64 = 0.25;
+const ZONE_CHANCE: f64 = 0.5;
+const ZONE_SIZE: Vec2 = Vec2::new(300.0, 300.0);
+
+#[derive(Clone, Copy)]
+enum WallPiece {
+    Wall1x1,
+    Wall2x2,
+    Wall1x5,
+    Wall5x1,
+    Wall1x10,
+}
+
+impl WallPiece {
+    fn random(rng: &mut GameRng) -> Self {
+        match rng.gen_range(0..5) {
+            0 => Self::Wall1x1,
+            1 => Self::Wall2x2,
+            2 => Self::Wall1x5,
+            3 => Self::Wall5x1,
+            _ => Self::Wall1x10,
+        }
+    }
+
+    fn extents(&self) -> [Vec2; 2] {
+        match self {
+            Self::Wall1x1 => WALL_1_1_EXTENTS,
+            Self::Wall2x2 => WALL_2_2_EXTENTS,
+            Self::Wall1x5 => WALL_1_5_EXTENTS,
+            Self::Wall5x1 => WALL_5_1_EXTENTS,
+            Self::Wall1x10 => WALL_1_10_EXTENTS,
+        }
+    }
+
+    fn can_be_destructible(&self) -> bool {
+        matches!(self, Self::Wall1x1 | Self::Wall2x2 | Self::Wall5x1)
+    }
+
+    fn spawn(
+        &self,
+        commands: &mut Commands,
+        position: Vec2,
+        destructible: bool,
+        assets: &Res<GameAssets>,
+    ) {
+        match (self, destructible) {
+            (Self::Wall1x1, false) => spawn_wall_1_1(commands, position, assets),
+            (Self::Wall1x1, true) => spawn_destructible_wall_1_1(commands, position, assets),
+            (Self::Wall2x2, false) => spawn_wall_2_2(commands, position, assets),
+            (Self::Wall2x2, true) => spawn_destructible_wall_2_2(commands, position, assets),
+            (Self::Wall5x1, false) => spawn_wall_5_1(commands, position, assets),
+            (Self::Wall5x1, true) => spawn_destructible_wall_5_1(commands, position, assets),
+            (Self::Wall1x5, _) => spawn_wall_1_5(commands, position, assets),
+            (Self::Wall1x10, _) => spawn_wall_1_10(commands, position, assets),
+        };
+    }
+}
+
+struct PlacedWall {
+    piece: WallPiece,
+    position: Vec2,
+    destructible: bool,
+}
+
+impl PlacedWall {
+    fn rect(&self) -> Rect {
+        let [min, max] = self.piece.extents();
+        Rect::from_corners(self.position + min, self.position + max)
+    }
+
+    /// The wall on the opposite side of the arena, the spawns are point symmetric
+    /// around the origin so the collision rect has to be mirrored through it.
+    fn mirrored(&self) -> PlacedWall {
+        let [min, max] = self.piece.extents();
+        PlacedWall {
+            piece: self.piece,
+            position: -self.position - (min + max),
+            destructible: self.destructible,
+        }
+    }
+}
+
+/// Walls must be at least this far apart from each other and from the border,
+/// that way every part of the arena can be reached by the planes.
+fn min_gap(balance: &Balance) -> f32 {
+    2.0 * balance.player_radius() + 30.0
+}
+
+fn gap(r1: Rect, r2: Rect) -> f32 {
+    let dx = (r1.min.x - r2.max.x).max(r2.min.x - r1.max.x).max(0.0);
+    let dy = (r1.min.y - r2.max.y).max(r2.min.y - r1.max.y).max(0.0);
+    Vec2::new(dx, dy).length()
+}
+
+fn spawn_corridors(balance: &Balance) -> [Rect; 2] {
+    let spawn = P1_TRANSFORM.translation.truncate();
+    let forward = math::forward(&P1_TRANSFORM).truncate();
+    let side = forward.perp() * SPAWN_CORRIDOR_HALF_WIDTH;
+    // Start a bit behind the spawn so the plane itself is covered
+    let start = spawn - forward * 2.0 * balance.player_radius();
+    let end = spawn + forward * SPAWN_CORRIDOR_LENGTH;
+    let corridor = Rect::from_corners(start + side, end - side);
+    [corridor, Rect::from_corners(-corridor.min, -corridor.max)]
+}
+
+fn valid_placement(
+    wall: &PlacedWall,
+    placed_walls: &[PlacedWall],
+    arena: &Arena,
+    balance: &Balance,
+) -> bool {
+    let rect = wall.rect();
+    let inner_arena = Rect::from_center_half_size(Vec2::ZERO, arena.half_size - min_gap(balance));
+    if inner_arena.intersect(rect) != rect {
+        return false;
+    }
+    if spawn_corridors(balance)
+        .iter()
+        .any(|corridor| !corridor.intersect(rect).is_empty())
+    {
+        return false;
+    }
+    placed_walls
+        .iter()
+        .all(|placed_wall| gap(placed_wall.rect(), rect) >= min_gap(balance))
+}
+
+fn random_wall(rng: &mut GameRng, arena: &Arena) -> PlacedWall {
+    let piece = WallPiece::random(rng);
+    let destructible = piece.can_be_destructible() && rng.gen_bool(DESTRUCTIBLE_CHANCE);
+    let [min, max] = piece.extents();
+    let center = Vec2::new(
+        rng.gen_range(-arena.half_size.x..arena.half_size.x),
+        rng.gen_range(-arena.half_size.y..arena.half_size.y),
+    );
+    PlacedWall {
+        piece,
+        position: center - (min + max) / 2.0,
+        destructible,
+    }
+}
+
+/// Generate a fair layout, every wall has a twin that is point symmetric to it
+/// (except for a possible wall right in the center, which is its own twin).
+fn generate_walls(rng: &mut GameRng, arena: &Arena, balance: &Balance) -> Vec<PlacedWall> {
+    let mut walls = Vec::new();
+
+    if rng.gen_bool(0.5) {
+        let piece = WallPiece::random(rng);
+        let [min, max] = piece.extents();
+        let center_wall = PlacedWall {
+            piece,
+            position: -(min + max) / 2.0,
+            destructible: false,
+        };
+        if valid_placement(&center_wall, &walls, arena, balance) {
+            walls.push(center_wall);
+        }
+    }
+
+    let wall_pairs = rng.gen_range(MIN_WALL_PAIRS..=MAX_WALL_PAIRS);
+    let mut placed_pairs = 0;
+    for _ in 0..MAX_ATTEMPTS {
+        if placed_pairs == wall_pairs {
+            break;
+        }
+
+        let wall = random_wall(rng, arena);
+        let twin = wall.mirrored();
+        if !valid_placement(&wall, &walls, arena, balance)
+            || !valid_placement(&twin, &walls, arena, balance)
+        {
+            continue;
+        }
+        // The wall might be too close to its own twin if it's near the center
+        if gap(wall.rect(), twin.rect()) < min_gap(balance) {
+            continue;
+        }
+
+        walls.push(wall);
+        walls.push(twin);
+        placed_pairs += 1;
+    }
+    walls
+}
+
+pub fn spawn_generated_map(
+    commands: &mut Commands,
+    assets: Res<GameAssets>,
+    rng: &mut GameRng,
+    arena: &Arena,
+    balance: &Balance,
+) {
+    for wall in generate_walls(rng, arena, balance) {
+        wall.piece
+            .spawn(commands, wall.position, wall.destructible, &assets);
+    }
+
+    // Zones don't block anything, so they can be placed anywhere
+    if rng.gen_bool(ZONE_CHANCE) {
+        let effect = match rng.gen_range(0..3) {
+            0 => ZoneEffect::Wind {
+                velocity: math::from_angle(rng.gen_range(0.0..TAU)) * 120.0,
+            },
+            1 => ZoneEffect::Turbulence {
+                strength: 2.0,
+                seed: rng.gen(),
+            },
+            _ => ZoneEffect::Cloud { factor: 0.5 },
+        };
+        let center = Vec2::new(
+            rng.gen_range(-arena.half_size.x..arena.half_size.x),
+            rng.gen_range(-arena.half_size.y..arena.half_size.y),
+        );
+        spawn_mirrored_zones(commands, &assets, center, ZONE_SIZE, effect);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::player::spawning::P2_TRANSFORM;
+
+    fn balance() -> Balance {
+        toml::from_str(include_str!("../../../assets/default.balance.toml"))
+            .expect("failed to parse the default balance")
+    }
+
+    fn is_mirrored(wall: &PlacedWall, twin: &PlacedWall) -> bool {
+        let (rect, twin_rect) = (wall.rect(), twin.rect());
+        wall.destructible == twin.destructible
+            && rect.min.abs_diff_eq(-twin_rect.max, 1e-3)
+            && rect.max.abs_diff_eq(-twin_rect.min, 1e-3)
+    }
+
+    #[test]
+    fn spawns_are_point_symmetric() {
+        assert_eq!(P1_TRANSFORM.translation, -P2_TRANSFORM.translation);
+        assert!(math::forward(&P1_TRANSFORM).abs_diff_eq(-math::forward(&P2_TRANSFORM), 1e-6));
+    }
+
+    #[test]
+    fn generated_maps_are_mirror_symmetric() {
+        let balance = balance();
+        let arena = Arena::default();
+        let corridors = spawn_corridors(&balance);
+        for seed in 0..200 {
+            let walls = generate_walls(&mut GameRng::seed_from_u64(seed), &arena, &balance);
+            assert_eq!(
+                walls.len(),
+                generate_walls(&mut GameRng::seed_from_u64(seed), &arena, &balance).len(),
+                "seed {} isn't deterministic",
+                seed
+            );
+
+            for wall in &walls {
+                assert!(
+                    walls.iter().any(|twin| is_mirrored(wall, twin)),
+                    "seed {}, wall at {} has no twin",
+                    seed,
+                    wall.position
+                );
+                for corridor in &corridors {
+                    assert!(
+                        corridor.intersect(wall.rect()).is_empty(),
+                        "seed {}, wall at {} blocks a spawn",
+                        seed,
+                        wall.position
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,29 +1,83 @@
+pub mod border;
+pub mod destructible;
+pub mod motion;
 pub mod obstacle;
+pub mod playlist;
+pub mod theme;
+pub mod zone;
 
+mod generator;
 mod wall;
 
-use rand::Rng;
+use std::f32::consts::PI;
+
 use rand_xoshiro::rand_core::SeedableRng;
 
 use bevy::prelude::*;
 use bevy_ggrs::GgrsSchedule;
 
-use super::{RoundStats, Seed};
-use crate::misc::GameRng;
-use crate::player::InGameSet;
+use super::{tick_round_frame, MatchRules, RoundStats, Score, Seed};
+use crate::misc::{math, Balance, GameRng};
+use crate::player::{health, InGameSet};
 use crate::{GameAssets, GameState, RollbackState};
+use border::Arena;
+use motion::{make_moving, ObstacleMotion};
 use obstacle::disable_collision_entities;
+use playlist::GENERATED_MAP;
+use theme::MapTheme;
 use wall::*;
+use zone::{spawn_mirrored_zones, spawn_zone, ZoneEffect};
 
 pub struct MapPlugin;
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(RollbackState::RoundStart), spawn_random_map)
-            .add_systems(OnExit(GameState::Matchmaking), spawn_background)
+            .init_resource::<Arena>()
+            .init_resource::<MapTheme>()
+            .add_systems(
+                OnEnter(RollbackState::RoundStart),
+                theme::update_background.after(spawn_random_map),
+            )
+            .add_systems(
+                Update,
+                theme::tint_walls.run_if(in_state(GameState::InRollbackGame)),
+            )
+            .add_event::<destructible::WallDestroyed>()
+            .add_systems(
+                OnExit(GameState::Matchmaking),
+                (
+                    theme::spawn_background,
+                    destructible::spawn_wall_debris_effect_spawner,
+                ),
+            )
             .add_systems(
                 GgrsSchedule,
-                disable_collision_entities
+                motion::move_obstacles
+                    .after(tick_round_frame)
+                    .before(InGameSet::Movement)
+                    .after(apply_state_transition::<RollbackState>)
+                    .run_if(
+                        in_state(RollbackState::RoundStart)
+                            .or_else(in_state(RollbackState::InRound)),
+                    ),
+            )
+            .add_systems(
+                GgrsSchedule,
+                border::apply_border_rules
+                    .before(health::damage_players)
+                    .in_set(InGameSet::Health)
+                    .distributive_run_if(in_state(RollbackState::InRound)),
+            )
+            .add_systems(
+                GgrsSchedule,
+                (
+                    destructible::damage_walls_with_bullets,
+                    disable_collision_entities,
+                    destructible::update_destructible_walls,
+                    destructible::spawn_wall_destroyed_effects,
+                )
+                    .chain()
                     .after(InGameSet::Spawning)
                     .before(InGameSet::Last)
                     .after(apply_state_transition::<RollbackState>)
@@ -32,25 +86,16 @@ impl Plugin for MapPlugin {
     }
 }
 
-fn spawn_background(
-    mut commands: Commands,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-    assets: Res<GameAssets>,
-) {
-    let texture_handle = assets.background.clone();
-    let texture_atlas =
-        TextureAtlas::from_grid(texture_handle, Vec2::new(1600.0, 896.0), 1, 1, None, None);
-    let texture_atlas_handle = texture_atlases.add(texture_atlas);
-
-    commands.spawn(SpriteSheetBundle {
-        texture_atlas: texture_atlas_handle,
-        transform: Transform::from_translation(Vec3::new(0.0, 0.0, -1000.0)),
-        ..default()
-    });
+fn spawn_map_1(commands: &mut Commands, assets: Res<GameAssets>) {
+    spawn_mirrored_zones(
+        commands,
+        &assets,
+        Vec2::new(-350.0, 200.0),
+        Vec2::new(200.0, 200.0),
+        ZoneEffect::Cloud { factor: 0.5 },
+    );
 }
 
-fn spawn_map_1(_commands: &mut Commands, _assets: Res<GameAssets>) {}
-
 fn spawn_map_2(commands: &mut Commands, assets: Res<GameAssets>) {
     spawn_wall_1_5(commands, Vec2::new(0.0, 0.0), &assets);
     spawn_wall_5_1(commands, Vec2::new(-450.0, -150.0), &assets);
@@ -96,6 +141,13 @@ fn spawn_map_8(commands: &mut Commands, assets: Res<GameAssets>) {
     spawn_wall_1_1(commands, Vec2::new(0.0, 0.0), &assets);
     spawn_wall_1_1(commands, Vec2::new(500.0, 200.0), &assets);
     spawn_wall_1_1(commands, Vec2::new(-500.0, -200.0), &assets);
+    spawn_mirrored_zones(
+        commands,
+        &assets,
+        Vec2::new(250.0, -250.0),
+        Vec2::new(250.0, 250.0),
+        ZoneEffect::Cloud { factor: 0.5 },
+    );
 }
 
 fn spawn_map_9(commands: &mut Commands, assets: Res<GameAssets>) {
@@ -105,16 +157,148 @@ fn spawn_map_9(commands: &mut Commands, assets: Res<GameAssets>) {
 
 fn spawn_map_10(commands: &mut Commands, assets: Res<GameAssets>) {
     spawn_wall_5_1(commands, Vec2::new(0.0, 0.0), &assets);
+    spawn_mirrored_zones(
+        commands,
+        &assets,
+        Vec2::new(0.0, 250.0),
+        Vec2::new(800.0, 200.0),
+        ZoneEffect::Wind {
+            velocity: Vec2::new(120.0, 0.0),
+        },
+    );
 }
 
-fn spawn_random_map(
+fn spawn_map_11(commands: &mut Commands, assets: Res<GameAssets>) {
+    spawn_wall_1_1(commands, Vec2::new(0.0, 0.0), &assets);
+    spawn_destructible_wall_2_2(commands, Vec2::new(250.0, 150.0), &assets);
+    spawn_destructible_wall_2_2(commands, Vec2::new(-250.0, -150.0), &assets);
+    spawn_destructible_wall_5_1(commands, Vec2::new(-400.0, 250.0), &assets);
+    spawn_destructible_wall_5_1(commands, Vec2::new(400.0, -250.0), &assets);
+    spawn_destructible_wall_1_1(commands, Vec2::new(0.0, 300.0), &assets);
+    spawn_destructible_wall_1_1(commands, Vec2::new(0.0, -300.0), &assets);
+}
+
+fn spawn_map_12(commands: &mut Commands, assets: Res<GameAssets>) {
+    let position = Vec2::new(0.0, -24.0);
+    let bar = spawn_wall_1_10(commands, position, &assets);
+    make_moving(
+        commands,
+        bar,
+        position,
+        ObstacleMotion::Rotate { speed: 0.5 },
+    );
+}
+
+fn spawn_map_13(commands: &mut Commands, assets: Res<GameAssets>) {
+    for (position, offset) in [
+        (Vec2::new(-300.0, 150.0), Vec2::new(600.0, 0.0)),
+        (Vec2::new(300.0, -198.0), Vec2::new(-600.0, 0.0)),
+    ] {
+        let wall = spawn_wall_5_1(commands, position, &assets);
+        make_moving(
+            commands,
+            wall,
+            position,
+            ObstacleMotion::Slide {
+                offset,
+                period: 6.0,
+            },
+        );
+    }
+}
+
+fn spawn_map_14(commands: &mut Commands, assets: Res<GameAssets>) {
+    let center = Vec2::new(0.0, -24.0);
+    let radius = 250.0;
+    for phase in [0.5 * PI, 1.5 * PI] {
+        let position = center + math::from_angle(phase) * radius;
+        let block = spawn_wall_2_2(commands, position, &assets);
+        make_moving(
+            commands,
+            block,
+            position,
+            ObstacleMotion::Orbit {
+                center,
+                radius,
+                period: 8.0,
+                phase,
+            },
+        );
+    }
+}
+
+fn spawn_map_15(commands: &mut Commands, assets: Res<GameAssets>, arena: &mut Arena) {
+    *arena = Arena::new(Vec2::new(1400.0, 800.0));
+    spawn_wall_1_10(commands, Vec2::new(0.0, -24.0), &assets);
+    for position in [
+        Vec2::new(600.0, 400.0),
+        Vec2::new(-600.0, 400.0),
+        Vec2::new(600.0, -450.0),
+        Vec2::new(-600.0, -450.0),
+    ] {
+        spawn_wall_2_2(commands, position, &assets);
+    }
+    spawn_zone(
+        commands,
+        &assets,
+        Vec2::new(0.0, 0.0),
+        Vec2::new(500.0, 500.0),
+        ZoneEffect::Turbulence {
+            strength: 2.0,
+            seed: 15,
+        },
+    );
+    spawn_mirrored_zones(
+        commands,
+        &assets,
+        Vec2::new(1000.0, 0.0),
+        Vec2::new(300.0, 600.0),
+        ZoneEffect::Cloud { factor: 0.5 },
+    );
+}
+
+fn spawn_map_16(commands: &mut Commands, assets: Res<GameAssets>, arena: &mut Arena) {
+    *arena = Arena::new(Vec2::new(1800.0, 600.0));
+    spawn_wall_5_1(commands, Vec2::new(0.0, 250.0), &assets);
+    spawn_wall_5_1(commands, Vec2::new(0.0, -298.0), &assets);
+    spawn_wall_1_5(commands, Vec2::new(-400.0, -24.0), &assets);
+    spawn_wall_1_5(commands, Vec2::new(400.0, -24.0), &assets);
+    spawn_destructible_wall_2_2(commands, Vec2::new(-1300.0, -24.0), &assets);
+    spawn_destructible_wall_2_2(commands, Vec2::new(1300.0, -24.0), &assets);
+    spawn_mirrored_zones(
+        commands,
+        &assets,
+        Vec2::new(900.0, 0.0),
+        Vec2::new(600.0, 300.0),
+        ZoneEffect::Wind {
+            velocity: Vec2::new(0.0, 150.0),
+        },
+    );
+}
+
+pub fn spawn_random_map(
     mut commands: Commands,
     assets: Res<GameAssets>,
     seed: Res<Seed>,
     round_stats: Res<RoundStats>,
+    score: Res<Score>,
+    match_rules: Res<MatchRules>,
+    balance: Res<Balance>,
+    mut arena: ResMut<Arena>,
+    mut theme: ResMut<MapTheme>,
 ) {
+    // Maps that are larger than the screen overwrite this
+    *arena = Arena::default();
+
     let mut rng = GameRng::seed_from_u64(seed.seed + round_stats.rounds_played);
-    let index: usize = rng.gen_range(0..10);
+    *theme = MapTheme::random(&mut rng);
+
+    let index = playlist::choose_map(
+        &match_rules,
+        &mut rng,
+        round_stats.rounds_played,
+        score.last_winner,
+    );
     match index {
         0 => spawn_map_1(&mut commands, assets),
         1 => spawn_map_2(&mut commands, assets),
@@ -126,6 +310,15 @@ fn spawn_random_map(
         7 => spawn_map_8(&mut commands, assets),
         8 => spawn_map_9(&mut commands, assets),
         9 => spawn_map_10(&mut commands, assets),
+        10 => spawn_map_11(&mut commands, assets),
+        11 => spawn_map_12(&mut commands, assets),
+        12 => spawn_map_13(&mut commands, assets),
+        13 => spawn_map_14(&mut commands, assets),
+        14 => spawn_map_15(&mut commands, assets, &mut arena),
+        15 => spawn_map_16(&mut commands, assets, &mut arena),
+        GENERATED_MAP => {
+            generator::spawn_generated_map(&mut commands, assets, &mut rng, &arena, &balance)
+        }
         _ => panic!("now map with this index exists, index: {}", index),
     }
 }
@@ -0,0 +1,84 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use super::obstacle::Obstacle;
+use crate::debug::DebugTransform;
+use crate::misc::math;
+use crate::world::RoundFrame;
+
+/// Deterministic path of a moving obstacle.
+/// The pose only depends on the time since the round started.
+#[derive(Clone, Copy)]
+pub enum ObstacleMotion {
+    /// Slide back and forth between the spawn position and `spawn_position + offset`.
+    Slide { offset: Vec2, period: f32 },
+    /// Rotate around the spawn position with the given speed in radians per second.
+    Rotate { speed: f32 },
+    /// Orbit around `center`, starting at the angle `phase`.
+    Orbit {
+        center: Vec2,
+        radius: f32,
+        period: f32,
+        phase: f32,
+    },
+}
+
+#[derive(Component)]
+pub struct MovingObstacle {
+    origin: Vec2,
+    motion: ObstacleMotion,
+}
+
+impl MovingObstacle {
+    fn pose(&self, seconds: f32) -> (Vec2, f32) {
+        match self.motion {
+            ObstacleMotion::Slide { offset, period } => {
+                let progress = (1.0 - math::sin_cos(TAU * seconds / period).1) / 2.0;
+                (self.origin + offset * progress, 0.0)
+            }
+            ObstacleMotion::Rotate { speed } => (self.origin, speed * seconds),
+            ObstacleMotion::Orbit {
+                center,
+                radius,
+                period,
+                phase,
+            } => (
+                center + math::from_angle(phase + TAU * seconds / period) * radius,
+                0.0,
+            ),
+        }
+    }
+}
+
+pub fn make_moving(
+    commands: &mut Commands,
+    obstacle: Entity,
+    spawn_position: Vec2,
+    motion: ObstacleMotion,
+) {
+    commands.entity(obstacle).insert(MovingObstacle {
+        origin: spawn_position,
+        motion,
+    });
+}
+
+pub fn move_obstacles(
+    round_frame: Res<RoundFrame>,
+    mut obstacles: Query<(
+        &MovingObstacle,
+        &mut Obstacle,
+        &mut Transform,
+        &mut DebugTransform,
+    )>,
+) {
+    let seconds = round_frame.seconds();
+    for (moving_obstacle, mut obstacle, mut transform, mut debug_transform) in &mut obstacles {
+        let (position, rotation) = moving_obstacle.pose(seconds);
+        obstacle.set_pose(position, rotation);
+
+        transform.translation = position.extend(transform.translation.z);
+        transform.rotation = math::rotation_z(rotation);
+        debug_transform.update(&transform);
+    }
+}
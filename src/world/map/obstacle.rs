@@ -1,18 +1,22 @@
 use bevy::prelude::*;
 
-const BORDER_MIN_X: f32 = -800.0;
-const BORDER_MAX_X: f32 = 800.0;
-const BORDER_MIN_Y: f32 = -448.0;
-const BORDER_MAX_Y: f32 = 448.0;
+use super::border::Arena;
+use super::destructible::DestructibleWall;
+use crate::misc::math;
+use crate::player::Player;
+use crate::world::{BorderBehavior, MatchRules};
 
 #[derive(Component)]
 pub struct Obstacle {
     min_pos: Vec2,
     max_pos: Vec2,
     global_pos: Vec2,
+    // Rotation around `global_pos` in radians
+    rotation: f32,
 }
 
-#[derive(Component, Reflect, Default)]
+#[derive(Component, Reflect, Default, Hash)]
+#[reflect(Hash)]
 pub struct CollisionEntity {
     pub disabled: bool,
 }
@@ -23,20 +27,57 @@ impl Obstacle {
             min_pos,
             max_pos,
             global_pos,
+            rotation: 0.0,
         }
     }
+
+    pub fn set_pose(&mut self, global_pos: Vec2, rotation: f32) {
+        self.global_pos = global_pos;
+        self.rotation = rotation;
+    }
+
+    fn to_world(&self, local_pos: Vec2) -> Vec2 {
+        self.global_pos + math::from_angle(self.rotation).rotate(local_pos)
+    }
+
+    fn to_local(&self, pos: Vec2) -> Vec2 {
+        math::from_angle(-self.rotation).rotate(pos - self.global_pos)
+    }
+
+    pub fn center(&self) -> Vec2 {
+        self.to_world((self.min_pos + self.max_pos) / 2.0)
+    }
+
+    pub fn closest_point(&self, pos: Vec2) -> Vec2 {
+        self.to_world(self.to_local(pos).clamp(self.min_pos, self.max_pos))
+    }
+
+    fn corners(&self) -> [Vec2; 4] {
+        [
+            self.min_pos,
+            Vec2::new(self.max_pos.x, self.min_pos.y),
+            self.max_pos,
+            Vec2::new(self.min_pos.x, self.max_pos.y),
+        ]
+        .map(|corner| self.to_world(corner))
+    }
 }
 
-fn collision(obstacle: &Obstacle, other_pos: Vec3) -> bool {
+pub fn collision(obstacle: &Obstacle, other_pos: Vec3) -> bool {
     let circle_pos = other_pos.truncate();
-    let closest_point = circle_pos.clamp(
-        obstacle.min_pos + obstacle.global_pos,
-        obstacle.max_pos + obstacle.global_pos,
-    );
-    let distance = circle_pos.distance_squared(closest_point);
+    let distance = circle_pos.distance_squared(obstacle.closest_point(circle_pos));
     distance < 1.0
 }
 
+/// Destroyed walls stay around (so that rollbacks can restore them)
+/// but must not collide with anything anymore.
+fn solid(destructible_wall: Option<&DestructibleWall>) -> bool {
+    match destructible_wall {
+        Some(wall) => !wall.destroyed(),
+        None => true,
+    }
+}
+
 fn vec_vec_collision(v1: Vec2, v2: Vec2, w1: Vec2, w2: Vec2) -> bool {
     // From https://en.wikipedia.org/wiki/Line%E2%80%93line_intersection#Given_two_points_on_each_line
     let denominator = (v1.x - v2.x) * (w1.y - w2.y) - (v1.y - v2.y) * (w1.x - w2.x);
@@ -53,12 +94,7 @@ fn vec_vec_collision(v1: Vec2, v2: Vec2, w1: Vec2, w2: Vec2) -> bool {
     (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)
 }
 
-fn vec_obstacle_collision(v1: Vec2, v2: Vec2, o: &Obstacle) -> bool {
-    let bl = o.min_pos + o.global_pos;
-    let br = Vec2::new(o.max_pos.x, o.min_pos.y) + o.global_pos;
-    let tl = Vec2::new(o.min_pos.x, o.max_pos.y) + o.global_pos;
-    let tr = o.max_pos + o.global_pos;
-
+fn vec_corners_collision(v1: Vec2, v2: Vec2, [bl, br, tr, tl]: [Vec2; 4]) -> bool {
     let rect_vecs = [(bl, br), (br, tr), (tr, tl), (tl, bl)];
     for (w1, w2) in rect_vecs {
         if vec_vec_collision(v1, v2, w1, w2) {
@@ -68,28 +104,43 @@ fn vec_obstacle_collision(v1: Vec2, v2: Vec2, o: &Obstacle) -> bool {
     false
 }
 
-fn outside_of_borders(target_position: Vec3) -> bool {
-    if target_position.x < BORDER_MIN_X
-        || target_position.x > BORDER_MAX_X
-        || target_position.y < BORDER_MIN_Y
-        || target_position.y > BORDER_MAX_Y
-    {
+fn vec_obstacle_collision(v1: Vec2, v2: Vec2, o: &Obstacle) -> bool {
+    vec_corners_collision(v1, v2, o.corners())
+}
+
+/// Whether the ray touches the (axis aligned) rect, including rays that start or end inside of it.
+pub fn ray_rect_collision(start_pos: Vec2, end_pos: Vec2, rect: Rect) -> bool {
+    if rect.contains(start_pos) || rect.contains(end_pos) {
         return true;
     }
-    false
+
+    let corners = [
+        rect.min,
+        Vec2::new(rect.max.x, rect.min.y),
+        rect.max,
+        Vec2::new(rect.min.x, rect.max.y),
+    ];
+    vec_corners_collision(start_pos, end_pos, corners)
 }
 
 pub fn disable_collision_entities(
-    mut collision_entities: Query<(&mut CollisionEntity, &Transform)>,
-    obstacles: Query<&Obstacle>,
+    match_rules: Res<MatchRules>,
+    arena: Res<Arena>,
+    mut collision_entities: Query<(&mut CollisionEntity, &Transform, Has<Player>)>,
+    obstacles: Query<(&Obstacle, Option<&DestructibleWall>)>,
 ) {
-    for (mut collision_entity, collision_transform) in &mut collision_entities {
-        if outside_of_borders(collision_transform.translation) {
+    for (mut collision_entity, collision_transform, is_player) in &mut collision_entities {
+        // Planes are allowed to fly into the soft wall, see `apply_border_rules`
+        let soft_wall = match_rules.border == BorderBehavior::SoftWall && is_player;
+        if !soft_wall && arena.outside(collision_transform.translation) {
             collision_entity.disabled = true;
             continue;
         }
 
-        for obstacle in &obstacles {
+        for (obstacle, destructible_wall) in &obstacles {
+            if !solid(destructible_wall) {
+                continue;
+            }
             if collision(obstacle, collision_transform.translation) {
                 collision_entity.disabled = true;
             }
@@ -100,9 +151,12 @@ pub fn disable_collision_entities(
 pub fn ray_obstacle_collision(
     start_pos: Vec2,
     end_pos: Vec2,
-    obstacles: &Query<&Obstacle>,
+    obstacles: &Query<(&Obstacle, Option<&DestructibleWall>)>,
 ) -> bool {
-    for obstacle in obstacles {
+    for (obstacle, destructible_wall) in obstacles {
+        if !solid(destructible_wall) {
+            continue;
+        }
         if vec_obstacle_collision(start_pos, end_pos, obstacle) {
             return true;
         }
@@ -0,0 +1,114 @@
+use rand::Rng;
+
+use crate::misc::GameRng;
+use crate::world::MatchRules;
+
+/// Names of all maps, the index is the id of the map.
+/// The last one isn't a fixed layout, it's generated from the seed every time.
+pub const MAP_NAMES: [&str; MAP_COUNT] = [
+    "OPEN SKY",
+    "CROSSING",
+    "PILLARS",
+    "GREAT WALL",
+    "LANES",
+    "FOUR BLOCKS",
+    "ARCH",
+    "SCATTER",
+    "TWIN BLOCKS",
+    "BAR",
+    "RUINS",
+    "WINDMILL",
+    "SLIDERS",
+    "ORBIT",
+    "BIG SKY",
+    "CORRIDOR",
+    "GENERATED",
+];
+pub const MAP_COUNT: usize = 17;
+pub const GENERATED_MAP: usize = MAP_COUNT - 1;
+
+/// How the map of each round is chosen from the maps that are not banned.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum PlaylistMode {
+    #[default]
+    Random,
+    /// Go through the maps one after another
+    Rotation,
+    /// The winner of the last round plays on one of their picked maps
+    WinnersPick,
+    /// The loser of the last round plays on one of their picked maps
+    LosersPick,
+}
+
+impl PlaylistMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Random => Self::Rotation,
+            Self::Rotation => Self::WinnersPick,
+            Self::WinnersPick => Self::LosersPick,
+            Self::LosersPick => Self::Random,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Random => "random",
+            Self::Rotation => "rotation",
+            Self::WinnersPick => "winners_pick",
+            Self::LosersPick => "losers_pick",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::Random,
+            Self::Rotation,
+            Self::WinnersPick,
+            Self::LosersPick,
+        ]
+        .into_iter()
+        .find(|mode| mode.name() == name)
+    }
+}
+
+fn random_map(rng: &mut GameRng, maps: &[usize]) -> usize {
+    // Every other round (on average) is played on a generated map
+    if maps.contains(&GENERATED_MAP) && rng.gen_bool(0.5) {
+        return GENERATED_MAP;
+    }
+
+    let fixed_maps: Vec<usize> = maps
+        .iter()
+        .copied()
+        .filter(|&map| map != GENERATED_MAP)
+        .collect();
+    if fixed_maps.is_empty() {
+        return GENERATED_MAP;
+    }
+    fixed_maps[rng.gen_range(0..fixed_maps.len())]
+}
+
+/// Choose the map for the next round, this has to be deterministic.
+pub fn choose_map(
+    rules: &MatchRules,
+    rng: &mut GameRng,
+    rounds_played: u64,
+    last_winner: Option<usize>,
+) -> usize {
+    let maps = rules.available_maps();
+
+    let picking_player = match (rules.playlist, last_winner) {
+        (PlaylistMode::Random, _) => return random_map(rng, &maps),
+        (PlaylistMode::Rotation, _) => return maps[rounds_played as usize % maps.len()],
+        // First round or nobody won the last one
+        (_, None) => return random_map(rng, &maps),
+        (PlaylistMode::WinnersPick, Some(winner)) => winner,
+        (PlaylistMode::LosersPick, Some(winner)) => 1 - winner,
+    };
+
+    let picks = &rules.map_picks[picking_player];
+    if picks.is_empty() {
+        return random_map(rng, &maps);
+    }
+    picks[rng.gen_range(0..picks.len())]
+}
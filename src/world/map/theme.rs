@@ -0,0 +1,88 @@
+use rand::Rng;
+
+use bevy::prelude::*;
+
+use super::border::Arena;
+use super::obstacle::Obstacle;
+use crate::misc::GameRng;
+use crate::GameAssets;
+
+/// The look (and sound) of a map, it's chosen together with the map every round.
+#[derive(Resource, Reflect, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[reflect(Resource, Hash)]
+pub enum MapTheme {
+    #[default]
+    Classic,
+    Grass,
+    Old,
+}
+
+impl MapTheme {
+    pub fn random(rng: &mut GameRng) -> Self {
+        match rng.gen_range(0..3) {
+            0 => Self::Classic,
+            1 => Self::Grass,
+            _ => Self::Old,
+        }
+    }
+
+    pub fn background(&self, assets: &GameAssets) -> Handle<Image> {
+        match self {
+            Self::Classic => assets.background.clone(),
+            Self::Grass => assets.grass_background.clone(),
+            Self::Old => assets.old_background.clone(),
+        }
+    }
+
+    pub fn wall_tint(&self) -> Color {
+        match self {
+            Self::Classic => Color::WHITE,
+            Self::Grass => Color::rgb(0.8, 0.95, 0.75),
+            Self::Old => Color::rgb(0.95, 0.85, 0.7),
+        }
+    }
+
+    /// The BGM is played slower or faster depending on the theme.
+    pub fn music_playback_rate(&self) -> f64 {
+        match self {
+            Self::Classic => 1.0,
+            Self::Grass => 1.0,
+            Self::Old => 0.85,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MapBackground;
+
+pub fn spawn_background(mut commands: Commands, assets: Res<GameAssets>) {
+    commands.spawn((
+        MapBackground,
+        SpriteBundle {
+            texture: assets.background.clone(),
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, -1000.0)),
+            ..default()
+        },
+    ));
+}
+
+/// Swap the background to the one of the current theme and stretch it so that it covers the whole arena.
+pub fn update_background(
+    assets: Res<GameAssets>,
+    theme: Res<MapTheme>,
+    arena: Res<Arena>,
+    mut backgrounds: Query<(&mut Handle<Image>, &mut Sprite), With<MapBackground>>,
+) {
+    for (mut texture, mut sprite) in &mut backgrounds {
+        *texture = theme.background(&assets);
+        sprite.custom_size = Some(arena.size());
+    }
+}
+
+pub fn tint_walls(theme: Res<MapTheme>, mut walls: Query<(Ref<Obstacle>, &mut Sprite)>) {
+    for (obstacle, mut sprite) in &mut walls {
+        if theme.is_changed() || obstacle.is_added() {
+            sprite.color = theme.wall_tint();
+        }
+    }
+}
@@ -1,99 +1,131 @@
 use bevy::prelude::*;
 use bevy_ggrs::prelude::*;
 
+use super::destructible::make_destructible;
 use super::obstacle::Obstacle;
 use crate::{debug::DebugTransform, GameAssets};
 
 const OFFSET: Vec3 = Vec3::new(0.0, 0.0, -100.0);
 
-#[allow(dead_code)]
-pub fn spawn_wall_1_1(commands: &mut Commands, spawn_position: Vec2, assets: &Res<GameAssets>) {
+pub const WALL_1_1_EXTENTS: [Vec2; 2] = [Vec2::new(-32.0, 0.0), Vec2::new(32.0, 48.0)];
+pub const WALL_2_2_EXTENTS: [Vec2; 2] = [Vec2::new(-64.0, -32.0), Vec2::new(64.0, 80.0)];
+pub const WALL_1_5_EXTENTS: [Vec2; 2] = [Vec2::new(-32.0, -112.0), Vec2::new(32.0, 160.0)];
+pub const WALL_5_1_EXTENTS: [Vec2; 2] = [Vec2::new(-160.0, 0.0), Vec2::new(160.0, 48.0)];
+pub const WALL_1_10_EXTENTS: [Vec2; 2] = [Vec2::new(-32.0, -272.0), Vec2::new(32.0, 320.0)];
+
+fn spawn_wall(
+    commands: &mut Commands,
+    spawn_position: Vec2,
+    texture: Handle<Image>,
+    extents: [Vec2; 2],
+) -> Entity {
     let transform = Transform::from_translation(spawn_position.extend(0.0) + OFFSET);
     commands
         .spawn((
-            Obstacle::new(Vec2::new(-32.0, 0.0), Vec2::new(32.0, 48.0), spawn_position),
+            Obstacle::new(extents[0], extents[1], spawn_position),
             DebugTransform::new(&transform),
             SpriteBundle {
-                texture: assets.wall_1_1.clone(),
+                texture,
                 transform,
                 ..default()
             },
         ))
-        .add_rollback();
+        .add_rollback()
+        .id()
 }
 
-pub fn spawn_wall_2_2(commands: &mut Commands, spawn_position: Vec2, assets: &Res<GameAssets>) {
-    let transform = Transform::from_translation(spawn_position.extend(0.0) + OFFSET);
-    commands
-        .spawn((
-            Obstacle::new(
-                Vec2::new(-64.0, -32.0),
-                Vec2::new(64.0, 80.0),
-                spawn_position,
-            ),
-            DebugTransform::new(&transform),
-            SpriteBundle {
-                texture: assets.wall_2_2.clone(),
-                transform,
-                ..default()
-            },
-        ))
-        .add_rollback();
+#[allow(dead_code)]
+pub fn spawn_wall_1_1(
+    commands: &mut Commands,
+    spawn_position: Vec2,
+    assets: &Res<GameAssets>,
+) -> Entity {
+    spawn_wall(
+        commands,
+        spawn_position,
+        assets.wall_1_1.clone(),
+        WALL_1_1_EXTENTS,
+    )
 }
 
-pub fn spawn_wall_1_5(commands: &mut Commands, spawn_position: Vec2, assets: &Res<GameAssets>) {
-    let transform = Transform::from_translation(spawn_position.extend(0.0) + OFFSET);
-    commands
-        .spawn((
-            Obstacle::new(
-                Vec2::new(-32.0, -112.0),
-                Vec2::new(32.0, 160.0),
-                spawn_position,
-            ),
-            DebugTransform::new(&transform),
-            SpriteBundle {
-                texture: assets.wall_1_5.clone(),
-                transform,
-                ..default()
-            },
-        ))
-        .add_rollback();
+pub fn spawn_wall_2_2(
+    commands: &mut Commands,
+    spawn_position: Vec2,
+    assets: &Res<GameAssets>,
+) -> Entity {
+    spawn_wall(
+        commands,
+        spawn_position,
+        assets.wall_2_2.clone(),
+        WALL_2_2_EXTENTS,
+    )
 }
 
-pub fn spawn_wall_5_1(commands: &mut Commands, spawn_position: Vec2, assets: &Res<GameAssets>) {
-    let transform = Transform::from_translation(spawn_position.extend(0.0) + OFFSET);
-    commands
-        .spawn((
-            Obstacle::new(
-                Vec2::new(-160.0, 0.0),
-                Vec2::new(160.0, 48.0),
-                spawn_position,
-            ),
-            DebugTransform::new(&transform),
-            SpriteBundle {
-                texture: assets.wall_5_1.clone(),
-                transform,
-                ..default()
-            },
-        ))
-        .add_rollback();
+pub fn spawn_wall_1_5(
+    commands: &mut Commands,
+    spawn_position: Vec2,
+    assets: &Res<GameAssets>,
+) -> Entity {
+    spawn_wall(
+        commands,
+        spawn_position,
+        assets.wall_1_5.clone(),
+        WALL_1_5_EXTENTS,
+    )
 }
 
-pub fn spawn_wall_1_10(commands: &mut Commands, spawn_position: Vec2, assets: &Res<GameAssets>) {
-    let transform = Transform::from_translation(spawn_position.extend(0.0) + OFFSET);
-    commands
-        .spawn((
-            Obstacle::new(
-                Vec2::new(-32.0, -272.0),
-                Vec2::new(32.0, 320.0),
-                spawn_position,
-            ),
-            DebugTransform::new(&transform),
-            SpriteBundle {
-                texture: assets.wall_1_10.clone(),
-                transform,
-                ..default()
-            },
-        ))
-        .add_rollback();
+pub fn spawn_wall_5_1(
+    commands: &mut Commands,
+    spawn_position: Vec2,
+    assets: &Res<GameAssets>,
+) -> Entity {
+    spawn_wall(
+        commands,
+        spawn_position,
+        assets.wall_5_1.clone(),
+        WALL_5_1_EXTENTS,
+    )
+}
+
+pub fn spawn_wall_1_10(
+    commands: &mut Commands,
+    spawn_position: Vec2,
+    assets: &Res<GameAssets>,
+) -> Entity {
+    spawn_wall(
+        commands,
+        spawn_position,
+        assets.wall_1_10.clone(),
+        WALL_1_10_EXTENTS,
+    )
+}
+
+pub fn spawn_destructible_wall_1_1(
+    commands: &mut Commands,
+    spawn_position: Vec2,
+    assets: &Res<GameAssets>,
+) -> Entity {
+    let wall = spawn_wall_1_1(commands, spawn_position, assets);
+    make_destructible(commands, wall, WALL_1_1_EXTENTS);
+    wall
+}
+
+pub fn spawn_destructible_wall_2_2(
+    commands: &mut Commands,
+    spawn_position: Vec2,
+    assets: &Res<GameAssets>,
+) -> Entity {
+    let wall = spawn_wall_2_2(commands, spawn_position, assets);
+    make_destructible(commands, wall, WALL_2_2_EXTENTS);
+    wall
+}
+
+pub fn spawn_destructible_wall_5_1(
+    commands: &mut Commands,
+    spawn_position: Vec2,
+    assets: &Res<GameAssets>,
+) -> Entity {
+    let wall = spawn_wall_5_1(commands, spawn_position, assets);
+    make_destructible(commands, wall, WALL_5_1_EXTENTS);
+    wall
 }
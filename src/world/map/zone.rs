@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use bevy_ggrs::prelude::*;
+use rand::Rng;
+use rand_xoshiro::rand_core::SeedableRng;
+
+use super::obstacle::ray_rect_collision;
+use crate::debug::DebugTransform;
+use crate::misc::GameRng;
+use crate::network::ggrs_config::GGRS_FPS;
+use crate::world::RoundFrame;
+use crate::GameAssets;
+
+// Above the background, below the walls
+const ZONE_Z: f32 = -500.0;
+// Turbulence picks a new random direction every couple of frames and blends between them
+const TURBULENCE_PERIOD: u32 = 12;
+
+/// What a zone does to everything inside of it.
+#[derive(Clone, Copy, Debug)]
+pub enum ZoneEffect {
+    /// Pushes planes and bullets, `velocity` is in pixels per second
+    Wind { velocity: Vec2 },
+    /// Adds noise (in radians per second) to the steering of planes
+    Turbulence { strength: f32, seed: u64 },
+    /// Slows down bullets and rockets, `factor` is the fraction of speed they keep.
+    /// Planes can hide in clouds, they block vision and rocket lock-on.
+    Cloud { factor: f32 },
+}
+
+impl ZoneEffect {
+    fn color(&self) -> Color {
+        match self {
+            Self::Wind { .. } => Color::rgba(0.6, 0.85, 1.0, 0.15),
+            Self::Turbulence { .. } => Color::rgba(1.0, 0.6, 0.3, 0.15),
+            Self::Cloud { .. } => Color::rgba(0.95, 0.95, 0.95, 0.35),
+        }
+    }
+
+    /// The zone on the opposite side of a point symmetric map.
+    fn mirrored(&self) -> Self {
+        match *self {
+            Self::Wind { velocity } => Self::Wind {
+                velocity: -velocity,
+            },
+            effect => effect,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct EnvironmentZone {
+    pub rect: Rect,
+    pub effect: ZoneEffect,
+}
+
+impl EnvironmentZone {
+    fn cloud(&self) -> bool {
+        matches!(self.effect, ZoneEffect::Cloud { .. })
+    }
+}
+
+pub fn inside_cloud<'a>(
+    zones: impl IntoIterator<Item = &'a EnvironmentZone>,
+    position: Vec2,
+) -> bool {
+    zones
+        .into_iter()
+        .any(|zone| zone.cloud() && zone.rect.contains(position))
+}
+
+/// Whether the line of sight between the two positions goes through a cloud.
+pub fn ray_cloud_collision<'a>(
+    zones: impl IntoIterator<Item = &'a EnvironmentZone>,
+    start_pos: Vec2,
+    end_pos: Vec2,
+) -> bool {
+    zones
+        .into_iter()
+        .any(|zone| zone.cloud() && ray_rect_collision(start_pos, end_pos, zone.rect))
+}
+
+/// The combined effect of all zones at one position.
+#[derive(Default)]
+pub struct ZoneForces {
+    /// Offset per frame
+    pub wind: Vec2,
+    /// Rotation per frame
+    pub turbulence: f32,
+    /// Fraction of speed projectiles keep
+    pub speed_factor: f32,
+}
+
+impl ZoneForces {
+    pub fn at<'a>(
+        zones: impl IntoIterator<Item = &'a EnvironmentZone>,
+        position: Vec2,
+        round_frame: &RoundFrame,
+    ) -> ZoneForces {
+        let mut forces = ZoneForces {
+            speed_factor: 1.0,
+            ..default()
+        };
+
+        for zone in zones {
+            if !zone.rect.contains(position) {
+                continue;
+            }
+
+            match zone.effect {
+                ZoneEffect::Wind { velocity } => forces.wind += velocity / GGRS_FPS as f32,
+                ZoneEffect::Turbulence { strength, seed } => {
+                    forces.turbulence +=
+                        turbulence(seed, round_frame.frame) * strength / GGRS_FPS as f32;
+                }
+                ZoneEffect::Cloud { factor } => forces.speed_factor *= factor,
+            }
+        }
+        forces
+    }
+}
+
+fn noise(seed: u64, key: u32) -> f32 {
+    GameRng::seed_from_u64(seed.wrapping_add(key as u64)).gen_range(-1.0..1.0)
+}
+
+/// Smooth deterministic noise in the range [-1, 1] that only depends on the frame.
+fn turbulence(seed: u64, frame: u32) -> f32 {
+    let key = frame / TURBULENCE_PERIOD;
+    let progress = (frame % TURBULENCE_PERIOD) as f32 / TURBULENCE_PERIOD as f32;
+    noise(seed, key) + (noise(seed, key + 1) - noise(seed, key)) * progress
+}
+
+pub fn spawn_zone(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    center: Vec2,
+    size: Vec2,
+    effect: ZoneEffect,
+) -> Entity {
+    let transform = Transform::from_translation(center.extend(ZONE_Z));
+    commands
+        .spawn((
+            EnvironmentZone {
+                rect: Rect::from_center_size(center, size),
+                effect,
+            },
+            DebugTransform::new(&transform),
+            SpriteBundle {
+                texture: assets.white_pixel.clone(),
+                transform,
+                sprite: Sprite {
+                    color: effect.color(),
+                    custom_size: Some(size),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .add_rollback()
+        .id()
+}
+
+/// Spawn the zone and its point symmetric twin.
+pub fn spawn_mirrored_zones(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    center: Vec2,
+    size: Vec2,
+    effect: ZoneEffect,
+) {
+    spawn_zone(commands, assets, center, size, effect);
+    spawn_zone(commands, assets, -center, size, effect.mirrored());
+}
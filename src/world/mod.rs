@@ -1,14 +1,18 @@
 pub mod map;
 
 mod clear;
+mod objective;
 mod round;
+mod rules;
 mod seed;
 
 pub use map::obstacle::CollisionEntity;
+pub use objective::{ObjectiveState, CAPTURES_TO_WIN, HILL_POINTS_TO_WIN};
 pub use round::{
-    adjust_score, check_rematch, round_end_timeout, Rematch, RoundEndTimer, RoundStats, Score,
-    MAX_SCORE,
+    adjust_score, check_rematch, round_end_timeout, tick_round_frame, Rematch, RoundEndTimer,
+    RoundFrame, RoundStats, Score, MAX_SCORE,
 };
+pub use rules::{BorderBehavior, GameMode, MatchRules, PerkDraft, PlaneCollision, RulesProposal};
 pub use seed::{determine_seed, Seed, SeedHandle, Seeds};
 
 use bevy::prelude::*;
@@ -22,6 +26,8 @@ impl Plugin for GameLogicPlugin {
             clear::WorldClearPlugin,
             round::WorldRoundPlugin,
             map::MapPlugin,
+            rules::WorldRulesPlugin,
+            objective::WorldObjectivePlugin,
         ));
     }
 }
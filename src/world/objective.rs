@@ -0,0 +1,313 @@
+use bevy::prelude::*;
+use bevy_ggrs::prelude::*;
+use bevy_hanabi::EffectAsset;
+
+use super::{GameMode, MatchRules, RoundFrame};
+use crate::debug::DebugTransform;
+use crate::misc::Balance;
+use crate::network::ggrs_config::{GGRS_FPS, PLAYER_COUNT};
+use crate::player::perk::Perks;
+use crate::player::spawning::{player_spawn_transform, spawn_plane};
+use crate::player::{InGameSet, PersistentPlayerStats, Player, P1_COLOR, P2_COLOR};
+use crate::world::CollisionEntity;
+use crate::{GameAssets, RollbackState};
+
+// Number of frames a player has to hold the hill alone to win the round
+pub const HILL_POINTS_TO_WIN: u32 = 15 * GGRS_FPS as u32;
+pub const CAPTURES_TO_WIN: u32 = 3;
+const RESPAWN_FRAMES: u32 = 2 * GGRS_FPS as u32;
+
+const HILL_SIZE: Vec2 = Vec2::new(300.0, 300.0);
+const HILL_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.12);
+const BASE_SIZE: Vec2 = Vec2::new(240.0, 240.0);
+const FLAG_SIZE: Vec2 = Vec2::new(20.0, 30.0);
+const FLAG_PICKUP_RADIUS: f32 = 60.0;
+const FLAG_CARRY_OFFSET: Vec3 = Vec3::new(0.0, 45.0, 0.0);
+// Above the zones, below the walls
+const OBJECTIVE_Z: f32 = -400.0;
+
+/// Everything that belongs to the objective of the current mode,
+/// gets cleared at the start of each round.
+#[derive(Component)]
+pub struct ObjectiveEntity;
+
+#[derive(Component)]
+pub struct Flag {
+    team: usize,
+}
+
+/// The progress of the objective in the current round.
+#[derive(Resource, Reflect, Default, Debug, Hash)]
+#[reflect(Resource, Hash)]
+pub struct ObjectiveState {
+    /// Frames held alone on the hill or flags captured, indexed by handle
+    pub points: [u32; PLAYER_COUNT],
+    /// The player that carries the flag of the given team
+    pub flag_carriers: [Option<usize>; PLAYER_COUNT],
+    respawn_frames: [Option<u32>; PLAYER_COUNT],
+    pub winner: Option<usize>,
+}
+
+fn team_color(team: usize) -> Color {
+    if team == 0 {
+        P1_COLOR
+    } else {
+        P2_COLOR
+    }
+}
+
+fn base_position(team: usize) -> Vec2 {
+    player_spawn_transform(team).translation.truncate()
+}
+
+fn hill_rect() -> Rect {
+    Rect::from_center_size(Vec2::ZERO, HILL_SIZE)
+}
+
+fn alive(player: &Player, collision_entity: &CollisionEntity) -> bool {
+    player.health > 0 && !collision_entity.disabled
+}
+
+fn spawn_objective_sprite(
+    commands: &mut Commands,
+    texture: Handle<Image>,
+    sprite: Sprite,
+    position: Vec2,
+) -> Entity {
+    let transform = Transform::from_translation(position.extend(OBJECTIVE_Z));
+    commands
+        .spawn((
+            ObjectiveEntity,
+            DebugTransform::new(&transform),
+            SpriteBundle {
+                texture,
+                transform,
+                sprite,
+                ..default()
+            },
+        ))
+        .add_rollback()
+        .id()
+}
+
+fn spawn_objective(mut commands: Commands, assets: Res<GameAssets>, match_rules: Res<MatchRules>) {
+    match match_rules.mode {
+        GameMode::Elimination => {}
+        GameMode::KingOfTheHill => {
+            spawn_objective_sprite(
+                &mut commands,
+                assets.white_pixel.clone(),
+                Sprite {
+                    color: HILL_COLOR,
+                    custom_size: Some(HILL_SIZE),
+                    ..default()
+                },
+                Vec2::ZERO,
+            );
+        }
+        GameMode::CaptureTheFlag => {
+            for team in 0..PLAYER_COUNT {
+                spawn_objective_sprite(
+                    &mut commands,
+                    assets.white_pixel.clone(),
+                    Sprite {
+                        color: team_color(team).with_a(0.15),
+                        custom_size: Some(BASE_SIZE),
+                        ..default()
+                    },
+                    base_position(team),
+                );
+                let flag = spawn_objective_sprite(
+                    &mut commands,
+                    assets.white_pixel.clone(),
+                    Sprite {
+                        color: team_color(team),
+                        custom_size: Some(FLAG_SIZE),
+                        ..default()
+                    },
+                    base_position(team),
+                );
+                commands.entity(flag).insert(Flag { team });
+            }
+        }
+    }
+}
+
+fn reset_objective_state(mut objective_state: ResMut<ObjectiveState>) {
+    *objective_state = ObjectiveState::default();
+}
+
+fn update_hill(
+    match_rules: Res<MatchRules>,
+    mut objective_state: ResMut<ObjectiveState>,
+    players: Query<(&Transform, &Player, &CollisionEntity)>,
+) {
+    if match_rules.mode != GameMode::KingOfTheHill {
+        return;
+    }
+
+    let mut holders = players
+        .iter()
+        .filter(|(transform, player, collision_entity)| {
+            alive(player, collision_entity)
+                && hill_rect().contains(transform.translation.truncate())
+        });
+    // The hill only counts while it's occupied by a single plane
+    if let (Some((_, player, _)), None) = (holders.next(), holders.next()) {
+        objective_state.points[player.handle] += 1;
+    }
+}
+
+fn update_flags(
+    match_rules: Res<MatchRules>,
+    mut objective_state: ResMut<ObjectiveState>,
+    players: Query<(&Transform, &Player, &CollisionEntity)>,
+) {
+    if match_rules.mode != GameMode::CaptureTheFlag {
+        return;
+    }
+
+    let state = &mut *objective_state;
+    for (team, flag_carrier) in state.flag_carriers.iter_mut().enumerate() {
+        match *flag_carrier {
+            Some(carrier) => {
+                let carrier = players.iter().find(|(_, player, collision_entity)| {
+                    player.handle == carrier && alive(player, collision_entity)
+                });
+                match carrier {
+                    // The carrier got shot down, the flag goes back to its base
+                    None => *flag_carrier = None,
+                    Some((transform, player, _)) => {
+                        let base = base_position(player.handle);
+                        if transform.translation.truncate().distance(base) < BASE_SIZE.x / 2.0 {
+                            state.points[player.handle] += 1;
+                            *flag_carrier = None;
+                        }
+                    }
+                }
+            }
+            None => {
+                let flag_position = base_position(team);
+                for (transform, player, collision_entity) in &players {
+                    if player.handle == team || !alive(player, collision_entity) {
+                        continue;
+                    }
+                    if transform.translation.truncate().distance(flag_position) < FLAG_PICKUP_RADIUS
+                    {
+                        *flag_carrier = Some(player.handle);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn move_flags(
+    objective_state: Res<ObjectiveState>,
+    mut flags: Query<(&Flag, &mut Transform, &mut DebugTransform), Without<Player>>,
+    players: Query<(&Transform, &Player)>,
+) {
+    for (flag, mut flag_transform, mut debug_transform) in &mut flags {
+        let carrier_position = objective_state.flag_carriers[flag.team].and_then(|carrier| {
+            players
+                .iter()
+                .find(|(_, player)| player.handle == carrier)
+                .map(|(transform, _)| transform.translation + FLAG_CARRY_OFFSET)
+        });
+        let position = carrier_position.unwrap_or(base_position(flag.team).extend(0.0));
+        flag_transform.translation = position.truncate().extend(OBJECTIVE_Z + 1.0);
+        debug_transform.update(&flag_transform);
+    }
+}
+
+fn check_objective_winner(
+    match_rules: Res<MatchRules>,
+    mut objective_state: ResMut<ObjectiveState>,
+    mut next_state: ResMut<NextState<RollbackState>>,
+) {
+    let points_to_win = match match_rules.mode {
+        GameMode::Elimination => return,
+        GameMode::KingOfTheHill => HILL_POINTS_TO_WIN,
+        GameMode::CaptureTheFlag => CAPTURES_TO_WIN,
+    };
+
+    let points = objective_state.points;
+    if points.iter().all(|&points| points < points_to_win) {
+        return;
+    }
+
+    objective_state.winner = if points[0] > points[1] {
+        Some(0)
+    } else if points[1] > points[0] {
+        Some(1)
+    } else {
+        None
+    };
+    next_state.set(RollbackState::RoundEnd);
+}
+
+fn respawn_players(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut effects: ResMut<Assets<EffectAsset>>,
+    stats: Res<PersistentPlayerStats>,
+    perks: Res<Perks>,
+    balance: Res<Balance>,
+    match_rules: Res<MatchRules>,
+    round_frame: Res<RoundFrame>,
+    mut objective_state: ResMut<ObjectiveState>,
+    players: Query<&Player>,
+) {
+    if !match_rules.mode.objective() {
+        return;
+    }
+
+    for (handle, respawn_frame) in objective_state.respawn_frames.iter_mut().enumerate() {
+        if players.iter().any(|player| player.handle == handle) {
+            continue;
+        }
+
+        match *respawn_frame {
+            None => *respawn_frame = Some(round_frame.frame + RESPAWN_FRAMES),
+            Some(frame) if frame <= round_frame.frame => {
+                *respawn_frame = None;
+                spawn_plane(
+                    &mut commands,
+                    &assets,
+                    &mut effects,
+                    &balance,
+                    handle,
+                    perks.stats(handle, &stats),
+                );
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+pub struct WorldObjectivePlugin;
+
+impl Plugin for WorldObjectivePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ObjectiveState>()
+            .add_systems(
+                OnEnter(RollbackState::RoundStart),
+                (reset_objective_state, spawn_objective),
+            )
+            .add_systems(
+                GgrsSchedule,
+                (
+                    update_hill,
+                    update_flags,
+                    move_flags,
+                    check_objective_winner,
+                    respawn_players,
+                )
+                    .chain()
+                    .after(InGameSet::Spawning)
+                    .before(InGameSet::Last)
+                    .after(apply_state_transition::<RollbackState>)
+                    .distributive_run_if(in_state(RollbackState::InRound)),
+            );
+    }
+}
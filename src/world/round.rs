@@ -1,34 +1,54 @@
+use std::hash::{Hash, Hasher};
+
 use bevy::prelude::*;
 use bevy_ggrs::GgrsSchedule;
 
-use crate::player;
+use super::{MatchRules, ObjectiveState};
+use crate::misc::utils::hash_timer;
+use crate::network::ggrs_config::GGRS_FPS;
+use crate::player::perk::Perks;
+use crate::player::{self, InGameSet};
 use crate::RollbackState;
 
 pub const MAX_SCORE: usize = 5;
 
-#[derive(Resource, Reflect, Default, Debug)]
-#[reflect(Resource)]
+#[derive(Resource, Reflect, Default, Debug, Hash)]
+#[reflect(Resource, Hash)]
 pub struct Score {
     pub p1: usize,
     pub p2: usize,
     pub last_winner: Option<usize>,
 }
 
-#[derive(Resource, Reflect, Default, Debug)]
-#[reflect(Resource)]
+#[derive(Resource, Reflect, Default, Debug, Hash)]
+#[reflect(Resource, Hash)]
 pub struct RoundStats {
     pub rounds_played: u64,
 }
 
-#[derive(Resource, Reflect, Default, Debug)]
-#[reflect(Resource)]
+/// Number of rollback frames that were simulated in the current round.
+/// Use this instead of `FrameCount` for anything that has to be deterministic.
+#[derive(Resource, Reflect, Default, Debug, Hash)]
+#[reflect(Resource, Hash)]
+pub struct RoundFrame {
+    pub frame: u32,
+}
+
+impl RoundFrame {
+    pub fn seconds(&self) -> f32 {
+        self.frame as f32 / GGRS_FPS as f32
+    }
+}
+
+#[derive(Resource, Reflect, Default, Debug, Hash)]
+#[reflect(Resource, Hash)]
 pub struct Rematch {
     pub p1: bool,
     pub p2: bool,
 }
 
 #[derive(Resource, Reflect, Deref, DerefMut)]
-#[reflect(Resource)]
+#[reflect(Resource, Hash)]
 pub struct RoundEndTimer(Timer);
 
 impl Default for RoundEndTimer {
@@ -37,10 +57,22 @@ impl Default for RoundEndTimer {
     }
 }
 
+impl Hash for RoundEndTimer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_timer(&self.0, state);
+    }
+}
+
 pub fn round_end_timeout(
     mut timer: ResMut<RoundEndTimer>,
+    perks: Res<Perks>,
     mut next_state: ResMut<NextState<RollbackState>>,
 ) {
+    // Wait for the players to pick their perks
+    if perks.drafting() {
+        return;
+    }
+
     timer.tick(std::time::Duration::from_secs_f32(1.0 / 60.0));
 
     if timer.just_finished() {
@@ -48,19 +80,40 @@ pub fn round_end_timeout(
     }
 }
 
+/// The last plane standing wins, unless the round is played in an objective mode.
+fn round_winner(
+    players: &Query<&player::Player>,
+    match_rules: &MatchRules,
+    objective_state: &ObjectiveState,
+) -> Option<usize> {
+    if match_rules.mode.objective() {
+        return objective_state.winner;
+    }
+
+    match players.get_single() {
+        Ok(player) => Some(player.handle),
+        Err(_) => None,
+    }
+}
+
 pub fn adjust_score(
     players: Query<&player::Player>,
+    match_rules: Res<MatchRules>,
+    objective_state: Res<ObjectiveState>,
     mut score: ResMut<Score>,
     mut round_stats: ResMut<RoundStats>,
     mut next_rollback_state: ResMut<NextState<RollbackState>>,
 ) {
     round_stats.rounds_played += 1;
-    if players.iter().count() == 0 {
-        score.last_winner = None;
-        return;
-    }
-
-    if players.single().handle == 0 {
+    let winner = match round_winner(&players, &match_rules, &objective_state) {
+        Some(winner) => winner,
+        None => {
+            score.last_winner = None;
+            return;
+        }
+    };
+
+    if winner == 0 {
         score.p1 += 1;
         score.last_winner = Some(0);
     } else {
@@ -87,6 +140,14 @@ pub fn check_rematch(
     next_rollback_state.set(RollbackState::RoundStart);
 }
 
+pub fn tick_round_frame(mut round_frame: ResMut<RoundFrame>) {
+    round_frame.frame += 1;
+}
+
+fn reset_round_frame(mut round_frame: ResMut<RoundFrame>) {
+    *round_frame = RoundFrame::default();
+}
+
 fn reset_rematch(mut rematch: ResMut<Rematch>) {
     *rematch = Rematch::default();
 }
@@ -99,7 +160,9 @@ impl Plugin for WorldRoundPlugin {
             .init_resource::<Score>()
             .init_resource::<Rematch>()
             .init_resource::<RoundStats>()
+            .init_resource::<RoundFrame>()
             .add_systems(OnExit(RollbackState::GameOver), reset_rematch)
+            .add_systems(OnEnter(RollbackState::RoundStart), reset_round_frame)
             .add_systems(OnEnter(RollbackState::RoundEnd), adjust_score)
             .add_systems(
                 GgrsSchedule,
@@ -114,6 +177,10 @@ impl Plugin for WorldRoundPlugin {
                         .distributive_run_if(in_state(RollbackState::GameOver))
                         .after(apply_state_transition::<RollbackState>)
                         .after(player::check_rematch_state),
+                    tick_round_frame
+                        .before(InGameSet::Movement)
+                        .distributive_run_if(in_state(RollbackState::InRound))
+                        .after(apply_state_transition::<RollbackState>),
                 ),
             );
     }
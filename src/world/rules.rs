@@ -0,0 +1,422 @@
+use std::fmt::Display;
+
+use bevy::prelude::*;
+
+use crate::misc::Settings;
+use crate::world::map::playlist::{PlaylistMode, MAP_COUNT};
+use crate::GameState;
+
+/// What happens when two planes crash into each other.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum PlaneCollision {
+    /// Planes fly through each other
+    #[default]
+    Disabled,
+    /// Both planes get destroyed
+    Destroy,
+    /// Planes bounce off each other and take damage based on their relative speed
+    Bounce,
+}
+
+impl PlaneCollision {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Disabled => Self::Destroy,
+            Self::Destroy => Self::Bounce,
+            Self::Bounce => Self::Disabled,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Disabled => "disabled",
+            Self::Destroy => "destroy",
+            Self::Bounce => "bounce",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [Self::Disabled, Self::Destroy, Self::Bounce]
+            .into_iter()
+            .find(|collision| collision.name() == name)
+    }
+}
+
+/// What happens to planes, bullets and rockets that leave the arena.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum BorderBehavior {
+    /// Everything that leaves the arena gets destroyed
+    #[default]
+    Death,
+    /// Leaving the arena on one side puts you back in on the opposite side
+    Wrap,
+    /// Planes get pushed back and take damage over time, bullets and rockets get destroyed
+    SoftWall,
+    /// Everything bounces off the border
+    Bounce,
+}
+
+impl BorderBehavior {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Death => Self::Wrap,
+            Self::Wrap => Self::SoftWall,
+            Self::SoftWall => Self::Bounce,
+            Self::Bounce => Self::Death,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Death => "death",
+            Self::Wrap => "wrap",
+            Self::SoftWall => "soft_wall",
+            Self::Bounce => "bounce",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [Self::Death, Self::Wrap, Self::SoftWall, Self::Bounce]
+            .into_iter()
+            .find(|border| border.name() == name)
+    }
+}
+
+/// How a round is won.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum GameMode {
+    /// The last plane standing wins the round
+    #[default]
+    Elimination,
+    /// Hold the hill alone to gain points, planes respawn
+    KingOfTheHill,
+    /// Bring the flag of the opponent back to your base, planes respawn
+    CaptureTheFlag,
+}
+
+impl GameMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Elimination => Self::KingOfTheHill,
+            Self::KingOfTheHill => Self::CaptureTheFlag,
+            Self::CaptureTheFlag => Self::Elimination,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Elimination => "elimination",
+            Self::KingOfTheHill => "king_of_the_hill",
+            Self::CaptureTheFlag => "capture_the_flag",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [Self::Elimination, Self::KingOfTheHill, Self::CaptureTheFlag]
+            .into_iter()
+            .find(|mode| mode.name() == name)
+    }
+
+    /// Objective modes respawn planes and end the round on their own.
+    pub fn objective(&self) -> bool {
+        *self != Self::Elimination
+    }
+}
+
+/// Who gets to pick a perk after each round.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum PerkDraft {
+    #[default]
+    Off,
+    /// Only the loser of the round picks a perk
+    Loser,
+    Both,
+}
+
+impl PerkDraft {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Loser,
+            Self::Loser => Self::Both,
+            Self::Both => Self::Off,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Loser => "loser",
+            Self::Both => "both",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [Self::Off, Self::Loser, Self::Both]
+            .into_iter()
+            .find(|draft| draft.name() == name)
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn on_off_from_str(s: &str) -> Option<bool> {
+    match s {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn maps_to_string(maps: &[usize]) -> String {
+    maps.iter()
+        .map(|map| map.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn maps_from_str(s: &str) -> Option<Vec<usize>> {
+    if s.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let mut maps = Vec::new();
+    for map in s.split(',') {
+        let map = map.parse::<usize>().ok()?;
+        if map >= MAP_COUNT {
+            return None;
+        }
+        if !maps.contains(&map) {
+            maps.push(map);
+        }
+    }
+    Some(maps)
+}
+
+/// The rules one player would like to play with.
+/// Both players send their proposal to each other before the match starts,
+/// the actual rules are then agreed on with `MatchRules::agree`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RulesProposal {
+    pub mode: GameMode,
+    pub perk_draft: PerkDraft,
+    /// Whether the console commands that change the stats of players are allowed
+    pub cheats: bool,
+    pub plane_collision: PlaneCollision,
+    pub border: BorderBehavior,
+    pub playlist: PlaylistMode,
+    pub banned_maps: Vec<usize>,
+    /// Maps the player wants to play on if they get to pick the map
+    pub picked_maps: Vec<usize>,
+}
+
+impl Default for RulesProposal {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::default(),
+            perk_draft: PerkDraft::default(),
+            cheats: true,
+            plane_collision: PlaneCollision::default(),
+            border: BorderBehavior::default(),
+            playlist: PlaylistMode::default(),
+            banned_maps: Vec::new(),
+            picked_maps: Vec::new(),
+        }
+    }
+}
+
+impl Display for RulesProposal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "rules mode={} perk_draft={} cheats={} plane_collision={} border={} playlist={} bans={} picks={}",
+            self.mode.name(),
+            self.perk_draft.name(),
+            on_off(self.cheats),
+            self.plane_collision.name(),
+            self.border.name(),
+            self.playlist.name(),
+            maps_to_string(&self.banned_maps),
+            maps_to_string(&self.picked_maps),
+        )
+    }
+}
+
+impl RulesProposal {
+    pub fn from_str(s: &str) -> Option<RulesProposal> {
+        let mut parts = s.split_whitespace();
+        if parts.next() != Some("rules") {
+            return None;
+        }
+
+        let mut proposal = RulesProposal::default();
+        for part in parts {
+            let (key, value) = part.split_once('=')?;
+            match key {
+                "mode" => proposal.mode = GameMode::from_name(value)?,
+                "perk_draft" => proposal.perk_draft = PerkDraft::from_name(value)?,
+                "cheats" => proposal.cheats = on_off_from_str(value)?,
+                "plane_collision" => proposal.plane_collision = PlaneCollision::from_name(value)?,
+                "border" => proposal.border = BorderBehavior::from_name(value)?,
+                "playlist" => proposal.playlist = PlaylistMode::from_name(value)?,
+                "bans" => proposal.banned_maps = maps_from_str(value)?,
+                "picks" => proposal.picked_maps = maps_from_str(value)?,
+                _ => warn!("unknown match rule '{}', ignoring it", key),
+            }
+        }
+        Some(proposal)
+    }
+
+    /// Banned maps can't be picked and vice versa.
+    pub fn toggle_map(&mut self, map: usize) {
+        if let Some(index) = self.banned_maps.iter().position(|&m| m == map) {
+            self.banned_maps.remove(index);
+            self.picked_maps.push(map);
+        } else if let Some(index) = self.picked_maps.iter().position(|&m| m == map) {
+            self.picked_maps.remove(index);
+        } else {
+            self.banned_maps.push(map);
+        }
+    }
+}
+
+/// The rules of the current match.
+/// Both peers have to play with the same rules, they are merged from the proposals
+/// of both players before the match starts.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct MatchRules {
+    pub mode: GameMode,
+    pub perk_draft: PerkDraft,
+    pub cheats: bool,
+    pub plane_collision: PlaneCollision,
+    pub border: BorderBehavior,
+    pub playlist: PlaylistMode,
+    pub banned_maps: Vec<usize>,
+    /// The picked maps of each player (indexed by handle), without the banned ones
+    pub map_picks: [Vec<usize>; 2],
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        let proposal = RulesProposal::default();
+        Self::agree(&proposal, &proposal)
+    }
+}
+
+impl Display for MatchRules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "mode={} perk_draft={} cheats={} plane_collision={} border={} playlist={} bans={} picks={}|{}",
+            self.mode.name(),
+            self.perk_draft.name(),
+            on_off(self.cheats),
+            self.plane_collision.name(),
+            self.border.name(),
+            self.playlist.name(),
+            maps_to_string(&self.banned_maps),
+            maps_to_string(&self.map_picks[0]),
+            maps_to_string(&self.map_picks[1]),
+        )
+    }
+}
+
+impl MatchRules {
+    /// Merge the proposals of both players, this has to give the same result on both peers.
+    /// The game mode, perk draft, plane collision and border are decided by the player with handle 0,
+    /// the playlist mode only changes if both players voted for it,
+    /// cheats are only allowed if both players allow them
+    /// and the bans of both players are combined.
+    pub fn agree(host: &RulesProposal, guest: &RulesProposal) -> MatchRules {
+        let playlist = if host.playlist == guest.playlist {
+            host.playlist
+        } else {
+            PlaylistMode::default()
+        };
+
+        let banned_maps = playable_bans(
+            (0..MAP_COUNT)
+                .filter(|map| host.banned_maps.contains(map) || guest.banned_maps.contains(map))
+                .collect(),
+        );
+
+        let picks = |proposal: &RulesProposal| -> Vec<usize> {
+            proposal
+                .picked_maps
+                .iter()
+                .copied()
+                .filter(|map| !banned_maps.contains(map))
+                .collect()
+        };
+        let map_picks = [picks(host), picks(guest)];
+
+        MatchRules {
+            mode: host.mode,
+            perk_draft: host.perk_draft,
+            cheats: host.cheats && guest.cheats,
+            plane_collision: host.plane_collision,
+            border: host.border,
+            playlist,
+            banned_maps,
+            map_picks,
+        }
+    }
+
+    /// Parse the rules in the format of `Display`, used by snapshots.
+    pub fn from_str(s: &str) -> Option<MatchRules> {
+        let mut rules = MatchRules::default();
+        for part in s.split_whitespace() {
+            let (key, value) = part.split_once('=')?;
+            match key {
+                "mode" => rules.mode = GameMode::from_name(value)?,
+                "perk_draft" => rules.perk_draft = PerkDraft::from_name(value)?,
+                "cheats" => rules.cheats = on_off_from_str(value)?,
+                "plane_collision" => rules.plane_collision = PlaneCollision::from_name(value)?,
+                "border" => rules.border = BorderBehavior::from_name(value)?,
+                "playlist" => rules.playlist = PlaylistMode::from_name(value)?,
+                "bans" => rules.banned_maps = playable_bans(maps_from_str(value)?),
+                "picks" => {
+                    let (first, second) = value.split_once('|')?;
+                    rules.map_picks = [maps_from_str(first)?, maps_from_str(second)?];
+                }
+                _ => warn!("unknown match rule '{}', ignoring it", key),
+            }
+        }
+        Some(rules)
+    }
+
+    /// All maps that are not banned, sorted by their id.
+    pub fn available_maps(&self) -> Vec<usize> {
+        (0..MAP_COUNT)
+            .filter(|map| !self.banned_maps.contains(map))
+            .collect()
+    }
+}
+
+/// We need at least one map to play on, ignore the bans if they cover all maps.
+fn playable_bans(banned_maps: Vec<usize>) -> Vec<usize> {
+    if banned_maps.len() == MAP_COUNT {
+        warn!("all maps are banned, ignoring the bans");
+        return Vec::new();
+    }
+    banned_maps
+}
+
+fn init_match_rules(mut match_rules: ResMut<MatchRules>, settings: Res<Settings>) {
+    // We don't know the proposal of our peer yet, use our own until we receive it
+    *match_rules = MatchRules::agree(&settings.rules, &settings.rules);
+}
+
+pub struct WorldRulesPlugin;
+
+impl Plugin for WorldRulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MatchRules>()
+            .add_systems(OnEnter(GameState::Matchmaking), init_match_rules);
+    }
+}